@@ -46,6 +46,7 @@ const auths_api = (authServerUrl: string) =>
 const tr_api = (transcryptorUrl: string) =>
 	new Api(transcryptorUrl, {
 		encryptedHubPseudonymPackage: '.ph/ehpp',
+		continueEhpp: '.ph/ehpp/continue',
 	});
 
 export { api, auths_api, hub_api, phc_api, tr_api };
@@ -116,6 +116,8 @@ export default class PHCServer {
 			return { entered: false, errorMessage: { key: 'errors.general_error' }, enterResp: null };
 		} else if (enterResp === 'RetryWithNewIdentifyingAttr') {
 			return { entered: false, errorMessage: { key: 'errors.retry_with_new_attr' }, enterResp: null };
+		} else if ('CardRevoked' in enterResp) {
+			return { entered: false, errorMessage: { key: 'errors.card_revoked' }, enterResp: null };
 		} else if ('AttributeBanned' in enterResp) {
 			return { entered: false, errorMessage: { key: 'errors.attribute_banned', values: [enterResp.AttributeBanned.value] }, enterResp: null };
 		} else if ('AttributeAlreadyTaken' in enterResp && enterResp.AttributeAlreadyTaken.not_identifying) {
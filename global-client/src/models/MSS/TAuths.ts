@@ -20,6 +20,9 @@ export type AuthsWelcomeResp = Result<WelcomeResp, ErrorCode>;
 
 export enum Source {
 	Yivi = 'Yivi',
+	Email = 'Email',
+	Oidc = 'Oidc',
+	WebAuthn = 'WebAuthn',
 }
 
 export type LoginMethod = {
@@ -41,18 +44,31 @@ export const loginMethods: Record<string, LoginMethod> = {
 } as const;
 
 export type AuthStartReq = {
-	source: Source;
+	// exactly one of source and sources must be set
+	source?: Source;
+	sources?: Source[];
 	attr_types: readonly string[];
 	attr_type_choices: string[][];
+	optional_attr_type_choices?: string[][];
 	yivi_chained_session: boolean;
 	yivi_chained_session_drip: boolean;
+	email?: string;
 };
 
 type AuthTask = {
-	Yivi: {
+	Yivi?: {
 		disclosure_request: string;
 		yivi_requestor_url: string;
 	};
+	Email?: Record<string, never>;
+	Oidc?: {
+		authorization_url: string;
+	};
+	WebAuthn?: {
+		challenge: string;
+		rp_id: string;
+		user_verification: boolean;
+	};
 };
 export type YiviWaitForResultResp = { Success: { disclosure: string } } | 'PleaseRestartAuth' | 'SessionGone';
 
@@ -60,31 +76,58 @@ export type YiviReleaseNextSessionResp = { Success: Record<string, never> } | 'P
 
 export type YiviReleaseNextSessionReq = { state: number[]; next_session?: string; stale_after?: number };
 
+export type YiviSignReq = { message: string; attr_type_choices: string[][] };
+
+export type YiviSignResp = { Success: { signature_request: string; yivi_requestor_url: string } } | { UnknownAttrType: string } | { SourceNotAvailableFor: string } | { Conflict: [string, string] };
+
 export type CardResp = { Success: CardRespSuccess } | 'PleaseRetryWithNewCardPseud';
 
 export type CardRespSuccess = { attr: string; issuance_request: string; yivi_requestor_url: string };
 
-export type CardReq = { card_pseud_package: { card_pseud: number; registration_date?: number }; comment: string };
+export type CardReq = { card_pseud_package: { card_pseud: number; registration_date?: number; revocation_nonce?: string }; comment: string };
 
 export type StartResp =
-	{ Success: StartRespSuccess } | { UnknownAttrType: string } | { SourceNotAvailableFor: string } | 'ChainedSessionsTemporarilyUnavailable';
+	{ Success: StartRespSuccess } | { UnknownAttrType: string } | { SourceNotAvailableFor: string } | 'ChainedSessionsTemporarilyUnavailable' | 'InvalidEmailAddress' | 'TooManyCodesRequested';
 
 export type StartRespSuccess = { task: AuthTask; state: Array<number> };
 
 export type AuthStartResp = Result<StartResp, ErrorCode>;
 
-export type AuthProof = {
-	Yivi: {
-		disclosure: string;
-	};
-};
+export type AuthProof =
+	| {
+			Yivi: {
+				disclosure: string;
+			};
+	  }
+	| {
+			Email: {
+				code: string;
+			};
+	  }
+	| {
+			Oidc: {
+				code: string;
+				state: string;
+			};
+	  }
+	| {
+			WebAuthn: {
+				public_key: WebAuthnPublicKey;
+				authenticator_data: string;
+				client_data_json: string;
+				signature: string;
+			};
+	  };
+
+// DER encoded SubjectPublicKeyInfo, unpadded url-safe base64
+export type WebAuthnPublicKey = { ES256: string } | { EdDSA: string };
 
 export type AuthCompleteReq = {
 	proof: AuthProof;
 	state: Array<number>;
 };
 
-export type CompleteResp = { Success: { attrs: Record<string, string> } } | 'PleaseRestartAuth';
+export type CompleteResp = { Success: { attrs: Record<string, string> } } | 'PleaseRestartAuth' | 'IncorrectCode';
 
 export type SignedIdentifyingAttrs = Record<string, { signedAttr: string; id: string; value: string }>;
 
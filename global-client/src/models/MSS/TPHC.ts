@@ -1,4 +1,5 @@
 import { type ErrorCode, type Result } from '@global-client/models/MSS/TGeneral';
+import { type Source } from '@global-client/models/MSS/TAuths';
 
 export type Constellation = {
 	id: string;
@@ -7,6 +8,7 @@ export type Constellation = {
 	transcryptor_jwt_key: string;
 	transcryptor_enc_key: string;
 	transcryptor_master_enc_key_part: string;
+	co_transcryptors?: { url: string }[];
 	phc_url: string;
 	phc_jwt_key: string;
 	phc_enc_key: string;
@@ -52,6 +54,8 @@ export type Attr = {
 	bannable: boolean;
 	not_identifying: boolean;
 	not_addable: boolean;
+	source?: Source;
+	unnormalized_value?: string;
 };
 
 export enum AuthTokenDeniedReason {
@@ -74,6 +78,7 @@ export type EnterResp =
 	| 'AccountDoesNotExist'
 	| { AttributeBanned: Attr }
 	| 'Banned'
+	| { CardRevoked: Attr }
 	| { AttributeAlreadyTaken: Attr }
 	| 'NoBannableAttribute'
 	| 'RetryWithNewIdentifyingAttr'
@@ -92,7 +97,7 @@ export type EnterResp =
 
 export type CardPseudResp = { RetryWithNewAuthToken: string; Success: CardPseudRespSucces };
 
-export type CardPseudRespSucces = { card_pseud: number; registration_date?: number };
+export type CardPseudRespSucces = { card_pseud: number; registration_date?: number; revocation_nonce?: string };
 
 export type PHCEnterResp = Result<EnterResp, ErrorCode>;
 
@@ -106,10 +111,24 @@ export type UserObjectDetails = {
 	size: number;
 };
 
+export type ObjectHistory = {
+	keep: number;
+	versions: UserObjectDetails[];
+};
+
+export type AttrDetails = {
+	attr_type: string | null;
+	allows_login: boolean;
+	bans: boolean;
+	added_at: number | null;
+};
+
 type UserState = {
 	allow_login_by: string[];
 	could_be_banned_by: string[];
+	attrs: Record<string, AttrDetails>;
 	stored_objects: Record<string, UserObjectDetails>;
+	object_history?: Record<string, ObjectHistory>;
 };
 
 export type StateResp = 'RetryWithNewAuthToken' | { State: UserState };
@@ -7,6 +7,16 @@ export type EhpppReq = {
 	hub_mac_key?: string;
 };
 
-export type EhppResp = 'RetryWithNewPpp' | { Success: string };
+// Sealed for the next co-transcryptor; opaque to the global client.
+export type SealedPartialEhpp = {
+	ss_encap: { ml: string; ec: string };
+	sealed: string;
+};
+
+export type EhppResp = 'RetryWithNewPpp' | 'HubSuspended' | { Success: string } | { Continue: SealedPartialEhpp };
+
+export type ContinueEhppReq = {
+	partial_ehpp: SealedPartialEhpp;
+};
 
 export type TrEhppResp = Result<EhppResp, ErrorCode>;
@@ -5,13 +5,15 @@ import { handleErrors, requestOptions } from '@global-client/logic/utils/mssUtil
 import { type Api } from '@hub-client/logic/core/apiCore';
 
 // Models
-import { type EhppResp, type EhpppReq, type TrEhppResp } from '@global-client/models/MSS/TTranscryptor';
+import { type ContinueEhppReq, type EhppResp, type EhpppReq, type TrEhppResp } from '@global-client/models/MSS/TTranscryptor';
 
 export default class Transcryptor {
 	private readonly _trApi: Api;
+	private readonly _coTrApis: Api[];
 
-	constructor(transcryptorUrl: string) {
+	constructor(transcryptorUrl: string, coTranscryptorUrls: string[] = []) {
 		this._trApi = tr_api(transcryptorUrl);
+		this._coTrApis = coTranscryptorUrls.map((url) => tr_api(url));
 	}
 
 	async ehppEP({ nonce, id, ppp, hubMacKey }: { nonce: string; id: string; ppp: string; hubMacKey?: string }) {
@@ -21,11 +23,23 @@ export default class Transcryptor {
 			ppp,
 			hub_mac_key: hubMacKey,
 		};
-		const okEhppResp = await handleErrors<EhppResp>(() =>
+		let okEhppResp = await handleErrors<EhppResp>(() =>
 			this._trApi.api<TrEhppResp>(this._trApi.apiURLS.encryptedHubPseudonymPackage, requestOptions<EhpppReq>(requestPayload)),
 		);
+		// When the transcryptor is split, pass the request along the co-transcryptors.
+		for (const coTrApi of this._coTrApis) {
+			if (typeof okEhppResp !== 'object' || !('Continue' in okEhppResp)) {
+				break;
+			}
+			const continuePayload: ContinueEhppReq = { partial_ehpp: okEhppResp.Continue };
+			okEhppResp = await handleErrors<EhppResp>(() =>
+				coTrApi.api<TrEhppResp>(coTrApi.apiURLS.continueEhpp, requestOptions<ContinueEhppReq>(continuePayload)),
+			);
+		}
 		if (okEhppResp === 'RetryWithNewPpp') {
 			return okEhppResp;
+		} else if (okEhppResp === 'HubSuspended') {
+			throw new Error('This hub has been suspended.');
 		} else if ('Success' in okEhppResp) {
 			return okEhppResp.Success;
 		} else {
@@ -251,7 +251,10 @@ const useMSS = defineStore('mss', {
 			}
 			assert.isNotNull(this.constellation);
 			this._authServer = new AuthenticationServer(filters.removeTrailingSlash(this.constellation.auths_url));
-			this._transcryptor = new Transcryptor(filters.removeTrailingSlash(this.constellation.transcryptor_url));
+			this._transcryptor = new Transcryptor(
+				filters.removeTrailingSlash(this.constellation.transcryptor_url),
+				(this.constellation.co_transcryptors ?? []).map((ct) => filters.removeTrailingSlash(ct.url)),
+			);
 		},
 
 		async getAuthServer() {
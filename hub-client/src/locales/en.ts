@@ -55,6 +55,7 @@ const en = {
 			'A PubHubs account with these attributes does not exist. Try to register a new account with these attributes or try to login with different attributes.',
 		attribute_banned: 'The following attribute is banned and therefore cannot be used to register a new PubHubs account: {0}.',
 		banned: 'This account is banned and can no longer be used to login to PubHubs.',
+		card_revoked: 'This PubHubs card has been revoked and can no longer be used to login to PubHubs.',
 		attribute_already_taken: 'The following attribute is already tied to a PubHubs account: {0}. Try logging in.',
 		general_error:
 			'Unfortunately an error occured. Try again and <a href="https://www.pubhubs.net/en/help/contact/" target="_blank" class="text-accent-primary">contact the developers</a> if the problem persists.',
@@ -54,6 +54,7 @@ const nl = {
 			'Er bestaat geen PubHubs account met deze attributen. Probeer een nieuw account te registreren met deze attributen of probeer in te loggen met andere attributen.',
 		attribute_banned: 'Het volgende attribuut is verbannen en kan daarom niet worden gebruikt om een nieuw PubHubs account te registreren: {0}.',
 		banned: 'Dit account is verbannen en kan niet langer worden gebruikt om in te loggen bij PubHubs.',
+		card_revoked: 'Deze PubHubs-kaart is ingetrokken en kan niet langer worden gebruikt om in te loggen bij PubHubs.',
 		attribute_already_taken: 'Het volgende attribuut is al gekoppeld aan een PubHubs account: {0}. Probeer in te loggen.',
 		general_error:
 			'Helaas is er een fout opgetreden. Probeer het opnieuw en <a href="https://www.pubhubs.net/nl/help/contact/" target="_blank" class="text-accent-primary">neem contact op met de ontwikkelaars</a> als het probleem blijft bestaan.',
@@ -83,3 +83,667 @@ pub enum InfoResp {
         config: Box<crate::servers::Config>,
     },
 }
+
+/// Bans an [`Attr`]ibute at PubHubs Central, and all users that provided this attribute as
+/// bannable attribute (see [`AttrState::bans_users`]).
+///
+/// The request is verified using the [crate::servers::config::ServerConfig::admin_key].
+///
+/// Only available at PubHubs Central.
+///
+/// [`Attr`]: crate::attr::Attr
+/// [`AttrState::bans_users`]: crate::attr::AttrState::bans_users
+pub struct BanEP {}
+impl EndpointDetails for BanEP {
+    type RequestType = Signed<BanReq>;
+    type ResponseType = Result<BanResp>;
+
+    const METHOD: http::Method = http::Method::POST;
+    const PATH: &'static str = ".ph/admin/ban";
+}
+
+/// Request type for [`BanEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct BanReq {
+    /// Handle of the [`crate::attr::Type`] of the attribute to ban, e.g. `email`.
+    pub attr_type: crate::handle::Handle,
+
+    /// Value of the attribute to ban, e.g. `user@example.com`.
+    pub value: String,
+
+    /// Why the attribute is banned, recorded in the attribute's [`BanRecord`]s.
+    pub reason: String,
+}
+
+having_message_code!(BanReq, AdminBanReq);
+
+/// Response type for [`BanEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+#[must_use]
+pub enum BanResp {
+    /// Signature on request was expired; retry with a fresh one
+    ResignRequest,
+
+    /// Admin key is invalid
+    InvalidAdminKey,
+
+    /// The authentication server knows no attribute type with this handle
+    UnknownAttrType,
+
+    /// The attribute is now banned, and so are the listed users.
+    ///
+    /// Also returned when the attribute was already banned.
+    Success {
+        /// The users banned by this attribute
+        banned_users: Vec<crate::id::Id>,
+    },
+}
+
+/// Lifts the ban on an [`Attr`]ibute at PubHubs Central, see [`BanEP`].
+///
+/// Each user that provided this attribute as bannable attribute is unbanned too,
+/// unless another of their bannable attributes is still banned.
+///
+/// The request is verified using the [crate::servers::config::ServerConfig::admin_key].
+///
+/// Only available at PubHubs Central.
+///
+/// [`Attr`]: crate::attr::Attr
+pub struct UnbanEP {}
+impl EndpointDetails for UnbanEP {
+    type RequestType = Signed<UnbanReq>;
+    type ResponseType = Result<UnbanResp>;
+
+    const METHOD: http::Method = http::Method::POST;
+    const PATH: &'static str = ".ph/admin/unban";
+}
+
+/// Request type for [`UnbanEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct UnbanReq {
+    /// Handle of the [`crate::attr::Type`] of the attribute to unban, e.g. `email`.
+    pub attr_type: crate::handle::Handle,
+
+    /// Value of the attribute to unban, e.g. `user@example.com`.
+    pub value: String,
+
+    /// Why the ban is lifted, recorded in the attribute's [`BanRecord`]s.
+    pub reason: String,
+}
+
+having_message_code!(UnbanReq, AdminUnbanReq);
+
+/// Response type for [`UnbanEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+#[must_use]
+pub enum UnbanResp {
+    /// Signature on request was expired; retry with a fresh one
+    ResignRequest,
+
+    /// Admin key is invalid
+    InvalidAdminKey,
+
+    /// The authentication server knows no attribute type with this handle
+    UnknownAttrType,
+
+    /// The attribute is no longer banned.
+    ///
+    /// Also returned when the attribute was not banned to begin with.
+    Success {
+        /// The users that are no longer banned
+        unbanned_users: Vec<crate::id::Id>,
+
+        /// The users that remain banned by one of their other bannable attributes
+        still_banned_users: Vec<crate::id::Id>,
+    },
+}
+
+/// Retrieves the [`BanRecord`]s of an attribute, or of all bannable attributes of a user.
+///
+/// The request is verified using the [crate::servers::config::ServerConfig::admin_key].
+///
+/// Only available at PubHubs Central.
+pub struct BanLogEP {}
+impl EndpointDetails for BanLogEP {
+    type RequestType = Signed<BanLogReq>;
+    type ResponseType = Result<BanLogResp>;
+
+    const METHOD: http::Method = http::Method::POST;
+    const PATH: &'static str = ".ph/admin/ban-log";
+}
+
+/// Request type for [`BanLogEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "snake_case")]
+pub enum BanLogReq {
+    /// The records of the attribute with the given type and value
+    Attr {
+        attr_type: crate::handle::Handle,
+        value: String,
+    },
+
+    /// The records of all attributes that could ban the user with this id
+    User(crate::id::Id),
+}
+
+having_message_code!(BanLogReq, AdminBanLogReq);
+
+/// Response type for [`BanLogEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+#[must_use]
+pub enum BanLogResp {
+    /// Signature on request was expired; retry with a fresh one
+    ResignRequest,
+
+    /// Admin key is invalid
+    InvalidAdminKey,
+
+    /// The authentication server knows no attribute type with this handle
+    UnknownAttrType,
+
+    /// No user with this id exists
+    UnknownUser,
+
+    /// The ban records of the requested attribute(s) by attribute id, oldest first.
+    Success {
+        records: std::collections::HashMap<crate::id::Id, Vec<BanRecord>>,
+    },
+}
+
+/// Entry in the append-only log PubHubs Central keeps of the (un)bans of an attribute.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct BanRecord {
+    /// Whether the attribute was banned or unbanned
+    pub action: BanAction,
+
+    /// When the action was taken
+    pub at: NumericDate,
+
+    /// The reason given by the admin
+    pub reason: String,
+
+    /// Identifies the admin key used to sign the request, see [`crate::phcrypto::admin_key_id`].
+    pub admin_key_id: crate::id::Id,
+
+    /// Identifies the signed request that caused this record, see [`Signed::id`], so that
+    /// retrying the request does not record it twice.  Not set on records made before this field
+    /// was introduced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<crate::id::Id>,
+}
+
+/// Type of [`BanRecord::action`]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BanAction {
+    Ban,
+    Unban,
+}
+
+/// Adds a hub to the hub registry of PubHubs Central.
+///
+/// The request is verified using the [crate::servers::config::ServerConfig::admin_key].
+///
+/// Only available at PubHubs Central.
+pub struct AddHubEP {}
+impl EndpointDetails for AddHubEP {
+    type RequestType = Signed<AddHubReq>;
+    type ResponseType = Result<AddHubResp>;
+
+    const METHOD: http::Method = http::Method::POST;
+    const PATH: &'static str = ".ph/admin/hubs/add";
+}
+
+/// Request type for [`AddHubEP`], see [`crate::hub::BasicInfo`] for the meaning of the fields.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct AddHubReq {
+    pub handles: crate::handle::Handles,
+    pub name: String,
+    pub description: String,
+
+    /// Must end with a `/`.
+    pub url: url::Url,
+
+    /// Randomly generated when not set
+    #[serde(default)]
+    pub id: Option<crate::id::Id>,
+}
+
+having_message_code!(AddHubReq, AdminAddHubReq);
+
+/// Response type for [`AddHubEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+#[must_use]
+pub enum AddHubResp {
+    /// Signature on request was expired; retry with a fresh one
+    ResignRequest,
+
+    /// Admin key is invalid
+    InvalidAdminKey,
+
+    /// This handle is already used by another, possibly retired, hub
+    HandleTaken(crate::handle::Handle),
+
+    /// This id is already used by another, possibly retired, hub
+    IdTaken,
+
+    /// The url does not end with a `/`
+    InvalidUrl,
+
+    /// The hub was added
+    Success(crate::hub::BasicInfo),
+}
+
+/// Changes the details of a hub in the hub registry of PubHubs Central.
+///
+/// In accordance with [`crate::hub::BasicInfo`], handles can only be added and the id can not be
+/// changed at all.
+///
+/// The request is verified using the [crate::servers::config::ServerConfig::admin_key].
+///
+/// Only available at PubHubs Central.
+pub struct UpdateHubEP {}
+impl EndpointDetails for UpdateHubEP {
+    type RequestType = Signed<UpdateHubReq>;
+    type ResponseType = Result<UpdateHubResp>;
+
+    const METHOD: http::Method = http::Method::POST;
+    const PATH: &'static str = ".ph/admin/hubs/update";
+}
+
+/// Request type for [`UpdateHubEP`].  Fields that are not set are left unchanged.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateHubReq {
+    /// One of the handles of the hub to update
+    pub hub: crate::handle::Handle,
+
+    #[serde(default)]
+    pub name: Option<String>,
+
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// Must end with a `/`.
+    #[serde(default)]
+    pub url: Option<url::Url>,
+
+    /// Handles to append to the hub's handles; those the hub already has are ignored.
+    #[serde(default)]
+    pub add_handles: Vec<crate::handle::Handle>,
+}
+
+having_message_code!(UpdateHubReq, AdminUpdateHubReq);
+
+/// Response type for [`UpdateHubEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+#[must_use]
+pub enum UpdateHubResp {
+    /// Signature on request was expired; retry with a fresh one
+    ResignRequest,
+
+    /// Admin key is invalid
+    InvalidAdminKey,
+
+    /// There is no (unretired) hub with this handle
+    UnknownHub,
+
+    /// This handle is already used by another, possibly retired, hub
+    HandleTaken(crate::handle::Handle),
+
+    /// The url does not end with a `/`
+    InvalidUrl,
+
+    /// The hub was updated, and now has these details
+    Success(crate::hub::BasicInfo),
+}
+
+/// Retires a hub from the hub registry of PubHubs Central, so that it is no longer offered to
+/// users and can no longer obtain tickets.
+///
+/// The handles and id of a retired hub remain reserved, and can not be reused by another hub.
+///
+/// The request is verified using the [crate::servers::config::ServerConfig::admin_key].
+///
+/// Only available at PubHubs Central.
+pub struct RetireHubEP {}
+impl EndpointDetails for RetireHubEP {
+    type RequestType = Signed<RetireHubReq>;
+    type ResponseType = Result<RetireHubResp>;
+
+    const METHOD: http::Method = http::Method::POST;
+    const PATH: &'static str = ".ph/admin/hubs/retire";
+}
+
+/// Request type for [`RetireHubEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RetireHubReq {
+    /// One of the handles of the hub to retire
+    pub hub: crate::handle::Handle,
+}
+
+having_message_code!(RetireHubReq, AdminRetireHubReq);
+
+/// Response type for [`RetireHubEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+#[must_use]
+pub enum RetireHubResp {
+    /// Signature on request was expired; retry with a fresh one
+    ResignRequest,
+
+    /// Admin key is invalid
+    InvalidAdminKey,
+
+    /// There is no (unretired) hub with this handle
+    UnknownHub,
+
+    /// The hub is retired
+    Success,
+}
+
+/// Lists the hubs that asked to be registered via [`crate::api::phc::hub::OnboardEP`], and await
+/// approval.
+///
+/// The request is verified using the [crate::servers::config::ServerConfig::admin_key].
+///
+/// Only available at PubHubs Central.
+pub struct PendingHubsEP {}
+impl EndpointDetails for PendingHubsEP {
+    type RequestType = Signed<PendingHubsReq>;
+    type ResponseType = Result<PendingHubsResp>;
+
+    const METHOD: http::Method = http::Method::POST;
+    const PATH: &'static str = ".ph/admin/hubs/pending";
+}
+
+/// Request type for [`PendingHubsEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PendingHubsReq {}
+
+having_message_code!(PendingHubsReq, AdminPendingHubsReq);
+
+/// Response type for [`PendingHubsEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+#[must_use]
+pub enum PendingHubsResp {
+    /// Signature on request was expired; retry with a fresh one
+    ResignRequest,
+
+    /// Admin key is invalid
+    InvalidAdminKey,
+
+    /// The pending hubs, oldest request first
+    Success { pending: Vec<PendingHub> },
+}
+
+/// A hub that asked to be registered via [`crate::api::phc::hub::OnboardEP`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct PendingHub {
+    /// The details the hub will be registered with when approved; the `id` is assigned by
+    /// PubHubs Central.
+    pub info: crate::hub::BasicInfo,
+
+    /// The key the hub signed its request with
+    pub verifying_key: VerifyingKeyBytes,
+
+    /// When the (latest) request was made
+    pub requested_at: NumericDate,
+}
+
+/// Approves a hub's request to be registered, see [`crate::api::phc::hub::OnboardEP`].
+///
+/// The request is verified using the [crate::servers::config::ServerConfig::admin_key].
+///
+/// Only available at PubHubs Central.
+pub struct ApproveHubEP {}
+impl EndpointDetails for ApproveHubEP {
+    type RequestType = Signed<ApproveHubReq>;
+    type ResponseType = Result<ApproveHubResp>;
+
+    const METHOD: http::Method = http::Method::POST;
+    const PATH: &'static str = ".ph/admin/hubs/approve";
+}
+
+/// Request type for [`ApproveHubEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ApproveHubReq {
+    /// The handle requested by the pending hub
+    pub handle: crate::handle::Handle,
+}
+
+having_message_code!(ApproveHubReq, AdminApproveHubReq);
+
+/// Response type for [`ApproveHubEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+#[must_use]
+pub enum ApproveHubResp {
+    /// Signature on request was expired; retry with a fresh one
+    ResignRequest,
+
+    /// Admin key is invalid
+    InvalidAdminKey,
+
+    /// No hub with this handle awaits approval
+    UnknownPendingHub,
+
+    /// The handle has been taken by another hub in the meantime, for example via [`AddHubEP`].
+    /// Reject the request instead.
+    HandleTaken,
+
+    /// The hub is registered
+    Success(crate::hub::BasicInfo),
+}
+
+/// Rejects a hub's request to be registered, see [`crate::api::phc::hub::OnboardEP`].
+///
+/// The request is verified using the [crate::servers::config::ServerConfig::admin_key].
+///
+/// Only available at PubHubs Central.
+pub struct RejectHubEP {}
+impl EndpointDetails for RejectHubEP {
+    type RequestType = Signed<RejectHubReq>;
+    type ResponseType = Result<RejectHubResp>;
+
+    const METHOD: http::Method = http::Method::POST;
+    const PATH: &'static str = ".ph/admin/hubs/reject";
+}
+
+/// Request type for [`RejectHubEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RejectHubReq {
+    /// The handle requested by the pending hub
+    pub handle: crate::handle::Handle,
+}
+
+having_message_code!(RejectHubReq, AdminRejectHubReq);
+
+/// Response type for [`RejectHubEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+#[must_use]
+pub enum RejectHubResp {
+    /// Signature on request was expired; retry with a fresh one
+    ResignRequest,
+
+    /// Admin key is invalid
+    InvalidAdminKey,
+
+    /// No hub with this handle awaits approval
+    UnknownPendingHub,
+
+    /// The request is removed from the queue
+    Success,
+}
+
+/// Suspends a hub: it can no longer obtain tickets (see [`crate::api::phc::hub::TicketEP`]), its
+/// unexpired tickets are revoked (see [`crate::api::phc::hub::RevocationsEP`]), and the
+/// transcryptor no longer produces pseudonyms for it.
+///
+/// The request is verified using the [crate::servers::config::ServerConfig::admin_key].
+///
+/// Only available at PubHubs Central.
+pub struct SuspendHubEP {}
+impl EndpointDetails for SuspendHubEP {
+    type RequestType = Signed<SuspendHubReq>;
+    type ResponseType = Result<SuspendHubResp>;
+
+    const METHOD: http::Method = http::Method::POST;
+    const PATH: &'static str = ".ph/admin/hubs/suspend";
+}
+
+/// Request type for [`SuspendHubEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SuspendHubReq {
+    /// One of the handles of the hub to suspend
+    pub hub: crate::handle::Handle,
+}
+
+having_message_code!(SuspendHubReq, AdminSuspendHubReq);
+
+/// Response type for [`SuspendHubEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+#[must_use]
+pub enum SuspendHubResp {
+    /// Signature on request was expired; retry with a fresh one
+    ResignRequest,
+
+    /// Admin key is invalid
+    InvalidAdminKey,
+
+    /// There is no (unretired) hub with this handle
+    UnknownHub,
+
+    /// The hub is suspended.  Also returned when the hub was already suspended.
+    Success {
+        /// The number of unexpired tickets of the hub that were revoked
+        revoked_tickets: usize,
+    },
+}
+
+/// Lifts the suspension of a hub, see [`SuspendHubEP`].  Tickets that were revoked remain so.
+///
+/// The request is verified using the [crate::servers::config::ServerConfig::admin_key].
+///
+/// Only available at PubHubs Central.
+pub struct UnsuspendHubEP {}
+impl EndpointDetails for UnsuspendHubEP {
+    type RequestType = Signed<UnsuspendHubReq>;
+    type ResponseType = Result<UnsuspendHubResp>;
+
+    const METHOD: http::Method = http::Method::POST;
+    const PATH: &'static str = ".ph/admin/hubs/unsuspend";
+}
+
+/// Request type for [`UnsuspendHubEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct UnsuspendHubReq {
+    /// One of the handles of the hub to unsuspend
+    pub hub: crate::handle::Handle,
+}
+
+having_message_code!(UnsuspendHubReq, AdminUnsuspendHubReq);
+
+/// Response type for [`UnsuspendHubEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+#[must_use]
+pub enum UnsuspendHubResp {
+    /// Signature on request was expired; retry with a fresh one
+    ResignRequest,
+
+    /// Admin key is invalid
+    InvalidAdminKey,
+
+    /// There is no (unretired) hub with this handle
+    UnknownHub,
+
+    /// The hub is no longer suspended.  Also returned when the hub was not suspended.
+    Success,
+}
+
+/// Revokes the PubHubs cards issued to a banned user: PubHubs Central no longer accepts them
+/// to log in (see [`crate::api::phc::user::EnterResp::CardRevoked`]), and, when the cards
+/// were issued with a revocation key, the authentication server has the yivi server revoke
+/// them too (see [`crate::api::auths::CardRevocationEP`]).  New cards issued to the user get
+/// a new registration pseudonym.
+///
+/// The cards issued to the accounts merged into the user's account (see
+/// [`crate::api::phc::user::MergeEP`]) are revoked too.
+///
+/// The request is verified using the [crate::servers::config::ServerConfig::admin_key].
+///
+/// Only available at PubHubs Central.
+pub struct RevokeCardsEP {}
+impl EndpointDetails for RevokeCardsEP {
+    type RequestType = Signed<RevokeCardsReq>;
+    type ResponseType = Result<RevokeCardsResp>;
+
+    const METHOD: http::Method = http::Method::POST;
+    const PATH: &'static str = ".ph/admin/revoke-cards";
+}
+
+/// Request type for [`RevokeCardsEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RevokeCardsReq {
+    /// The user whose cards to revoke, as returned by [`BanResp::Success::banned_users`]
+    pub user: crate::id::Id,
+}
+
+having_message_code!(RevokeCardsReq, AdminRevokeCardsReq);
+
+/// Response type for [`RevokeCardsEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+#[must_use]
+pub enum RevokeCardsResp {
+    /// Signature on request was expired; retry with a fresh one
+    ResignRequest,
+
+    /// Admin key is invalid
+    InvalidAdminKey,
+
+    /// There is no user with this id
+    UnknownUser,
+
+    /// Only the cards of banned users can be revoked
+    UserNotBanned,
+
+    /// The cards are revoked
+    Success {
+        /// The registration pseudonym on the revoked cards
+        card_pseud: crate::api::phc::user::CardPseud,
+
+        /// The registration pseudonyms on the revoked cards of the accounts merged into the
+        /// user's account
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        merged_card_pseuds: Vec<crate::api::phc::user::CardPseud>,
+
+        /// Whether all these cards were revoked at the yivi server too, which is only possible
+        /// when they were issued with a revocation key
+        /// (see [`crate::servers::auths::card::CardConfig::revocation`]).
+        revoked_at_yivi: bool,
+    },
+}
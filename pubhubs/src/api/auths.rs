@@ -67,8 +67,23 @@ impl EndpointDetails for AuthStartEP {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct AuthStartReq {
-    /// Which source to use (e.g. yivi)
-    pub source: attr::Source,
+    /// Which source to use (e.g. yivi).
+    ///
+    /// Exactly one of `source` and [`Self::sources`] must be set.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<attr::Source>,
+
+    /// Let the authentication server pick any of these sources.  It picks the source that can
+    /// provide all requested attribute types and that appears earliest in the
+    /// [`attr::Type::sources`] of those types, where ties are broken by the order of this list.
+    /// Which source was picked can be seen from the returned [`AuthTask`].
+    ///
+    /// [`attr::Source::Email`] is only considered when [`Self::email`] is set, and only
+    /// [`attr::Source::Yivi`] when [`Self::yivi_chained_session`] is set.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<attr::Source>,
 
     /// List of requested attributes.
     ///
@@ -92,7 +107,14 @@ pub struct AuthStartReq {
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub attr_type_choices: Vec<Vec<handle::Handle>>,
 
-    /// Only when [`Self::source`] is `attr::Source::Yivi` can this flag be set.
+    /// Attribute types the user may, but need not, disclose, given as alternatives like
+    /// [`AuthStartReq::attr_type_choices`].  Only supported by [`attr::Source::Yivi`], where each
+    /// of these becomes a 'disjunction' with an empty option in the disclosure request.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub optional_attr_type_choices: Vec<Vec<handle::Handle>>,
+
+    /// Only when the source is `attr::Source::Yivi` can this flag be set.
     /// It makes the [`AuthTask::Yivi::disclosure_request`]  instruct the yivi server to use
     /// [`YIVI_NEXT_SESSION_PATH`] as next `nextSession` url,
     /// see [yivi documentaton](https://docs.yivi.app/chained-sessions/),
@@ -120,6 +142,13 @@ pub struct AuthStartReq {
     #[serde(default)]
     #[serde(skip_serializing_if = "std::ops::Not::not")]
     pub yivi_chained_session_drip: bool,
+
+    /// The email address to send a one-time code to.  Must be set if and only if [`Self::source`]
+    /// is [`attr::Source::Email`] or [`Self::sources`] contains it.  When the email source is used,
+    /// only one attribute type (choice) can be requested.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
 }
 
 /// Response to [`AuthStartEP`]
@@ -140,7 +169,8 @@ pub enum AuthStartResp {
     /// No attribute type known with this handle
     UnknownAttrType(handle::Handle),
 
-    /// The [`AuthStartReq::source`] is not available for the attribute type with this handle
+    /// The [`AuthStartReq::source`] is not available (nor any of the [`AuthStartReq::sources`])
+    /// for the attribute type with this handle
     SourceNotAvailableFor(handle::Handle),
 
     /// For some reason these two attribute types cannot be requested together
@@ -157,6 +187,14 @@ pub enum AuthStartResp {
     /// `yivi_chained_session` (and `yivi_chained_session_drip`) set to `false`, at the cost of the
     /// user scanning a second Yivi QR for card issuance.
     ChainedSessionsTemporarilyUnavailable,
+
+    /// The [`AuthStartReq::email`] provided is not a valid email address
+    InvalidEmailAddress,
+
+    /// A one-time code was sent to this email address very recently, or too many codes were sent
+    /// to it during the past hour.  Ask the user to use the code already sent, or to try again
+    /// later.
+    TooManyCodesRequested,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -189,6 +227,33 @@ pub enum AuthTask {
         disclosure_request: jwt::JWT,
         yivi_requestor_url: url::Url,
     },
+
+    /// Have the end-user enter the one-time code that was sent to [`AuthStartReq::email`].
+    Email {},
+
+    /// Send the end-user to `authorization_url` to log in at their OpenID Connect provider.
+    /// The provider then redirects the end-user back to the authentication server's configured
+    /// redirect url, with the `code` and `state` query parameters to be passed in
+    /// [`AuthProof::Oidc`].
+    Oidc { authorization_url: url::Url },
+
+    /// Have the end-user's authenticator sign `challenge` using a passkey for relying party
+    /// `rp_id`, via `navigator.credentials.get`, and submit the result as
+    /// [`AuthProof::WebAuthn`].
+    ///
+    /// To register a passkey, first create it using `navigator.credentials.create`, then obtain
+    /// its attribute in this way, and add that attribute to the user's account via
+    /// [`phc::user::EnterEP`] using the user's auth token.
+    ///
+    /// [`phc::user::EnterEP`]: crate::api::phc::user::EnterEP
+    WebAuthn {
+        /// Unpadded url-safe base64 encoded
+        challenge: crate::id::Id,
+        rp_id: String,
+
+        /// Whether user verification is required (`userVerification: "required"`).
+        user_verification: bool,
+    },
 }
 
 /// After having completed the task set by the authentication server,
@@ -220,6 +285,47 @@ pub enum AuthProof {
         /// with [`AuthTask::Yivi::disclosure_request`].
         disclosure: jwt::JWT,
     },
+
+    Email {
+        /// The one-time code sent by email, see [`AuthTask::Email`].
+        code: String,
+    },
+
+    /// The query parameters the OpenID Connect provider redirected the end-user back with,
+    /// see [`AuthTask::Oidc`].
+    Oidc {
+        /// The authorization code
+        code: String,
+
+        /// The OAuth 2.0 `state` parameter - not to be confused with [`AuthCompleteReq::state`].
+        state: String,
+    },
+
+    /// The WebAuthn assertion (the `response` of the `PublicKeyCredential` returned by
+    /// `navigator.credentials.get`), see [`AuthTask::WebAuthn`].
+    ///
+    /// The authentication server keeps no record of passkeys, so the global client must provide
+    /// the passkey's public key too.  This public key is the value of the resulting attribute.
+    WebAuthn {
+        public_key: WebAuthnPublicKey,
+        authenticator_data: B64UU,
+        client_data_json: B64UU,
+        signature: B64UU,
+    },
+}
+
+/// Public key of a passkey, see [`AuthProof::WebAuthn`].
+///
+/// Contains the DER encoded `SubjectPublicKeyInfo` as returned by
+/// `AuthenticatorAttestationResponse.getPublicKey()` when the passkey was created.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub enum WebAuthnPublicKey {
+    /// ECDSA using P-256 and SHA-256 (COSE algorithm -7)
+    ES256(B64UU),
+
+    /// Ed25519 (COSE algorithm -8)
+    EdDSA(B64UU),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -231,14 +337,23 @@ pub enum AuthCompleteResp {
         /// The resulting attributes, in the same order they were requested in
         /// [`AuthStartReq::attr_types`] or [`AuthStartReq::attr_type_choices`].
         /// In the latter case, the key indicates the choice the user made.
+        /// Of [`AuthStartReq::optional_attr_type_choices`] only the attributes the user actually
+        /// disclosed are included, after the mandatory ones.
         attrs: IndexMap<handle::Handle, Signed<Attr>>,
     },
 
     /// Something went wrong;  please start again at [`AuthStartEP`].
     ///
     /// One reason is that the authentication server restarted and that the provided authenication
-    /// state is no longer valid.
+    /// state is no longer valid.  For [`AuthProof::Email`] this is also returned when too many
+    /// incorrect codes were submitted, or when the code was already used, and for
+    /// [`AuthProof::Oidc`] when the OpenID Connect provider did not accept the authorization code,
+    /// and for [`AuthProof::WebAuthn`] when the challenge was already used.
     PleaseRestartAuth,
+
+    /// The [`AuthProof::Email::code`] is not correct.  The end-user may try again, a limited
+    /// number of times, using the same [`AuthCompleteReq::state`].
+    IncorrectCode,
 }
 
 /// Allows the global client to retrieve secrets tied to identifying [`Attr`]ibutes.
@@ -354,6 +469,170 @@ pub enum CardResp {
     PleaseRetryWithNewCardPseud,
 }
 
+/// Used by PubHubs Central to have the authentication server revoke, at the yivi server, the
+/// pubhubs cards issued with the given [`phc::user::CardPseudPackage::revocation_nonce`].
+///
+/// The request must be signed by PubHubs Central.  Returns [`ErrorCode::BadRequest`] when the
+/// signature is invalid, or when yivi is not configured for this authentication server.
+pub struct CardRevocationEP {}
+impl EndpointDetails for CardRevocationEP {
+    type RequestType = Signed<CardRevocationReq>;
+    type ResponseType = Result<CardRevocationResp>;
+
+    const METHOD: http::Method = http::Method::POST;
+    const PATH: &'static str = ".ph/card/revoke";
+}
+
+having_message_code!(CardRevocationReq, AuthsCardRevocationReq);
+
+/// Request type for [`CardRevocationEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CardRevocationReq {
+    /// The [`phc::user::CardPseudPackage::revocation_nonce`] of the cards to revoke
+    pub revocation_nonce: crate::id::Id,
+}
+
+/// What's returned by [`CardRevocationEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+#[must_use]
+pub enum CardRevocationResp {
+    /// The yivi server revoked the cards
+    Success,
+
+    /// The authentication server does not issue revocable cards, see
+    /// [`crate::servers::auths::card::CardConfig::revocation`].
+    RevocationNotEnabled,
+
+    /// The signature on the request expired;  please resign it
+    ResignRequest,
+}
+
+/// Used by a hub to obtain a signed session request for the issuance of a hub-specific yivi
+/// credential, such as "verified member of this hub", to one of its users.
+///
+/// Only the credentials configured at the authentication server
+/// (see [`crate::servers::auths::card::HubCredentialConfig`]) can be issued, and only to the hubs
+/// listed there.  The request must be signed using a ticket obtained from PubHubs Central
+/// (see [`phc::hub::TicketEP`]).
+///
+/// Returns [`ErrorCode::BadRequest`] when yivi is not configured for this authentication server.
+pub struct HubCredentialEP {}
+impl EndpointDetails for HubCredentialEP {
+    type RequestType = phc::hub::TicketSigned<HubCredentialReq>;
+    type ResponseType = Result<HubCredentialResp>;
+
+    const METHOD: http::Method = http::Method::POST;
+    const PATH: &'static str = ".ph/hub/credential";
+}
+
+having_message_code!(HubCredentialReq, AuthsHubCredentialReq);
+
+/// Request type for [`HubCredentialEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+#[must_use]
+pub struct HubCredentialReq {
+    /// The hub credential to issue, by the name under which it is configured
+    pub credential: handle::Handle,
+
+    /// The hub's pseudonym for the user to whom the credential is issued
+    pub hub_pseudonym: String,
+
+    /// Values of the attributes of the credential that are provided by the hub, see
+    /// [`crate::servers::auths::card::HubCredentialConfig::attributes`].
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+}
+
+/// What's returned by [`HubCredentialEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+#[must_use]
+pub enum HubCredentialResp {
+    Success {
+        /// Signed issuance request to be passed to the yivi server, by the user's client, like
+        /// [`CardResp::Success::issuance_request`].
+        issuance_request: jwt::JWT,
+
+        /// The Yivi server that can handle the issuance request
+        yivi_requestor_url: url::Url,
+    },
+
+    /// No hub credential is configured with this name, or the hub is not allowed to issue it
+    UnknownCredential,
+
+    /// [`HubCredentialReq::attributes`] does not provide exactly the attributes configured for
+    /// this credential
+    InvalidAttributes,
+
+    /// The ticket has expired, or has been revoked;  please obtain a new one
+    RetryWithNewTicket,
+}
+
+/// Obtain a signed session request to have the end-user sign a message with their yivi
+/// attributes, for example to agree to a hub's house rules, or to sign a petition.
+///
+/// The yivi server returns the result of this signature session as a JWT signed by the yivi
+/// server, which can be passed to the hub, and checked by the hub (even much later, after the
+/// JWT has expired) against the yivi server's public key using
+/// [`crate::servers::yivi::SessionResult::open_signed_signature`] and
+/// [`crate::servers::yivi::SessionResult::validate_signature_and_extract_raw_singles`].
+///
+/// Returns [`ErrorCode::BadRequest`] when yivi is not configured for this authentication server,
+/// when no attribute types are requested, or when the message is longer than
+/// [`YiviSignReq::MAX_MESSAGE_LEN`] bytes.
+pub struct YiviSignEP {}
+impl EndpointDetails for YiviSignEP {
+    type RequestType = YiviSignReq;
+    type ResponseType = Result<YiviSignResp>;
+
+    const METHOD: http::Method = http::Method::POST;
+    const PATH: &'static str = ".ph/yivi/sign";
+}
+
+/// Request type for [`YiviSignEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+#[must_use]
+pub struct YiviSignReq {
+    /// The message to be signed, as provided by the hub
+    pub message: String,
+
+    /// The attributes to sign the message with, given as alternatives like
+    /// [`AuthStartReq::attr_type_choices`].
+    pub attr_type_choices: Vec<Vec<handle::Handle>>,
+}
+
+impl YiviSignReq {
+    /// Maximal length of [`YiviSignReq::message`] in bytes
+    pub const MAX_MESSAGE_LEN: usize = 4096;
+}
+
+/// What's returned by [`YiviSignEP`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+#[must_use]
+pub enum YiviSignResp {
+    Success {
+        /// Signed signature session request to be passed to the yivi server
+        signature_request: jwt::JWT,
+
+        /// The Yivi server that can handle the signature request
+        yivi_requestor_url: url::Url,
+    },
+
+    /// No attribute type known with this handle
+    UnknownAttrType(handle::Handle),
+
+    /// Yivi is not available for the attribute type with this handle
+    SourceNotAvailableFor(handle::Handle),
+
+    /// These two attribute types cannot be requested together, see [`AuthStartResp::Conflict`]
+    Conflict(handle::Handle, handle::Handle),
+}
+
 /// Wait for the disclosure result that the yivi server will post to the authentication server
 ///
 /// Might return [`ErrorCode::BadRequest`] when yivi is not configured for this authentication
@@ -624,7 +624,9 @@ macro_rules! wrap_dalek_type {
 /// Hybrid post-quantum signing/verifying keys for JWTs and [`Signed`](crate::api::Signed) messages.
 /// The live [`SigningKey`]/[`VerifyingKey`] sign/verify; the [`SigningKeyBytes`]/[`VerifyingKeyBytes`]
 /// forms are what travel on the wire and in config.  See [`crate::common::dsa`].
-pub use crate::common::dsa::{SigningKey, SigningKeyBytes, VerifyingKey, VerifyingKeyBytes};
+pub use crate::common::dsa::{
+    RotatingVerifyingKey, SigningKey, SigningKeyBytes, VerifyingKey, VerifyingKeyBytes,
+};
 
 /// An ed25519 public key, hex-encoded.
 ///
@@ -262,6 +262,10 @@ pub struct DiscoveryInfoResp {
     /// This server's hybrid post-quantum verifying key, used to verify its JWTs and signatures.
     pub verifying_key: VerifyingKeyBytes,
 
+    /// The verifying key this server will switch to, when it has staged a new signing key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_verifying_key: Option<NextVerifyingKey>,
+
     /// Hash of the transcryptor's master encryption key part `x_T B`, published so PHC can commit
     /// it to the constellation id before it is able to unseal the part itself.  Set by the
     /// transcryptor only: PHC commits its own part's hash to the constellation directly, and must
@@ -279,6 +283,25 @@ pub struct DiscoveryInfoResp {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub master_enc_key_part_sealed: Option<Sealed<MasterEncKeyPart>>,
 
+    /// Proof that the transcryptor knows the discrete logarithm `x_T` of its master encryption
+    /// key part `x_T B`, which PHC passes on to the co-transcryptors, see
+    /// [`tr::MasterEncKeyToScale`].  Only set by the transcryptors while the transcryptor
+    /// role is split.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub master_enc_key_part_proof: Option<elgamal::scaling::Proof>,
+
+    /// Hash of the transcryptor's previous master encryption key part, set by the transcryptor
+    /// while its part is being rotated.  Committed to the constellation by PHC.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_master_enc_key_part_hash: Option<crate::id::Id>,
+
+    /// The transcryptor's previous master encryption key part, together with the factor PHC needs
+    /// to re-key polymorphic pseudonyms, sealed like
+    /// [`master_enc_key_part_sealed`](Self::master_enc_key_part_sealed).  Set by the
+    /// transcryptor while its part is being rotated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_master_enc_key_part_sealed: Option<Sealed<PreviousMasterEncKeyPart>>,
+
     /// Hybrid post-quantum [`kem`] encapsulation key, used by pubhubs central to establish
     /// a shared secret with this server.  Only set for the transcryptor and authentication server.
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -290,6 +313,21 @@ pub struct DiscoveryInfoResp {
     pub constellation_or_id: Option<crate::servers::constellation::ConstellationOrId>,
 }
 
+/// A verifying key staged by a server to succeed its current one, see
+/// [`DiscoveryInfoResp::next_verifying_key`].
+///
+/// From [`Self::active_from`] on, the server signs using the corresponding signing key.  Until the
+/// server's configuration is updated to make it the current key, signatures by either key are
+/// accepted.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct NextVerifyingKey {
+    pub verifying_key: VerifyingKeyBytes,
+
+    /// When the server starts signing using this key.
+    pub active_from: NumericDate,
+}
+
 /// A master encryption key part, wrapped so it can be sealed as
 /// [`DiscoveryInfoResp::master_enc_key_part_sealed`].
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -297,6 +335,19 @@ pub struct MasterEncKeyPart(pub elgamal::PublicKey);
 
 having_message_code!(MasterEncKeyPart, MasterEncKeyPart);
 
+/// The transcryptor's previous master encryption key part, sealed as
+/// [`DiscoveryInfoResp::previous_master_enc_key_part_sealed`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PreviousMasterEncKeyPart {
+    /// The previous part `x_T' B`
+    pub part: elgamal::PublicKey,
+
+    /// `x_T / x_T'`, see [`crate::phcrypto::master_enc_key_part_rekey_factor`].
+    pub rekey_factor: elgamal::PrivateKey,
+}
+
+having_message_code!(PreviousMasterEncKeyPart, PreviousMasterEncKeyPart);
+
 /// What's returned by the [`DiscoveryRun`].
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 #[serde(deny_unknown_fields)]
@@ -50,6 +50,107 @@ pub mod hub {
 
         /// Hub has no verifying key set
         NoVerifyingKey,
+
+        /// The hub has been suspended by a PubHubs Central admin, see
+        /// [`crate::api::admin::SuspendHubEP`].
+        HubSuspended,
+    }
+
+    /// Used by a prospective hub to ask to be registered at PubHubs Central.  The request ends up
+    /// in a queue of pending hubs until a PubHubs Central admin approves or rejects it (see
+    /// [`crate::api::admin::ApproveHubEP`]), after which the hub can obtain tickets via
+    /// [`TicketEP`].
+    ///
+    /// The request must be signed for `verifying_key`, which must also be advertised by the hub
+    /// info endpoint (see [`crate::api::hub::InfoEP`]) at `url`.  If the signature cannot be
+    /// verified, [`ErrorCode::BadRequest`] is returned.
+    ///
+    /// A pending request can be changed by submitting a new one for the same handle, signed
+    /// using the same key.
+    pub struct OnboardEP {}
+    impl EndpointDetails for OnboardEP {
+        type RequestType = Signed<OnboardReq>;
+        type ResponseType = Result<OnboardResp>;
+
+        const METHOD: http::Method = http::Method::POST;
+        const PATH: &'static str = ".ph/hub/onboard";
+    }
+
+    having_message_code!(OnboardReq, PhcHubOnboardReq);
+
+    /// Request type for [`OnboardEP`], see [`crate::hub::BasicInfo`] for the meaning of most
+    /// fields.
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[serde(deny_unknown_fields)]
+    pub struct OnboardReq {
+        /// The handle the hub would like to have
+        pub handle: crate::handle::Handle,
+        pub name: String,
+        pub description: String,
+
+        /// Must end with a `/`.
+        pub url: url::Url,
+
+        /// The key the hub signs its requests with, as advertised by [`crate::api::hub::InfoEP`].
+        pub verifying_key: VerifyingKeyBytes,
+    }
+
+    /// What [`OnboardEP`] returns
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[serde(deny_unknown_fields)]
+    #[must_use]
+    pub enum OnboardResp {
+        /// The request awaits the approval of a PubHubs Central admin
+        Pending,
+
+        /// The handle is used by another (possibly retired or pending) hub
+        HandleTaken,
+
+        /// The url does not end with a `/`, is not `https`, or points to an internal host such as
+        /// `localhost` or a private ip address
+        InvalidUrl,
+
+        /// The hub info endpoint at the url could not be reached, or did not advertise
+        /// `verifying_key`
+        VerifyingKeyMismatch,
+
+        /// There are too many pending requests, or another hub at the same host is already
+        /// pending; try again later.  Pending requests expire after a week.
+        QueueFull,
+    }
+
+    /// Lists the hubs that are suspended (see [`crate::api::admin::SuspendHubEP`]), and the
+    /// tickets that were revoked before their expiry because of a suspension.
+    ///
+    /// Hubs (and the transcryptor) should regularly retrieve this list, and reject revoked tickets
+    /// even when they have not yet expired.  Tickets are identified by
+    /// [`crate::phcrypto::ticket_digest`].
+    pub struct RevocationsEP {}
+    impl EndpointDetails for RevocationsEP {
+        type RequestType = NoPayload;
+        type ResponseType = Result<RevocationsResp>;
+
+        const METHOD: http::Method = http::Method::GET;
+        const PATH: &'static str = ".ph/hub/revocations";
+    }
+
+    /// What [`RevocationsEP`] returns
+    #[derive(Serialize, Deserialize, Debug, Clone, Default)]
+    #[serde(deny_unknown_fields)]
+    pub struct RevocationsResp {
+        /// Ids of the suspended hubs
+        pub suspended_hubs: Vec<Id>,
+
+        /// Digests of the revoked tickets that have not yet expired
+        pub revoked_tickets: Vec<Id>,
+    }
+
+    impl RevocationsResp {
+        /// Whether the given ticket has been revoked.
+        pub fn is_revoked(&self, ticket: &Ticket) -> bool {
+            self.revoked_tickets
+                .contains(&crate::phcrypto::ticket_digest(ticket))
+        }
     }
 
     pub type Ticket = Signed<TicketContent>;
@@ -76,9 +177,9 @@ pub mod hub {
     impl<T> TicketSigned<T> {
         /// Opens this [`TicketSigned`], checking the signature on `signed` using the verifying key in
         /// the provided `ticket`, and checking the `ticket` using `key`.
-        pub fn open(
+        pub fn open<VK: crate::misc::jwt::VerifyingKey>(
             self,
-            key: &VerifyingKey,
+            key: &VK,
         ) -> std::result::Result<(T, crate::handle::Handle), TicketOpenError>
         where
             T: Signable,
@@ -272,6 +373,9 @@ pub mod user {
     #[must_use]
     pub enum EnterResp {
         /// Happens only in [`EnterMode::Login`]
+        ///
+        /// Also returned when the identifying attribute was removed from the account it
+        /// identifies, see [`RemoveIdentifyingAttrEP`].
         AccountDoesNotExist,
 
         /// This attribute is banned and therefore cannot be used.
@@ -280,6 +384,10 @@ pub mod user {
         /// Cannot login, because this account is banned.
         Banned,
 
+        /// This attribute comes from a PubHubs card that has been revoked (see
+        /// [`crate::api::admin::RevokeCardsEP`]), and therefore cannot be used.
+        CardRevoked(attr::Attr),
+
         /// The given identifying attribute (in [`EnterReq::add_attrs`] or [`EnterReq::identifying_attr`])
         /// is already tied to another account.
         ///
@@ -488,7 +596,7 @@ pub mod user {
         RetryWithNewAuthToken,
 
         /// Retrieval of [`UserState`] was successful
-        State(UserState),
+        State(Box<UserState>),
     }
 
     /// State of a user's account at pubhubs as shown to the user.
@@ -501,11 +609,40 @@ pub mod user {
         /// Attributes that when banned ban this user.
         pub could_be_banned_by: HashSet<Id>,
 
+        /// Details on the attributes in [`Self::allow_login_by`] and [`Self::could_be_banned_by`].
+        #[serde(default)]
+        pub attrs: HashMap<Id, AttrDetails>,
+
         /// Objects stored for this user
         pub stored_objects: HashMap<handle::Handle, UserObjectDetails>,
+
+        /// Earlier versions of objects stored for this user, for the handles for which this was
+        /// requested via [`SetObjectHistoryEP`].
+        #[serde(default)]
+        pub object_history: HashMap<handle::Handle, ObjectHistory>,
         // TODO: add information on Quota
     }
 
+    /// Details on an attribute of a user, see [`UserState::attrs`].
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    #[serde(deny_unknown_fields)]
+    pub struct AttrDetails {
+        /// The preferred handle of the attribute's [`attr::Type`].
+        ///
+        /// Not known for attributes added before these details were recorded, or whose type is no
+        /// longer offered by the authentication server.
+        pub attr_type: Option<handle::Handle>,
+
+        /// Whether this attribute may be used to log in as this user
+        pub allows_login: bool,
+
+        /// Whether banning this attribute bans this user
+        pub bans: bool,
+
+        /// When this attribute was added to the user's account, if known
+        pub added_at: Option<NumericDate>,
+    }
+
     /// Details on an object stored at pubhubs central for a user.
     #[derive(Serialize, Deserialize, Debug, Clone)]
     #[serde(deny_unknown_fields)]
@@ -580,6 +717,102 @@ pub mod user {
         const PATH: &'static str = ".ph/user/obj/by-hash/{handle}/{overwrite_hash}";
     }
 
+    /// Deletes the object stored at pubhubs central under the given `handle`, provided its hash is
+    /// `hash`.
+    ///
+    /// If earlier versions of the object are kept (see [`SetObjectHistoryEP`]), the deleted
+    /// object is kept as most recent earlier version.
+    pub struct DeleteObjectEP {}
+    impl EndpointDetails for DeleteObjectEP {
+        type RequestType = NoPayload;
+        type ResponseType = Result<DeleteObjectResp>;
+
+        const METHOD: http::Method = http::Method::POST;
+        const PATH: &'static str = ".ph/user/obj/delete/{handle}/{hash}";
+    }
+
+    /// Returned by [`DeleteObjectEP`].
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[serde(deny_unknown_fields)]
+    #[serde(rename = "snake_case")]
+    #[must_use]
+    pub enum DeleteObjectResp {
+        /// Please retry the same request again, see [`StoreObjectResp::PleaseRetry`].
+        PleaseRetry,
+
+        /// The auth provided is expired or otherwise invalid.  Obtain a new one and retry.
+        RetryWithNewAuthToken,
+
+        /// There is no (longer an) object stored under that handle.
+        NotFound,
+
+        /// The object stored under that handle has a different hash, presumably because it has
+        /// been changed in the meantime by another global client.
+        HashDidNotMatch,
+
+        /// The object was deleted.  The user objects that are currently stored for this user are
+        /// returned.
+        Deleted {
+            stored_objects: HashMap<handle::Handle, UserObjectDetails>,
+        },
+    }
+
+    /// Sets how many earlier versions of the object stored under `handle` are kept when it is
+    /// overwritten or deleted, so that the global client can recover from, for example, a bad
+    /// synchronization.  By default no earlier versions are kept.
+    ///
+    /// Earlier versions count towards the user's [`Quota`].  Lowering [`SetObjectHistoryReq::keep`]
+    /// discards the oldest versions.
+    pub struct SetObjectHistoryEP {}
+    impl EndpointDetails for SetObjectHistoryEP {
+        type RequestType = SetObjectHistoryReq;
+        type ResponseType = Result<SetObjectHistoryResp>;
+
+        const METHOD: http::Method = http::Method::POST;
+        const PATH: &'static str = ".ph/user/obj/history/{handle}";
+    }
+
+    /// Request type for [`SetObjectHistoryEP`]
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[serde(deny_unknown_fields)]
+    pub struct SetObjectHistoryReq {
+        /// The number of earlier versions to keep, at most [`SetObjectHistoryReq::MAX_KEEP`].
+        pub keep: u8,
+    }
+
+    impl SetObjectHistoryReq {
+        pub const MAX_KEEP: u8 = 10;
+    }
+
+    /// Returned by [`SetObjectHistoryEP`].
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[serde(deny_unknown_fields)]
+    #[serde(rename = "snake_case")]
+    #[must_use]
+    pub enum SetObjectHistoryResp {
+        /// Please retry the same request again, see [`StoreObjectResp::PleaseRetry`].
+        PleaseRetry,
+
+        /// The auth provided is expired or otherwise invalid.  Obtain a new one and retry.
+        RetryWithNewAuthToken,
+
+        /// The setting was changed.  Returns the current [`UserState::object_history`].
+        Success {
+            object_history: HashMap<handle::Handle, ObjectHistory>,
+        },
+    }
+
+    /// Earlier versions of an object stored for a user, see [`UserState::object_history`].
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[serde(deny_unknown_fields)]
+    pub struct ObjectHistory {
+        /// The number of earlier versions kept, see [`SetObjectHistoryReq::keep`]
+        pub keep: u8,
+
+        /// The earlier versions, most recent first.  Can be retrieved via [`GetObjectEP`].
+        pub versions: Vec<UserObjectDetails>,
+    }
+
     /// Returned by [`NewObjectEP`] and [`OverwriteObjectEP`].
     #[derive(Serialize, Deserialize, Debug, Clone)]
     #[serde(deny_unknown_fields)]
@@ -661,6 +894,258 @@ pub mod user {
         }
     }
 
+    /// Deletes the account of the current user.  Requires authentication.
+    ///
+    /// Removes the objects stored for the user, and detaches the user's identifying attributes,
+    /// so that they can be used to register a new account.  What could ban the user is retained,
+    /// so deleting an account does not lift (future) bans:  a new account registered with one of
+    /// the old account's bannable attributes is banned together with the old account.
+    pub struct DeleteEP {}
+    impl EndpointDetails for DeleteEP {
+        type RequestType = NoPayload;
+        type ResponseType = Result<DeleteResp>;
+
+        const METHOD: http::Method = http::Method::POST;
+        const PATH: &'static str = ".ph/user/delete";
+    }
+
+    /// Returned by [`DeleteEP`].
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[serde(deny_unknown_fields)]
+    #[serde(rename = "snake_case")]
+    #[must_use]
+    pub enum DeleteResp {
+        /// The auth provided is expired or otherwise invalid.  Obtain a new one and retry.
+        RetryWithNewAuthToken,
+
+        /// The account has been deleted.  The auth token can no longer be used.
+        Deleted,
+    }
+
+    /// Merges another account into the current user's account.  Requires authentication.
+    ///
+    /// Useful when a user registered a second account, for example because they lost control
+    /// over the identifying attribute of their first account.  The user proves control over the
+    /// other account using one of its identifying attributes, obtained via
+    /// [`auths::AuthCompleteEP`].
+    ///
+    /// The attributes and stored objects of the other account are moved to the current account,
+    /// after which the other account can no longer be used.  An object of the other account
+    /// stored under a handle also used by the current account becomes the most recent earlier
+    /// version of the current account's object, see [`UserState::object_history`]; the earlier
+    /// versions of both are kept too, and the number of versions kept for the handle is raised to
+    /// fit them all.  Attributes that could ban the other account will ban the current account
+    /// too, while still banning the other account.
+    ///
+    /// The pubhubs cards issued to the other account log in to the current account from now on,
+    /// and are revoked together with the current account's cards, see
+    /// [`UserExport::merged_card_pseuds`].
+    ///
+    /// What is lost:
+    ///  - the other account's pseudonyms at hubs, and thus its hub accounts: the current
+    ///    account's hub pseudonyms are kept;
+    ///  - earlier versions of objects under a handle beyond the
+    ///    [`SetObjectHistoryReq::MAX_KEEP`] most recent ones, where the other account's current
+    ///    object counts as most recent, followed by the earlier versions of the current account,
+    ///    and then those of the other account.
+    pub struct MergeEP {}
+    impl EndpointDetails for MergeEP {
+        type RequestType = MergeReq;
+        type ResponseType = Result<MergeResp>;
+
+        const METHOD: http::Method = http::Method::POST;
+        const PATH: &'static str = ".ph/user/merge";
+    }
+
+    /// Request type for [`MergeEP`]
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[serde(deny_unknown_fields)]
+    pub struct MergeReq {
+        /// Identifying attribute of the account to merge into the current account
+        pub identifying_attr: Signed<attr::Attr>,
+    }
+
+    /// Returned by [`MergeEP`].
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[serde(deny_unknown_fields)]
+    #[serde(rename = "snake_case")]
+    #[must_use]
+    pub enum MergeResp {
+        /// The auth provided is expired or otherwise invalid.  Obtain a new one and retry.
+        RetryWithNewAuthToken,
+
+        /// Signature on the identifying attribute is invalid or expired; please reobtain the
+        /// identifying attribute and retry.
+        RetryWithNewIdentifyingAttr,
+
+        /// The identifying attribute does not allow login to any account.
+        UnknownAccount,
+
+        /// The identifying attribute allows login to the current account already.
+        SameAccount,
+
+        /// One of the two accounts is banned.
+        Banned,
+
+        /// Cannot merge, because the current account would exceed the named quotum.
+        QuotumReached(QuotumName),
+
+        /// The accounts were merged.  Returns the updated state of the current account.
+        Merged(Box<UserState>),
+    }
+
+    /// Removes an attribute from [`UserState::allow_login_by`], so that it can no longer be used
+    /// to log in.  Requires authentication.
+    ///
+    /// The attribute can not be used to register another account either, but it can be added to
+    /// the user's account again via [`EnterReq::add_attrs`].
+    pub struct RemoveIdentifyingAttrEP {}
+    impl EndpointDetails for RemoveIdentifyingAttrEP {
+        type RequestType = RemoveIdentifyingAttrReq;
+        type ResponseType = Result<RemoveIdentifyingAttrResp>;
+
+        const METHOD: http::Method = http::Method::POST;
+        const PATH: &'static str = ".ph/user/remove-identifying-attr";
+    }
+
+    /// Request type for [`RemoveIdentifyingAttrEP`]
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[serde(deny_unknown_fields)]
+    pub struct RemoveIdentifyingAttrReq {
+        /// The [`Id`] of the attribute to remove, as listed in [`UserState::allow_login_by`].
+        pub attr: Id,
+    }
+
+    /// Returned by [`RemoveIdentifyingAttrEP`].
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[serde(deny_unknown_fields)]
+    #[serde(rename = "snake_case")]
+    #[must_use]
+    pub enum RemoveIdentifyingAttrResp {
+        /// The auth provided is expired or otherwise invalid.  Obtain a new one and retry.
+        RetryWithNewAuthToken,
+
+        /// The attribute is not among [`UserState::allow_login_by`].
+        UnknownAttr,
+
+        /// Refusing to remove the only attribute that can be used to log in.
+        LastIdentifyingAttr,
+
+        /// The attribute was removed.  Returns the updated state.
+        Removed(Box<UserState>),
+    }
+
+    /// Exports everything pubhubs central holds on the current user, including the contents of
+    /// their stored objects.  Requires authentication.
+    ///
+    /// The export is assembled in memory and returned as a single json document.  Its size is
+    /// bounded by [`Quota::object_bytes_total`] of the configured quota (plus the base64 overhead
+    /// and a few kilobytes of metadata):  users whose objects exceed the quota, for example
+    /// because it was lowered, get [`ExportResp::QuotumReached`] until they remove some objects.
+    pub struct ExportEP {}
+    impl EndpointDetails for ExportEP {
+        type RequestType = NoPayload;
+        type ResponseType = Result<ExportResp>;
+
+        const METHOD: http::Method = http::Method::GET;
+        const PATH: &'static str = ".ph/user/export";
+    }
+
+    /// Returned by [`ExportEP`].
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[serde(deny_unknown_fields)]
+    #[serde(rename = "snake_case")]
+    #[must_use]
+    pub enum ExportResp {
+        /// The auth provided is expired or otherwise invalid.  Obtain a new one and retry.
+        RetryWithNewAuthToken,
+
+        /// The user's objects (including their history) exceed the named quotum, so the export
+        /// would be larger than pubhubs central is willing to assemble.
+        QuotumReached(QuotumName),
+
+        Success(Box<UserExport>),
+    }
+
+    /// Everything pubhubs central holds on a user, see [`ExportEP`].
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[serde(deny_unknown_fields)]
+    pub struct UserExport {
+        /// Randomly generated identifier for the user's account
+        pub id: Id,
+
+        /// Pseudonym put on the user's pubhubs cards, see [`CardPseudEP`]
+        pub card_pseud: CardPseud,
+
+        /// Pseudonyms on the (unrevoked) pubhubs cards of the accounts merged into this one, see
+        /// [`MergeEP`]
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        pub merged_card_pseuds: Vec<CardPseud>,
+
+        /// Might not be set for users that registered under v3.0.0
+        pub registration_date: Option<NumericDate>,
+
+        /// Whether the account is banned
+        pub banned: bool,
+
+        /// Attributes that may be used to log in as this user.
+        pub allow_login_by: HashSet<Id>,
+
+        /// Attributes that when banned ban this user.
+        pub could_be_banned_by: HashSet<Id>,
+
+        /// What pubhubs central knows about the attributes in [`Self::allow_login_by`] and
+        /// [`Self::could_be_banned_by`]
+        pub attrs: HashMap<Id, ExportedAttrState>,
+
+        /// Objects stored for this user, with their contents
+        pub stored_objects: HashMap<handle::Handle, ExportedObject>,
+
+        /// Earlier versions of objects stored for this user, most recent first, see
+        /// [`UserState::object_history`]
+        pub object_history: HashMap<handle::Handle, Vec<ExportedObject>>,
+
+        /// The quota that apply to this user
+        pub quota: Quota,
+
+        /// How much of [`Self::quota`] is used
+        pub quota_usage: Quota,
+    }
+
+    /// What pubhubs central knows about one of the user's attributes, see [`UserExport::attrs`].
+    ///
+    /// The attribute itself is not stored by pubhubs central, only its [`Id`].
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    #[serde(deny_unknown_fields)]
+    pub struct ExportedAttrState {
+        /// Whether this attribute is banned
+        pub banned: bool,
+
+        /// Whether this attribute may be used to log in to this user's account
+        pub identifies_user: bool,
+
+        /// Whether banning this attribute bans this user
+        pub bans_user: bool,
+
+        /// See [`AttrDetails::attr_type`]
+        pub attr_type: Option<handle::Handle>,
+
+        /// See [`AttrDetails::added_at`]
+        pub added_at: Option<NumericDate>,
+    }
+
+    /// An object stored for a user, see [`UserExport::stored_objects`].
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[serde(deny_unknown_fields)]
+    pub struct ExportedObject {
+        /// See [`UserObjectDetails::hash`]
+        pub hash: Id,
+
+        /// The contents of the object
+        pub contents: B64UU,
+    }
+
     /// Requests an [`sso::PolymorphicPseudonymPackage`].  Requires authentication.
     pub struct PppEP {}
     impl EndpointDetails for PppEP {
@@ -752,6 +1237,13 @@ pub mod user {
 
         /// Registration date for this user.  Can be `None` for users that registered under v3.0.0.
         pub registration_date: Option<NumericDate>,
+
+        /// Random nonce that the authentication server uses as yivi revocation key for the cards
+        /// it issues with this package, so that these can be revoked later on (see
+        /// [`crate::api::admin::RevokeCardsEP`]).
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub revocation_nonce: Option<Id>,
     }
 
     having_message_code!(CardPseudPackage, CardPseudPackage);
@@ -48,6 +48,7 @@ pub enum PingResp {
         served_by: crate::servers::Name,
     },
 
-    /// Ticket signature was invalid or expired.  Obtain a new ticket and retry.
+    /// Ticket signature was invalid or expired, or the ticket was revoked.  Obtain a new ticket
+    /// and retry.
     RetryWithNewTicket,
 }
@@ -219,6 +219,11 @@ impl<T> Signed<T> {
     pub fn as_str(&self) -> &str {
         self.inner.as_str()
     }
+
+    /// Returns an [`id::Id`] identifying this signed message, see [`crate::phcrypto::jwt_id`].
+    pub fn id(&self) -> id::Id {
+        self.inner.id()
+    }
 }
 
 /// A number that represents the type of a message.  Every message type that's [Signed] gets such a
@@ -248,6 +253,25 @@ pub enum MessageCode {
     // new >v3.3.0
     /// Seals the transcryptor's master encryption key part in its discovery info.
     MasterEncKeyPart = 14,
+    AdminBanReq = 15,
+    AdminUnbanReq = 16,
+    AdminBanLogReq = 17,
+    AdminAddHubReq = 18,
+    AdminUpdateHubReq = 19,
+    AdminRetireHubReq = 20,
+    PhcHubOnboardReq = 21,
+    AdminPendingHubsReq = 22,
+    AdminApproveHubReq = 23,
+    AdminRejectHubReq = 24,
+    AdminSuspendHubReq = 25,
+    AdminUnsuspendHubReq = 26,
+    AuthsHubCredentialReq = 27,
+    AdminRevokeCardsReq = 28,
+    AuthsCardRevocationReq = 29,
+    PreviousMasterEncKeyPart = 30,
+    PartialEhpp = 31,
+    MasterEncKeyToScale = 32,
+    ScaledMasterEncKey = 33,
 
     /// Only used as an example in a doctest
     Example = 65535,
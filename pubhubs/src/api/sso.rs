@@ -114,7 +114,7 @@
 //!     [`PolymorphicPseudonymPackage`] (**PPP**), sealed for the transcryptor, which contains
 //!     a freshly rerandomized polymorphic pseudonym, $\mathrm{PP}\_U$, and a
 //!     polymorphic pseudonym nonce (**phc nonce**). The phc nonce is essentially an encrypted cookie
-//!     that contains the `user_id` and expiry of the PP.
+//!     that contains the `user_id`, the PP itself, and its expiry.
 //!     
 //!     Simultaneously, the global client obtains a **hub state** and **hub nonce** from the hub via the
 //!     [`hub::EnterStartEP`] endpoint.  The hub state and hub nonce are encrypted cookies too.
@@ -139,7 +139,8 @@
 //!  3. The global client forwards the EHPP back to PHC, via [`phc::user::HhppEP`].
 //!     PHC extracts the phc nonce, checks its validity, and extracts the `user_id` from it,
 //!     and checks that it coincides with the `user_id` from the auth token.
-//!     If this checks out, PHC proceeds by decrypting the encrypted hub pseudonym using
+//!     If this checks out, PHC checks the transcryptor's proof that it did its work correctly
+//!     (see below), and proceeds by decrypting the encrypted hub pseudonym using
 //!     $x\_\mathrm{PHC}$, yielding $g_H \mathrm{Id}\_U$.
 //!     PHC then computes the **hashed hub pseudonym** $\mathrm{Sha512}(g_H\cdot\mathrm{Id}\_U)$,
 //!     and returns it to global client in a signed [`HashedHubPseudonymPackage`] (**HHPP**) that also contains
@@ -152,12 +153,55 @@
 //!     whether the PP and hub state are fresh (issued no longer than 10 seconds ago).
 //!     If everything checks out, the hashed hub pseudonym is used by the hub as external user
 //!     id to look up the (or register a) matrix user for $U$ at $H$.
+//!
+//! # Splitting the transcryptor
+//!
+//! To remove the need to trust that PHC and a single transcryptor do not collude, the role of the
+//! transcryptor can be split between the transcryptor $T\_1$ and one or more **co-transcryptors**
+//! $T\_2,\dotsc,T\_n$, run by independent parties and listed in the constellation's
+//! [`co_transcryptors`](crate::servers::constellation::Inner::co_transcryptors).  Each $T\_i$
+//! holds its own master encryption key part $x\_{T\_i}$ and pseudonymisation factor secret, so
+//! that
+//! $$x\_\mathrm{T} = x\_{T\_1}\dotsb x\_{T\_n}\quad\text{and}\quad g\_H = g\_{H,1}\dotsb g\_{H,n},$$
+//! where $g\_{H,i}$ is computed from $T\_i$'s secret as $g\_H$ is above.  PHC obtains
+//! $x\_\mathrm{T} x\_\mathrm{PHC} B$ by having each co-transcryptor $T\_i$ multiply its part
+//! into $x\_{T\_{i-1}}\dotsb x\_{T\_1} B$ in turn, via [`tr::ScaleMasterEncKeyEP`], and
+//! multiplying in its own part last.  A co-transcryptor only does this for the one product it
+//! can check against the constellation and the proofs of the preceding transcryptors (see
+//! [`tr::MasterEncKeyToScale`]), so PHC can not use it to multiply, say, the ephemeral key of a
+//! polymorphic pseudonym by $x\_{T\_i}$.
+//!
+//! In step 2 of the flow, $T\_1$ then does not return the EHPP, but a [`PartialEhpp`] with
+//! only its own part of the work done, sealed for $T\_2$ (see [`SealedPartialEhpp`]).  The global
+//! client passes it on to $T\_2$'s [`tr::ContinueEhppEP`], which does its part, and so on, until
+//! $T\_n$ returns the EHPP, sealed for PHC.  Since $x\_{T\_i}$ and $g\_{H,i}$ are only known to
+//! $T\_i$, PHC colluding with some but not all of the transcryptors can neither decrypt
+//! polymorphic pseudonyms, nor compute the pseudonym of a user at a hub of its choosing.  Note
+//! that each transcryptor still learns the hub $H$ the user enters.
+//!
+//! Changing the number of transcryptors changes both the master encryption key and the hub
+//! pseudonyms, so this must be decided on before users register.  The rotation of master
+//! encryption key parts is not supported while the transcryptor role is split.
+//!
+//! # Proofs of correct transcryption
+//!
+//! PHC need not trust that the encrypted hub pseudonym was computed correctly: along with it,
+//! the transcryptor returns a [`TranscryptionProof`], containing for each transcryptor an
+//! [`elgamal::rsk::Proof`] that its result was obtained from its input via
+//! [`elgamal::Triple::rsk`].  PHC checks these against the polymorphic pseudonym it handed out,
+//! which it keeps in the phc nonce for this purpose.  This shows that the encrypted hub pseudonym
+//! decrypts to $s \mathrm{Id}\_U$ for some scalar $s$ known to the transcryptor(s), and PHC
+//! checks that $s\neq 0$.  So the transcryptor can not give $U$ the hub pseudonym of another
+//! user, nor give two users the same hub pseudonym, without computing discrete logarithms.
+//!
+//! The proof does not show that $s$ equals $g_H$: that would require a commitment to $g_H$ known
+//! to PHC, which would reveal to PHC which users visit the same hub.
 
 use crate::api::*;
 
 use serde::{Deserialize, Serialize};
 
-use crate::common::elgamal;
+use crate::common::{elgamal, kem};
 use crate::id;
 use crate::misc::jwt;
 
@@ -192,10 +236,161 @@ pub struct EncryptedHubPseudonymPackage {
     /// HMAC binding the hub id the transcryptor pseudonymised for; see [`hub::HubMacKey::mac`].
     /// `None` when [`EhppReq::hub_mac_key`](tr::EhppReq::hub_mac_key) was absent.
     pub hub_id_mac: Option<id::Id>,
+
+    /// The hub pseudonym under the transcryptors' previous pseudonymisation factor secrets,
+    /// elgamal encrypted for `x_PHC`: one per version in which one of them changed its secret,
+    /// most recent version first.  Empty unless one of those secrets is being changed.
+    pub previous_encrypted_hub_pseudonyms: Vec<PreviousPseudonym>,
+
+    /// Proves [`Self::encrypted_hub_pseudonym`] was computed correctly from the polymorphic
+    /// pseudonym.
+    pub transcryption_proof: TranscryptionProof,
 }
 
 having_message_code!(EncryptedHubPseudonymPackage, Ehpp);
 
+/// The hub pseudonym (or polymorphic pseudonym in the making) under the pseudonymisation factor
+/// secrets the transcryptors used before [`Self::version`], see
+/// [`EncryptedHubPseudonymPackage::previous_encrypted_hub_pseudonyms`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PreviousPseudonym {
+    /// The version of the change of pseudonymisation factor secrets, see
+    /// [`crate::servers::config::transcryptor::PreviousPseudFactorSecret::version`].
+    pub version: u32,
+
+    /// The pseudonym, elgamal encrypted like the current one.
+    pub pseudonym: elgamal::Triple,
+
+    /// Proves [`Self::pseudonym`] was computed correctly, starting from the input of step
+    /// [`TranscryptionProof::step_count`]` - transcryption_proof.step_count()` of the current
+    /// pseudonym's transcryption proof: the transcryptors before that step had not changed their
+    /// secrets since this version.
+    pub transcryption_proof: TranscryptionProof,
+}
+
+impl PreviousPseudonym {
+    /// Checks that [`Self::pseudonym`] was obtained from `start`, the input of the current
+    /// pseudonym's `transcryption_proof`.
+    pub fn verify(
+        &self,
+        start: &elgamal::Triple,
+        transcryption_proof: &TranscryptionProof,
+    ) -> bool {
+        transcryption_proof
+            .step_count()
+            .checked_sub(self.transcryption_proof.step_count())
+            .and_then(|step| transcryption_proof.input_of_step(start, step))
+            .is_some_and(|start| self.transcryption_proof.verify(start, &self.pseudonym))
+    }
+}
+
+/// Proof that an elgamal triple was obtained from another by the transcryptors, each applying
+/// [`elgamal::Triple::rsk`] in turn.  See "Proofs of correct transcryption" above.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TranscryptionProof {
+    /// The results of all but the last step
+    pub intermediate_results: Vec<elgamal::Triple>,
+
+    /// One proof for each step
+    pub proofs: Vec<elgamal::rsk::Proof>,
+}
+
+impl TranscryptionProof {
+    /// Starts a transcryption proof with the proof of its first step.
+    pub fn new(proof: elgamal::rsk::Proof) -> Self {
+        Self {
+            intermediate_results: vec![],
+            proofs: vec![proof],
+        }
+    }
+
+    /// Adds a step, whose input, `input`, was the result of the step before.
+    pub fn push(&mut self, input: elgamal::Triple, proof: elgamal::rsk::Proof) {
+        self.intermediate_results.push(input);
+        self.proofs.push(proof);
+    }
+
+    /// The number of steps.
+    pub fn step_count(&self) -> usize {
+        self.proofs.len()
+    }
+
+    /// Returns the input of step number `step`, `start` being the input of the first step.
+    pub fn input_of_step<'a>(
+        &'a self,
+        start: &'a elgamal::Triple,
+        step: usize,
+    ) -> Option<&'a elgamal::Triple> {
+        match step {
+            0 => Some(start),
+            _ => self.intermediate_results.get(step - 1),
+        }
+    }
+
+    /// Checks that `result` was obtained from `start`.
+    pub fn verify(&self, start: &elgamal::Triple, result: &elgamal::Triple) -> bool {
+        if self.proofs.len() != self.intermediate_results.len() + 1 {
+            return false;
+        }
+
+        let inputs = std::iter::once(start).chain(self.intermediate_results.iter());
+        let results = self
+            .intermediate_results
+            .iter()
+            .chain(std::iter::once(result));
+
+        inputs
+            .zip(results)
+            .zip(self.proofs.iter())
+            .all(|((input, result), proof)| proof.verify(input, result))
+    }
+}
+
+/// An [`EncryptedHubPseudonymPackage`] in the making, passed from one transcryptor to the next
+/// when the transcryptor role is split; see [`tr::EhppResp::Continue`].
+///
+/// NB: travels inside [`Sealed`], which uses postcard, see [`EncryptedHubPseudonymPackage`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PartialEhpp {
+    /// The polymorphic pseudonym multiplied by the pseudonymisation factors of the preceding
+    /// transcryptors, and elgamal encrypted for the master encryption key parts of the others.
+    pub polymorphic_pseudonym: elgamal::Triple,
+
+    /// Like [`Self::polymorphic_pseudonym`], but using the pseudonymisation factor secrets the
+    /// preceding transcryptors used before each version in which one of them changed theirs,
+    /// most recent version first.  Empty when none of them are changing theirs.
+    pub previous_polymorphic_pseudonyms: Vec<PreviousPseudonym>,
+
+    /// The hub the pseudonym is for, see [`tr::EhppReq::hub`].
+    pub hub: id::Id,
+
+    /// Copied to [`EncryptedHubPseudonymPackage::hub_nonce`].
+    pub hub_nonce: hub::EnterNonce,
+
+    /// Copied to [`EncryptedHubPseudonymPackage::phc_nonce`].
+    pub phc_nonce: phc::user::PpNonce,
+
+    /// Copied to [`EncryptedHubPseudonymPackage::hub_id_mac`].
+    pub hub_id_mac: Option<id::Id>,
+
+    /// Proves [`Self::polymorphic_pseudonym`] was computed correctly.
+    pub transcryption_proof: TranscryptionProof,
+}
+
+having_message_code!(PartialEhpp, PartialEhpp);
+
+/// A [`PartialEhpp`] sealed for the next transcryptor, under a shared secret encapsulated against
+/// its [`encap_key`](crate::servers::constellation::CoTranscryptor::encap_key).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SealedPartialEhpp {
+    /// The shared secret, encapsulated for the next transcryptor.
+    pub ss_encap: kem::CiphertextBytes,
+
+    /// Sealed under [`crate::phcrypto::sealing_secret`] of the shared secret.
+    pub sealed: Sealed<PartialEhpp>,
+}
+
 /// Returned (signed) by [`phc::user::HhppEP`], needed for [`hub::EnterCompleteEP`].
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(deny_unknown_fields)]
@@ -214,6 +409,13 @@ pub struct HashedHubPseudonymPackage {
     /// [`hub::HubMacKey::mac`].
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub hub_id_mac: Option<id::Id>,
+
+    /// The user's previous hashed hub pseudonyms, most recent first, set only while the
+    /// transcryptors' pseudonymisation factor secrets are being changed.  The hub should move the
+    /// account of the user with the first of these previous pseudonyms it knows (if any) to
+    /// [`Self::hashed_hub_pseudonym`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub previous_hashed_hub_pseudonyms: Vec<CurvePoint>,
 }
 
 impl Signable for HashedHubPseudonymPackage {
@@ -5,6 +5,7 @@ use crate::api::*;
 use actix_web::http;
 use serde::{Deserialize, Serialize};
 
+use crate::common::elgamal;
 use crate::id;
 
 /// Requests an [`sso::EncryptedHubPseudonymPackage`].
@@ -50,6 +51,186 @@ pub struct EhppReq {
 pub enum EhppResp {
     RetryWithNewPpp,
 
+    /// The hub has been suspended at PubHubs Central, see [`phc::hub::RevocationsEP`].
+    HubSuspended,
+
     /// The requested encrypted hub pseudonym package
     Success(Sealed<sso::EncryptedHubPseudonymPackage>),
+
+    /// The transcryptor role is split, and the next co-transcryptor in
+    /// [`Constellation::co_transcryptors`] must finish the package: pass this on to its
+    /// [`ContinueEhppEP`].
+    ///
+    /// [`Constellation::co_transcryptors`]: crate::servers::constellation::Inner::co_transcryptors
+    Continue(sso::SealedPartialEhpp),
+}
+
+/// Continues the creation of an [`sso::EncryptedHubPseudonymPackage`] started by the preceding
+/// transcryptor, see [`EhppResp::Continue`].  Only provided by co-transcryptors.
+pub struct ContinueEhppEP {}
+impl EndpointDetails for ContinueEhppEP {
+    type RequestType = ContinueEhppReq;
+    type ResponseType = Result<EhppResp>;
+
+    const METHOD: http::Method = http::Method::POST;
+    const PATH: &'static str = ".ph/ehpp/continue";
+}
+
+/// Request type of [`ContinueEhppEP`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+#[serde(rename = "snake_case")]
+pub struct ContinueEhppReq {
+    /// From [`EhppResp::Continue`], as returned by the preceding transcryptor.
+    pub partial_ehpp: sso::SealedPartialEhpp,
+}
+
+/// Used by PHC to have a co-transcryptor multiply its master encryption key part into the product
+/// of the master encryption key parts of the transcryptors preceding it.  Only provided by
+/// co-transcryptors.
+///
+/// Each co-transcryptor only multiplies this one product, which it checks against the
+/// constellation and the proofs in [`MasterEncKeyToScale`], and caches the result for as long as
+/// it is on this constellation.  So PHC can not use it to multiply other points, such as those in
+/// a polymorphic pseudonym, by the co-transcryptor's part.
+pub struct ScaleMasterEncKeyEP {}
+impl EndpointDetails for ScaleMasterEncKeyEP {
+    type RequestType = ScaleMasterEncKeyReq;
+    type ResponseType = Result<ScaleMasterEncKeyResp>;
+
+    const METHOD: http::Method = http::Method::POST;
+    const PATH: &'static str = ".ph/scale-master-enc-key";
+}
+
+/// Request type of [`ScaleMasterEncKeyEP`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+#[serde(rename = "snake_case")]
+pub struct ScaleMasterEncKeyReq {
+    /// Sealed under the secret the co-transcryptor shares with PHC.
+    pub key: Sealed<MasterEncKeyToScale>,
+}
+
+/// Returned by [`ScaleMasterEncKeyEP`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+#[serde(rename = "snake_case")]
+#[must_use]
+pub enum ScaleMasterEncKeyResp {
+    /// The co-transcryptor could not open [`ScaleMasterEncKeyReq::key`], probably because it
+    /// has not adopted PHC's current constellation yet.
+    RetryWithNewConstellation,
+
+    /// Sealed under the secret the co-transcryptor shares with PHC.
+    Success(Sealed<ScaledMasterEncKey>),
+}
+
+/// What PHC sends to the co-transcryptor `i` via [`ScaleMasterEncKeyEP`]: the master encryption
+/// key part `x_T1 B` of the transcryptor, followed by the products `x_Tj ... x_T1 B` of the
+/// co-transcryptors `j < i`, each with a proof that it is a multiple of its predecessor (or of
+/// `B`).
+///
+/// The co-transcryptor checks that `x_T1 B` is the part committed to in the constellation.  Since
+/// the scalars by which the products were obtained are known to their transcryptors, the product
+/// the co-transcryptor scales does not allow a colluding PHC and transcryptor to decrypt anything
+/// they could not decrypt before.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct MasterEncKeyToScale {
+    pub chain: Vec<ScaledMasterEncKey>,
+}
+
+having_message_code!(MasterEncKeyToScale, MasterEncKeyToScale);
+
+/// The point `key`, with a proof that it is a multiple of the preceding point in
+/// [`MasterEncKeyToScale::chain`] (or of `B`, for the first point).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ScaledMasterEncKey {
+    pub key: elgamal::PublicKey,
+    pub proof: elgamal::scaling::Proof,
+}
+
+having_message_code!(ScaledMasterEncKey, ScaledMasterEncKey);
+
+impl MasterEncKeyToScale {
+    /// Checks that [`Self::chain`] starts with the transcryptor's master encryption key part,
+    /// whose hash is `transcryptor_master_enc_key_part_hash`, and that all its proofs hold.
+    /// Returns the last point of the chain if so.
+    pub fn check(
+        &self,
+        transcryptor_master_enc_key_part_hash: &id::Id,
+    ) -> Option<&elgamal::PublicKey> {
+        let first = self.chain.first()?;
+
+        if crate::phcrypto::master_enc_key_part_hash(&first.key)
+            != *transcryptor_master_enc_key_part_hash
+        {
+            return None;
+        }
+
+        let mut input = elgamal::PublicKey::generator();
+
+        for ScaledMasterEncKey { key, proof } in self.chain.iter() {
+            if !proof.verify(&input, key) {
+                return None;
+            }
+            input = key.clone();
+        }
+
+        Some(&self.chain.last()?.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A co-transcryptor must not scale points other than the chained master encryption key, such
+    /// as those of a polymorphic pseudonym.
+    #[test]
+    fn master_enc_key_to_scale_check() {
+        let (t1, t2) = (elgamal::PrivateKey::random(), elgamal::PrivateKey::random());
+        let t1_hash = crate::phcrypto::master_enc_key_part_hash(t1.public_key());
+
+        let link = |sk: &elgamal::PrivateKey, input: &elgamal::PublicKey| {
+            let (key, proof) = sk.scale_proven(input);
+            ScaledMasterEncKey { key, proof }
+        };
+
+        let first = link(&t1, &elgamal::PublicKey::generator());
+        let second = link(&t2, &first.key);
+
+        let chain = |chain: Vec<ScaledMasterEncKey>| MasterEncKeyToScale { chain };
+
+        assert_eq!(
+            chain(vec![first.clone()]).check(&t1_hash),
+            Some(t1.public_key())
+        );
+        assert_eq!(
+            chain(vec![first.clone(), second.clone()]).check(&t1_hash),
+            Some(&second.key)
+        );
+
+        // not starting with the transcryptor's part
+        assert_eq!(chain(vec![second.clone()]).check(&t1_hash), None);
+        assert_eq!(chain(vec![]).check(&t1_hash), None);
+
+        // a point that is not a multiple of its predecessor by a known scalar
+        let pp = elgamal::PrivateKey::random()
+            .public_key()
+            .encrypt(elgamal::random_point());
+        let smuggled = ScaledMasterEncKey {
+            key: pp.pk(),
+            proof: second.proof.clone(),
+        };
+        assert_eq!(chain(vec![first.clone(), smuggled]).check(&t1_hash), None);
+
+        // the transcryptor's part without a proof of knowledge of its discrete logarithm
+        let t1_unproven = ScaledMasterEncKey {
+            key: t1.public_key().clone(),
+            proof: second.proof,
+        };
+        assert_eq!(chain(vec![t1_unproven]).check(&t1_hash), None);
+    }
 }
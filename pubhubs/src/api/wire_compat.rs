@@ -144,15 +144,21 @@ fn wire_compat() {
         inner: Inner {
             transcryptor_url: tr_url.clone(),
             transcryptor_verifying_key: vkb.clone(),
+            transcryptor_next_verifying_key: None,
             transcryptor_master_enc_key_part_hash: id,
+            transcryptor_previous_master_enc_key_part_hash: None,
             transcryptor_encap_key_id: id,
             transcryptor_ss_encap: ct.clone(),
+            co_transcryptors: vec![],
             phc_url: phc_url.clone(),
             phc_jwt_key: Ed25519VerifyingKeyHex::default(),
             phc_verifying_key: vkb.clone(),
+            phc_next_verifying_key: None,
             phc_master_enc_key_part_hash: id,
+            phc_previous_master_enc_key_part_hash: None,
             auths_url: auths_url.clone(),
             auths_verifying_key: vkb.clone(),
+            auths_next_verifying_key: None,
             auths_encap_key_id: id,
             auths_ss_encap: ct.clone(),
             global_client_url: gc_url,
@@ -166,8 +172,12 @@ fn wire_compat() {
         version: Some(v.clone()),
         phc_url: phc_url.clone(),
         verifying_key: vkb.clone(),
+        next_verifying_key: None,
         master_enc_key_part_hash: None,
         master_enc_key_part_sealed: None,
+        master_enc_key_part_proof: None,
+        previous_master_enc_key_part_hash: None,
+        previous_master_enc_key_part_sealed: None,
         encap_key: None,
         constellation_or_id: Some(ConstellationOrId::Constellation(Box::new(constellation))),
     };
@@ -178,8 +188,12 @@ fn wire_compat() {
         version: Some(v.clone()),
         phc_url: phc_url.clone(),
         verifying_key: vkb.clone(),
+        next_verifying_key: None,
         master_enc_key_part_hash: Some(id),
         master_enc_key_part_sealed: Some(sealed_part),
+        master_enc_key_part_proof: None,
+        previous_master_enc_key_part_hash: None,
+        previous_master_enc_key_part_sealed: None,
         encap_key: Some(encap_key.clone()),
         constellation_or_id: Some(ConstellationOrId::Id { id }),
     };
@@ -190,8 +204,12 @@ fn wire_compat() {
         version: Some(v),
         phc_url,
         verifying_key: vkb,
+        next_verifying_key: None,
         master_enc_key_part_hash: None,
         master_enc_key_part_sealed: None,
+        master_enc_key_part_proof: None,
+        previous_master_enc_key_part_hash: None,
+        previous_master_enc_key_part_sealed: None,
         encap_key: Some(encap_key),
         constellation_or_id: None,
     };
@@ -23,7 +23,8 @@ pub struct Type {
     /// Whether [`Attr`]ibutes of this type can be used to identify a users.
     pub identifying: bool,
 
-    /// The different 'regular' ways this attribute can be obtained via [`crate::api::auths::AuthStartEP`].
+    /// The different 'regular' ways this attribute can be obtained via [`crate::api::auths::AuthStartEP`],
+    /// most preferred first (see [`crate::api::auths::AuthStartReq::sources`]).
     ///
     /// Some attributes, like the pubhubs card attribute, can also be obtained in irregular ways.
     pub sources: Vec<SourceDetails>,
@@ -33,6 +34,135 @@ pub struct Type {
     /// added to a user account.  Pubhubs card attribute types have this flag set - to get an
     /// pubhubs card attribute instance that is addable, use [`crate::api::auths::CardEP`].
     pub not_addable_by_default: bool,
+
+    /// How the values of attributes of this type are normalized by the authentication server
+    /// before they are signed.  Attributes with the same normalized value are the same attribute,
+    /// so changing the normalizer of an existing attribute type changes [`Attr::id`]s, see
+    /// [`Attr::unnormalized_value`].
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Normalizer::is_verbatim")]
+    pub normalizer: Normalizer,
+}
+
+/// Type of [`Type::normalizer`].
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Normalizer {
+    /// The value is used as is.
+    #[default]
+    Verbatim,
+
+    /// Leading and trailing whitespace is removed, and the value is brought into Unicode
+    /// normalization form C (NFC).
+    Text,
+
+    /// Like [`Normalizer::Text`], but also lowercases the address.  For the domains in
+    /// [`SUBADDRESSING_DOMAINS`], which deliver `local+tag@domain` to `local@domain`, the `+tag`
+    /// is removed from the local part; elsewhere a `+` may be part of a different mailbox.  For
+    /// `gmail.com` (and `googlemail.com`) addresses, which ignore dots in the local part, dots are
+    /// removed as well.
+    Email,
+
+    /// Brings phone numbers into E.164 format (`+` followed by at most 15 digits) by removing
+    /// spaces, dashes, dots and parentheses, and replacing a leading `00` by `+`.  A leading
+    /// `0` (a trunk prefix) is replaced by `+` followed by `default_country_code`.
+    Phone {
+        /// Country calling code (like `31` for the Netherlands) for numbers without one.
+        #[serde(default)]
+        default_country_code: Option<u16>,
+    },
+}
+
+/// Email domains known to deliver `local+tag@domain` to `local@domain`, so that
+/// [`Normalizer::Email`] may remove the `+tag`.
+pub const SUBADDRESSING_DOMAINS: &[&str] = &[
+    "gmail.com",
+    "googlemail.com",
+    "outlook.com",
+    "hotmail.com",
+    "live.com",
+    "icloud.com",
+    "me.com",
+    "proton.me",
+    "protonmail.com",
+    "fastmail.com",
+];
+
+impl Normalizer {
+    fn is_verbatim(&self) -> bool {
+        matches!(self, Normalizer::Verbatim)
+    }
+
+    /// Normalizes the given attribute value, failing if it is not of the expected form.
+    pub fn normalize(&self, value: &str) -> anyhow::Result<String> {
+        let text = || -> String {
+            icu_normalizer::ComposingNormalizerBorrowed::new_nfc()
+                .normalize(value.trim())
+                .into_owned()
+        };
+
+        match self {
+            Normalizer::Verbatim => Ok(value.to_string()),
+            Normalizer::Text => Ok(text()),
+            Normalizer::Email => {
+                let address = text().to_lowercase();
+
+                let Some((local, domain)) = address.rsplit_once('@') else {
+                    anyhow::bail!("email address without @");
+                };
+
+                let local = if SUBADDRESSING_DOMAINS.contains(&domain) {
+                    local.split_once('+').map_or(local, |(local, _tag)| local)
+                } else {
+                    local
+                };
+
+                let (local, domain): (String, &str) = match domain {
+                    "gmail.com" | "googlemail.com" => (local.replace('.', ""), "gmail.com"),
+                    _ => (local.to_string(), domain),
+                };
+
+                anyhow::ensure!(
+                    !local.is_empty() && !domain.is_empty(),
+                    "email address with empty local part or domain"
+                );
+
+                Ok(format!("{local}@{domain}"))
+            }
+            Normalizer::Phone {
+                default_country_code,
+            } => {
+                let number: String = value
+                    .trim()
+                    .chars()
+                    .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')'))
+                    .collect();
+
+                let digits: String = if let Some(digits) = number.strip_prefix('+') {
+                    digits.to_string()
+                } else if let Some(digits) = number.strip_prefix("00") {
+                    digits.to_string()
+                } else if let Some(digits) = number.strip_prefix('0') {
+                    let Some(country_code) = default_country_code else {
+                        anyhow::bail!("phone number without country code");
+                    };
+                    format!("{country_code}{digits}")
+                } else {
+                    anyhow::bail!("phone number without country code or trunk prefix");
+                };
+
+                anyhow::ensure!(
+                    !digits.is_empty()
+                        && digits.len() <= 15
+                        && digits.chars().all(|c| c.is_ascii_digit())
+                        && !digits.starts_with('0'),
+                    "not a valid phone number"
+                );
+
+                Ok(format!("+{digits}"))
+            }
+        }
+    }
 }
 
 impl std::fmt::Display for Type {
@@ -45,12 +175,47 @@ impl Type {
     /// Iterates over all the [`yivi::AttributeTypeIdentifier`]s that can be used to obtain this
     /// attribite type.
     pub fn yivi_attr_type_ids(&self) -> impl Iterator<Item = &yivi::AttributeTypeIdentifier> {
-        #[expect(clippy::unnecessary_filter_map)] // remove when we get more sources
         self.sources.iter().filter_map(|source| match source {
             SourceDetails::Yivi { attr_type_id } => Some(attr_type_id),
+            SourceDetails::Email {} | SourceDetails::Oidc(..) | SourceDetails::WebAuthn {} => None,
         })
     }
 
+    /// Returns the details of the OpenID Connect provider this attribute type can be obtained
+    /// from, if any.  When several are listed, only the first is used.
+    pub fn oidc_source(&self) -> Option<&OidcSource> {
+        self.sources.iter().find_map(|source| match source {
+            SourceDetails::Oidc(oidc) => Some(oidc),
+            _ => None,
+        })
+    }
+
+    /// Whether this attribute type can be obtained via the given [`Source`].
+    pub fn has_source(&self, source: Source) -> bool {
+        self.source_rank(source).is_some()
+    }
+
+    /// The position of the given [`Source`] among [`Type::sources`], lower meaning more
+    /// preferred, or `None` when this attribute type cannot be obtained via `source`.
+    pub fn source_rank(&self, source: Source) -> Option<usize> {
+        self.sources
+            .iter()
+            .position(|source_details| source_details.source() == source)
+    }
+
+    /// Checks that this attribute type is consistent.  In particular, attribute types that can be
+    /// obtained via [`SourceDetails::WebAuthn`] must not be [`Type::bannable`], because anyone can
+    /// create as many passkeys as they like.
+    pub(crate) fn check(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            !(self.bannable && self.has_source(Source::WebAuthn)),
+            "attribute type {} can be obtained via webauthn, and so must not be bannable",
+            self.handles.preferred()
+        );
+
+        Ok(())
+    }
+
     /// Removes the [`SourceDetails`] from [`Source`]s not accepted by `accept_source`.
     pub(crate) fn filter_sources(&mut self, accept_source: impl Fn(Source) -> bool) {
         self.sources
@@ -66,12 +231,52 @@ pub enum SourceDetails {
         /// The yivi attribute type identifier
         attr_type_id: yivi::AttributeTypeIdentifier,
     },
+
+    /// The attribute's value is an email address, obtained by having the end-user enter a
+    /// one-time code sent to it.
+    Email {},
+
+    /// The attribute's value is a claim from an ID token issued by an OpenID Connect provider,
+    /// such as the identity provider of a university or municipality.
+    Oidc(OidcSource),
+
+    /// The attribute's value is the public key of a passkey, obtained by having the end-user's
+    /// authenticator sign a challenge (a WebAuthn assertion).  Since a passkey cannot be
+    /// vouched for by anyone but its owner, such attribute types must not be bannable (see
+    /// [`Type::check`]).
+    #[serde(rename = "webauthn")]
+    WebAuthn {},
+}
+
+/// Details of [`SourceDetails::Oidc`].
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct OidcSource {
+    /// The issuer identifier of the OpenID Connect provider, used to discover its endpoints via
+    /// `{issuer}/.well-known/openid-configuration`.  Must match the `iss` claim of its ID tokens.
+    pub issuer: url::Url,
+
+    /// The client id under which the authentication server is registered with the provider
+    pub client_id: String,
+
+    /// The name of the (string-valued) ID token claim that provides the attribute's value,
+    /// for example `email` or `eduPersonPrincipalName`.
+    pub claim: String,
+
+    /// Scopes to request besides `openid`, for example `email`, that might be needed to have the
+    /// provider include [`claim`](Self::claim) in the ID token.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub scopes: Vec<String>,
 }
 
 impl SourceDetails {
     pub fn source(&self) -> Source {
         match &self {
             SourceDetails::Yivi { .. } => Source::Yivi,
+            SourceDetails::Email { .. } => Source::Email,
+            SourceDetails::Oidc(..) => Source::Oidc,
+            SourceDetails::WebAuthn { .. } => Source::WebAuthn,
         }
     }
 }
@@ -79,6 +284,9 @@ impl SourceDetails {
 #[derive(serde::Deserialize, serde::Serialize, Hash, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Source {
     Yivi,
+    Email,
+    Oidc,
+    WebAuthn,
 }
 
 impl crate::map::Handled for Type {
@@ -115,6 +323,24 @@ pub struct Attr {
     #[serde(skip_serializing_if = "std::ops::Not::not")]
     #[serde(default)]
     pub not_addable: bool,
+
+    /// The [`Source`] this attribute was obtained from via [`crate::api::auths::AuthStartEP`].
+    ///
+    /// Not set for attributes obtained in other ways, such as the pubhubs card attribute issued
+    /// via [`crate::api::auths::CardEP`].  Does not influence [`Attr::id`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub source: Option<Source>,
+
+    /// The value as obtained from the source, when it differs from [`Attr::value`] due to
+    /// [`Type::normalizer`].
+    ///
+    /// Pubhubs central uses it to find the [`AttrState`] of attributes registered before their
+    /// type was normalized:  when there is no [`AttrState`] for the normalized value, but there
+    /// is one for the unnormalized value, the latter's [`Attr::id`] is used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub unnormalized_value: Option<String>,
 }
 
 impl Attr {
@@ -123,6 +349,21 @@ impl Attr {
     pub fn id(&self, secret: impl secret::DigestibleSecret) -> Id {
         phcrypto::attr_id(self, secret)
     }
+
+    /// Derives the identifier this attribute had before its type was normalized, if it differs
+    /// from [`Attr::id`], see [`Attr::unnormalized_value`].
+    pub fn unnormalized_id(&self, secret: impl secret::DigestibleSecret) -> Option<Id> {
+        let value = self.unnormalized_value.as_ref()?;
+
+        Some(phcrypto::attr_id(
+            &Attr {
+                value: value.clone(),
+                unnormalized_value: None,
+                ..self.clone()
+            },
+            secret,
+        ))
+    }
 }
 
 // So Signed<Attr> can be used.
@@ -141,7 +382,8 @@ pub struct AttrState {
     ///
     /// Only identifies the user if the user lists this attribute among its identifying attributes.
     ///
-    /// Once set, this should never be unset.  This prevents impersonation of a user when
+    /// Once set, this should only be unset when the user deletes their account (see
+    /// [`crate::api::phc::user::DeleteEP`]).  This prevents impersonation of a user when
     /// they remove their id.
     #[serde(default)]
     pub may_identify_user: Option<Id>,
@@ -171,3 +413,100 @@ impl AttrState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalizer() {
+        let text = Normalizer::Text;
+        assert_eq!(text.normalize("  Cafe\u{0301} ").unwrap(), "Caf\u{e9}");
+
+        let email = Normalizer::Email;
+        for (value, normalized) in [
+            ("Alice@Example.com", "alice@example.com"),
+            (" alice+pubhubs@outlook.com\n", "alice@outlook.com"),
+            ("alice+pubhubs@example.com", "alice+pubhubs@example.com"),
+            ("a.lice@example.com", "a.lice@example.com"),
+            ("A.Lice+x@GoogleMail.com", "alice@gmail.com"),
+            ("+tag@example.com", "+tag@example.com"),
+        ] {
+            assert_eq!(email.normalize(value).unwrap(), normalized, "{value}");
+        }
+        email.normalize("alice").unwrap_err();
+        email.normalize("+tag@gmail.com").unwrap_err();
+
+        let phone = Normalizer::Phone {
+            default_country_code: Some(31),
+        };
+        for value in [
+            "0612345678",
+            "06-12345678",
+            "+31 6 1234 5678",
+            "0031 6 12345678",
+        ] {
+            assert_eq!(phone.normalize(value).unwrap(), "+31612345678", "{value}");
+        }
+        phone.normalize("612345678").unwrap_err();
+        phone.normalize("+31 6 1234 567x").unwrap_err();
+        phone.normalize("+1234567890123456").unwrap_err();
+        Normalizer::Phone {
+            default_country_code: None,
+        }
+        .normalize("0612345678")
+        .unwrap_err();
+
+        assert_eq!(Normalizer::Verbatim.normalize(" A ").unwrap(), " A ");
+    }
+
+    #[test]
+    fn test_check() {
+        let passkey = |bannable: bool| -> Type {
+            toml::from_str(&format!(
+                r#"
+                id = "{id}"
+                handles = ["passkey"]
+                bannable = {bannable}
+                identifying = true
+                sources = [ {{ webauthn = {{}} }}, {{ email = {{}} }} ]
+                "#,
+                id = Id::random()
+            ))
+            .unwrap()
+        };
+
+        passkey(false).check().unwrap();
+        passkey(true).check().unwrap_err();
+    }
+
+    #[test]
+    fn test_unnormalized_id() {
+        let secret: &[u8] = b"secret";
+
+        let attr = Attr {
+            attr_type: Id::random(),
+            value: "alice@example.com".to_string(),
+            bannable: false,
+            not_identifying: false,
+            not_addable: false,
+            source: Some(Source::Email),
+            unnormalized_value: None,
+        };
+
+        assert_eq!(attr.unnormalized_id(secret), None);
+
+        let unnormalized = Attr {
+            value: "Alice@Example.com".to_string(),
+            ..attr.clone()
+        };
+
+        let attr = Attr {
+            unnormalized_value: Some(unnormalized.value.clone()),
+            ..attr
+        };
+
+        assert_eq!(attr.unnormalized_id(secret), Some(unnormalized.id(secret)));
+        assert_ne!(attr.id(secret), unnormalized.id(secret));
+    }
+}
@@ -26,14 +26,22 @@ impl AdminArgs {
         env_logger::init();
         let config = self.common.load_config()?;
 
+        let ctx = AdminContext {
+            config,
+            server: self.server,
+            admin_key: crate::misc::jwt::HS256(self.admin_key.into_inner().into_vec()),
+            url: tokio::sync::OnceCell::const_new(),
+            client: client::Client::builder().agent(client::Agent::Cli).finish(),
+        };
+
         match self.command {
-            Commands::Config(args) => args.run(AdminContext {
-                config,
-                server: self.server,
-                admin_key: crate::misc::jwt::HS256(self.admin_key.into_inner().into_vec()),
-                url: tokio::sync::OnceCell::const_new(),
-                client: client::Client::builder().agent(client::Agent::Cli).finish(),
-            }),
+            Commands::Config(args) => args.run(ctx),
+            Commands::StageSigningKey(args) => args.run(ctx),
+            Commands::Ban(args) => args.run(ctx),
+            Commands::Unban(args) => args.run(ctx),
+            Commands::BanLog(args) => args.run(ctx),
+            Commands::RevokeCards(args) => args.run(ctx),
+            Commands::Hubs(args) => args.run(ctx),
         }
     }
 }
@@ -70,6 +78,21 @@ impl AdminContext {
         }
     }
 
+    /// Runs the given future to completion, after checking that the command is sent to PubHubs
+    /// Central.
+    fn block_on_phc(&self, fut: impl std::future::Future<Output = Result<()>>) -> Result<()> {
+        anyhow::ensure!(
+            self.server == servers::Name::PubhubsCentral,
+            "this command is only supported by {}",
+            servers::Name::PubhubsCentral
+        );
+
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?
+            .block_on(tokio::task::LocalSet::new().run_until(fut))
+    }
+
     async fn get_url(&self) -> Result<&url::Url> {
         self.url
             .get_or_try_init(|| async {
@@ -103,6 +126,41 @@ enum Commands {
     /// Retrieves the current configuration,
     /// or change it, using the `update` subcommand.
     Config(ConfigArgs),
+
+    /// Stages a new signing key: it is published via discovery right away, but only used for
+    /// signing after some time.  Until then, and afterwards until the configuration file is
+    /// updated, signatures by either the current or the new signing key are accepted.
+    ///
+    /// Like `config update`, this changes the configuration in-memory only;  add the printed
+    /// `next_signing_key` to the configuration file too.
+    StageSigningKey(StageSigningKeyArgs),
+
+    /// Bans an attribute, and the users that provided it as bannable attribute.
+    ///
+    /// Only supported by PubHubs Central.
+    Ban(BanArgs),
+
+    /// Lifts the ban on an attribute, and unbans the users that provided it as bannable attribute
+    /// unless another of their bannable attributes is banned.
+    ///
+    /// Only supported by PubHubs Central.
+    Unban(UnbanArgs),
+
+    /// Shows who (un)banned an attribute, when, and why.
+    ///
+    /// Only supported by PubHubs Central.
+    BanLog(BanLogArgs),
+
+    /// Revokes the PubHubs cards of a banned user, so they can no longer be used to log in.
+    ///
+    /// Only supported by PubHubs Central.
+    RevokeCards(RevokeCardsArgs),
+
+    /// Adds, updates, retires or suspends hubs, or approves or rejects hubs that asked to be
+    /// registered.
+    ///
+    /// Only supported by PubHubs Central.
+    Hubs(HubsArgs),
 }
 
 #[derive(clap::Args, Debug)]
@@ -229,6 +287,55 @@ impl ConfigUpdateArgs {
     }
 }
 
+#[derive(clap::Args, Debug)]
+pub struct StageSigningKeyArgs {
+    /// After how much time to start signing using the new key, e.g. '1h'.  Should leave enough
+    /// time for discovery to propagate the new verifying key to the other servers and the hubs.
+    #[arg(long, value_name = "DURATION", default_value = "1h", value_parser = humantime::parse_duration)]
+    active_in: core::time::Duration,
+}
+
+impl StageSigningKeyArgs {
+    fn run(self, ctx: AdminContext) -> Result<()> {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?
+            .block_on(tokio::task::LocalSet::new().run_until(self.run_async(ctx)))
+    }
+
+    async fn run_async(self, ctx: AdminContext) -> Result<()> {
+        let signing_key = api::SigningKey::generate()
+            .map_err(|_| anyhow::anyhow!("failed to generate signing key"))?;
+
+        let next_signing_key = servers::config::NextSigningKey {
+            signing_key: signing_key.encode(),
+            active_from: api::NumericDate::now().add_clamp(self.active_in.as_secs()),
+        };
+
+        let server_key = serde_json::to_value(ctx.server)?;
+        let server_key = server_key
+            .as_str()
+            .context("server name did not serialize to a string")?;
+
+        ConfigUpdateArgs {
+            pointer: format!("/{server_key}/next_signing_key"),
+            new_value: serde_json::to_value(&next_signing_key)?,
+        }
+        .run(ctx)
+        .await?;
+
+        log::info!(
+            "new signing key is used from {}; add the following to the configuration file",
+            next_signing_key.active_from.date()
+        );
+
+        println!("[{server_key}.next_signing_key]");
+        print!("{}", toml::to_string(&next_signing_key)?);
+
+        Ok(())
+    }
+}
+
 #[derive(clap::Args, Debug, Default)]
 pub struct ConfigGetArgs {}
 
@@ -243,3 +350,624 @@ impl ConfigGetArgs {
         Ok(())
     }
 }
+
+#[derive(clap::Args, Debug)]
+pub struct BanArgs {
+    /// Handle of the type of the attribute to ban, e.g. 'email'.
+    #[arg(value_name = "ATTR_TYPE")]
+    attr_type: crate::handle::Handle,
+
+    /// Value of the attribute to ban, e.g. 'user@example.com'.
+    #[arg(value_name = "VALUE")]
+    value: String,
+
+    /// Why the attribute is banned; recorded in the attribute's ban log.
+    #[arg(short, long, value_name = "REASON")]
+    reason: String,
+}
+
+impl BanArgs {
+    fn run(self, ctx: AdminContext) -> Result<()> {
+        ctx.block_on_phc(self.run_async(&ctx))
+    }
+
+    async fn run_async(self, ctx: &AdminContext) -> Result<()> {
+        let resp = ctx
+            .client
+            .query_with_retry::<api::admin::BanEP, _, _>(
+                ctx.get_url().await?,
+                &api::Signed::<api::admin::BanReq>::new(
+                    &ctx.admin_key,
+                    &api::admin::BanReq {
+                        attr_type: self.attr_type.clone(),
+                        value: self.value.clone(),
+                        reason: self.reason.clone(),
+                    },
+                    std::time::Duration::from_secs(10),
+                )?,
+            )
+            .await?;
+
+        match resp {
+            api::admin::BanResp::Success { banned_users } => {
+                log::info!(
+                    "banned attribute {} of type {}, and {} user(s):",
+                    self.value,
+                    self.attr_type,
+                    banned_users.len()
+                );
+
+                for user_id in banned_users {
+                    println!("{user_id}");
+                }
+
+                Ok(())
+            }
+            api::admin::BanResp::UnknownAttrType => {
+                anyhow::bail!("unknown attribute type {}", self.attr_type)
+            }
+            api::admin::BanResp::ResignRequest => {
+                anyhow::bail!("request expired unexpectedly quickly")
+            }
+            api::admin::BanResp::InvalidAdminKey => anyhow::bail!("invalid admin key"),
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub struct UnbanArgs {
+    /// Handle of the type of the attribute to unban, e.g. 'email'.
+    #[arg(value_name = "ATTR_TYPE")]
+    attr_type: crate::handle::Handle,
+
+    /// Value of the attribute to unban, e.g. 'user@example.com'.
+    #[arg(value_name = "VALUE")]
+    value: String,
+
+    /// Why the ban is lifted; recorded in the attribute's ban log.
+    #[arg(short, long, value_name = "REASON")]
+    reason: String,
+}
+
+impl UnbanArgs {
+    fn run(self, ctx: AdminContext) -> Result<()> {
+        ctx.block_on_phc(self.run_async(&ctx))
+    }
+
+    async fn run_async(self, ctx: &AdminContext) -> Result<()> {
+        let resp = ctx
+            .client
+            .query_with_retry::<api::admin::UnbanEP, _, _>(
+                ctx.get_url().await?,
+                &api::Signed::<api::admin::UnbanReq>::new(
+                    &ctx.admin_key,
+                    &api::admin::UnbanReq {
+                        attr_type: self.attr_type.clone(),
+                        value: self.value.clone(),
+                        reason: self.reason.clone(),
+                    },
+                    std::time::Duration::from_secs(10),
+                )?,
+            )
+            .await?;
+
+        match resp {
+            api::admin::UnbanResp::Success {
+                unbanned_users,
+                still_banned_users,
+            } => {
+                log::info!(
+                    "unbanned attribute {} of type {}, and {} user(s):",
+                    self.value,
+                    self.attr_type,
+                    unbanned_users.len()
+                );
+
+                for user_id in unbanned_users {
+                    println!("{user_id}");
+                }
+
+                for user_id in still_banned_users {
+                    log::warn!("user {user_id} remains banned by another attribute");
+                }
+
+                Ok(())
+            }
+            api::admin::UnbanResp::UnknownAttrType => {
+                anyhow::bail!("unknown attribute type {}", self.attr_type)
+            }
+            api::admin::UnbanResp::ResignRequest => {
+                anyhow::bail!("request expired unexpectedly quickly")
+            }
+            api::admin::UnbanResp::InvalidAdminKey => anyhow::bail!("invalid admin key"),
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub struct BanLogArgs {
+    #[command(subcommand)]
+    command: BanLogCommands,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum BanLogCommands {
+    /// Shows the ban log of a single attribute.
+    Attr {
+        /// Handle of the type of the attribute, e.g. 'email'.
+        #[arg(value_name = "ATTR_TYPE")]
+        attr_type: crate::handle::Handle,
+
+        /// Value of the attribute, e.g. 'user@example.com'.
+        #[arg(value_name = "VALUE")]
+        value: String,
+    },
+
+    /// Shows the ban logs of all the attributes that could ban a user.
+    User {
+        /// Id of the user, as returned by the `ban` command.
+        #[arg(value_name = "USER_ID")]
+        user_id: crate::id::Id,
+    },
+}
+
+impl BanLogArgs {
+    fn run(self, ctx: AdminContext) -> Result<()> {
+        ctx.block_on_phc(self.run_async(&ctx))
+    }
+
+    async fn run_async(self, ctx: &AdminContext) -> Result<()> {
+        let req = match self.command {
+            BanLogCommands::Attr { attr_type, value } => {
+                api::admin::BanLogReq::Attr { attr_type, value }
+            }
+            BanLogCommands::User { user_id } => api::admin::BanLogReq::User(user_id),
+        };
+
+        let resp = ctx
+            .client
+            .query_with_retry::<api::admin::BanLogEP, _, _>(
+                ctx.get_url().await?,
+                &api::Signed::<api::admin::BanLogReq>::new(
+                    &ctx.admin_key,
+                    &req,
+                    std::time::Duration::from_secs(10),
+                )?,
+            )
+            .await?;
+
+        match resp {
+            api::admin::BanLogResp::Success { records } => {
+                let stdout = std::io::stdout().lock();
+
+                serde_json::to_writer_pretty(stdout, &records)?;
+
+                Ok(())
+            }
+            api::admin::BanLogResp::UnknownAttrType => anyhow::bail!("unknown attribute type"),
+            api::admin::BanLogResp::UnknownUser => anyhow::bail!("unknown user"),
+            api::admin::BanLogResp::ResignRequest => {
+                anyhow::bail!("request expired unexpectedly quickly")
+            }
+            api::admin::BanLogResp::InvalidAdminKey => anyhow::bail!("invalid admin key"),
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RevokeCardsArgs {
+    /// Id of the banned user, as returned by the `ban` command.
+    #[arg(value_name = "USER_ID")]
+    user_id: crate::id::Id,
+}
+
+impl RevokeCardsArgs {
+    fn run(self, ctx: AdminContext) -> Result<()> {
+        ctx.block_on_phc(self.run_async(&ctx))
+    }
+
+    async fn run_async(self, ctx: &AdminContext) -> Result<()> {
+        let resp = ctx
+            .client
+            .query_with_retry::<api::admin::RevokeCardsEP, _, _>(
+                ctx.get_url().await?,
+                &api::Signed::<api::admin::RevokeCardsReq>::new(
+                    &ctx.admin_key,
+                    &api::admin::RevokeCardsReq { user: self.user_id },
+                    std::time::Duration::from_secs(10),
+                )?,
+            )
+            .await?;
+
+        match resp {
+            api::admin::RevokeCardsResp::Success {
+                card_pseud,
+                merged_card_pseuds,
+                revoked_at_yivi,
+            } => {
+                println!("revoked cards with registration pseudonym {card_pseud}");
+                for card_pseud in merged_card_pseuds {
+                    println!(
+                        "revoked cards of a merged account with registration pseudonym {card_pseud}"
+                    );
+                }
+                if !revoked_at_yivi {
+                    println!(
+                        "NOTE: (some of) these cards were not revoked at the yivi server, \
+                        so they remain valid outside PubHubs"
+                    );
+                }
+                Ok(())
+            }
+            api::admin::RevokeCardsResp::UnknownUser => anyhow::bail!("unknown user"),
+            api::admin::RevokeCardsResp::UserNotBanned => {
+                anyhow::bail!("user is not banned; ban one of their attributes first")
+            }
+            api::admin::RevokeCardsResp::ResignRequest => {
+                anyhow::bail!("request expired unexpectedly quickly")
+            }
+            api::admin::RevokeCardsResp::InvalidAdminKey => anyhow::bail!("invalid admin key"),
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub struct HubsArgs {
+    #[command(subcommand)]
+    command: HubsCommands,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum HubsCommands {
+    /// Adds a hub, and prints its details.
+    Add {
+        /// Handle of the hub, e.g. 'testhub'.  Can be repeated; the first is the preferred one.
+        #[arg(long = "handle", value_name = "HANDLE", required = true)]
+        handles: Vec<crate::handle::Handle>,
+
+        /// Human-readable short name of the hub.
+        #[arg(long, value_name = "NAME")]
+        name: String,
+
+        /// Short description of the hub.
+        #[arg(long, value_name = "DESCRIPTION", default_value = "")]
+        description: String,
+
+        /// Client API url of the hub, ending with a '/', e.g. 'https://example.com/_synapse/client/'.
+        #[arg(long, value_name = "URL")]
+        url: url::Url,
+
+        /// Id of the hub; randomly generated when not given.
+        #[arg(long, value_name = "ID")]
+        id: Option<crate::id::Id>,
+    },
+
+    /// Changes the details of a hub, and prints its new details.
+    Update {
+        /// One of the handles of the hub to update.
+        #[arg(value_name = "HUB")]
+        hub: crate::handle::Handle,
+
+        #[arg(long, value_name = "NAME")]
+        name: Option<String>,
+
+        #[arg(long, value_name = "DESCRIPTION")]
+        description: Option<String>,
+
+        #[arg(long, value_name = "URL")]
+        url: Option<url::Url>,
+
+        /// Adds a handle to the hub.  Can be repeated.  Handles can not be removed.
+        #[arg(long = "add-handle", value_name = "HANDLE")]
+        add_handles: Vec<crate::handle::Handle>,
+    },
+
+    /// Retires a hub.  Its handles and id can not be reused.
+    Retire {
+        /// One of the handles of the hub to retire.
+        #[arg(value_name = "HUB")]
+        hub: crate::handle::Handle,
+    },
+
+    /// Lists the hubs that asked to be registered, and await approval.
+    Pending,
+
+    /// Approves the request of a hub to be registered, and prints its details.
+    Approve {
+        /// The handle requested by the hub.
+        #[arg(value_name = "HANDLE")]
+        handle: crate::handle::Handle,
+    },
+
+    /// Rejects the request of a hub to be registered.
+    Reject {
+        /// The handle requested by the hub.
+        #[arg(value_name = "HANDLE")]
+        handle: crate::handle::Handle,
+    },
+
+    /// Suspends a hub: users can no longer enter it, and its tickets are revoked.
+    Suspend {
+        /// One of the handles of the hub to suspend.
+        #[arg(value_name = "HUB")]
+        hub: crate::handle::Handle,
+    },
+
+    /// Lifts the suspension of a hub.
+    Unsuspend {
+        /// One of the handles of the hub to unsuspend.
+        #[arg(value_name = "HUB")]
+        hub: crate::handle::Handle,
+    },
+}
+
+impl HubsArgs {
+    fn run(self, ctx: AdminContext) -> Result<()> {
+        ctx.block_on_phc(self.run_async(&ctx))
+    }
+
+    async fn run_async(self, ctx: &AdminContext) -> Result<()> {
+        let url = ctx.get_url().await?;
+
+        let hub: crate::hub::BasicInfo = match self.command {
+            HubsCommands::Add {
+                handles,
+                name,
+                description,
+                url: hub_url,
+                id,
+            } => {
+                let resp = ctx
+                    .client
+                    .query_with_retry::<api::admin::AddHubEP, _, _>(
+                        url,
+                        &api::Signed::<api::admin::AddHubReq>::new(
+                            &ctx.admin_key,
+                            &api::admin::AddHubReq {
+                                handles: handles.into(),
+                                name,
+                                description,
+                                url: hub_url,
+                                id,
+                            },
+                            std::time::Duration::from_secs(10),
+                        )?,
+                    )
+                    .await?;
+
+                match resp {
+                    api::admin::AddHubResp::Success(hub) => hub,
+                    api::admin::AddHubResp::HandleTaken(handle) => {
+                        anyhow::bail!("handle {handle} is already taken")
+                    }
+                    api::admin::AddHubResp::IdTaken => anyhow::bail!("id is already taken"),
+                    api::admin::AddHubResp::InvalidUrl => {
+                        anyhow::bail!("hub url must end with a '/'")
+                    }
+                    api::admin::AddHubResp::ResignRequest => {
+                        anyhow::bail!("request expired unexpectedly quickly")
+                    }
+                    api::admin::AddHubResp::InvalidAdminKey => anyhow::bail!("invalid admin key"),
+                }
+            }
+            HubsCommands::Update {
+                hub,
+                name,
+                description,
+                url: hub_url,
+                add_handles,
+            } => {
+                let resp = ctx
+                    .client
+                    .query_with_retry::<api::admin::UpdateHubEP, _, _>(
+                        url,
+                        &api::Signed::<api::admin::UpdateHubReq>::new(
+                            &ctx.admin_key,
+                            &api::admin::UpdateHubReq {
+                                hub,
+                                name,
+                                description,
+                                url: hub_url,
+                                add_handles,
+                            },
+                            std::time::Duration::from_secs(10),
+                        )?,
+                    )
+                    .await?;
+
+                match resp {
+                    api::admin::UpdateHubResp::Success(hub) => hub,
+                    api::admin::UpdateHubResp::UnknownHub => anyhow::bail!("unknown hub"),
+                    api::admin::UpdateHubResp::HandleTaken(handle) => {
+                        anyhow::bail!("handle {handle} is already taken")
+                    }
+                    api::admin::UpdateHubResp::InvalidUrl => {
+                        anyhow::bail!("hub url must end with a '/'")
+                    }
+                    api::admin::UpdateHubResp::ResignRequest => {
+                        anyhow::bail!("request expired unexpectedly quickly")
+                    }
+                    api::admin::UpdateHubResp::InvalidAdminKey => {
+                        anyhow::bail!("invalid admin key")
+                    }
+                }
+            }
+            HubsCommands::Retire { hub } => {
+                let resp = ctx
+                    .client
+                    .query_with_retry::<api::admin::RetireHubEP, _, _>(
+                        url,
+                        &api::Signed::<api::admin::RetireHubReq>::new(
+                            &ctx.admin_key,
+                            &api::admin::RetireHubReq { hub: hub.clone() },
+                            std::time::Duration::from_secs(10),
+                        )?,
+                    )
+                    .await?;
+
+                return match resp {
+                    api::admin::RetireHubResp::Success => {
+                        log::info!("retired hub {hub}");
+                        Ok(())
+                    }
+                    api::admin::RetireHubResp::UnknownHub => anyhow::bail!("unknown hub"),
+                    api::admin::RetireHubResp::ResignRequest => {
+                        anyhow::bail!("request expired unexpectedly quickly")
+                    }
+                    api::admin::RetireHubResp::InvalidAdminKey => {
+                        anyhow::bail!("invalid admin key")
+                    }
+                };
+            }
+            HubsCommands::Pending => {
+                let resp = ctx
+                    .client
+                    .query_with_retry::<api::admin::PendingHubsEP, _, _>(
+                        url,
+                        &api::Signed::<api::admin::PendingHubsReq>::new(
+                            &ctx.admin_key,
+                            &api::admin::PendingHubsReq {},
+                            std::time::Duration::from_secs(10),
+                        )?,
+                    )
+                    .await?;
+
+                return match resp {
+                    api::admin::PendingHubsResp::Success { pending } => {
+                        let stdout = std::io::stdout().lock();
+
+                        serde_json::to_writer_pretty(stdout, &pending)?;
+
+                        Ok(())
+                    }
+                    api::admin::PendingHubsResp::ResignRequest => {
+                        anyhow::bail!("request expired unexpectedly quickly")
+                    }
+                    api::admin::PendingHubsResp::InvalidAdminKey => {
+                        anyhow::bail!("invalid admin key")
+                    }
+                };
+            }
+            HubsCommands::Approve { handle } => {
+                let resp = ctx
+                    .client
+                    .query_with_retry::<api::admin::ApproveHubEP, _, _>(
+                        url,
+                        &api::Signed::<api::admin::ApproveHubReq>::new(
+                            &ctx.admin_key,
+                            &api::admin::ApproveHubReq { handle },
+                            std::time::Duration::from_secs(10),
+                        )?,
+                    )
+                    .await?;
+
+                match resp {
+                    api::admin::ApproveHubResp::Success(hub) => hub,
+                    api::admin::ApproveHubResp::UnknownPendingHub => {
+                        anyhow::bail!("no hub with this handle awaits approval")
+                    }
+                    api::admin::ApproveHubResp::HandleTaken => {
+                        anyhow::bail!("the handle has been taken by another hub in the meantime")
+                    }
+                    api::admin::ApproveHubResp::ResignRequest => {
+                        anyhow::bail!("request expired unexpectedly quickly")
+                    }
+                    api::admin::ApproveHubResp::InvalidAdminKey => {
+                        anyhow::bail!("invalid admin key")
+                    }
+                }
+            }
+            HubsCommands::Reject { handle } => {
+                let resp = ctx
+                    .client
+                    .query_with_retry::<api::admin::RejectHubEP, _, _>(
+                        url,
+                        &api::Signed::<api::admin::RejectHubReq>::new(
+                            &ctx.admin_key,
+                            &api::admin::RejectHubReq {
+                                handle: handle.clone(),
+                            },
+                            std::time::Duration::from_secs(10),
+                        )?,
+                    )
+                    .await?;
+
+                return match resp {
+                    api::admin::RejectHubResp::Success => {
+                        log::info!("rejected hub {handle}");
+                        Ok(())
+                    }
+                    api::admin::RejectHubResp::UnknownPendingHub => {
+                        anyhow::bail!("no hub with this handle awaits approval")
+                    }
+                    api::admin::RejectHubResp::ResignRequest => {
+                        anyhow::bail!("request expired unexpectedly quickly")
+                    }
+                    api::admin::RejectHubResp::InvalidAdminKey => {
+                        anyhow::bail!("invalid admin key")
+                    }
+                };
+            }
+            HubsCommands::Suspend { hub } => {
+                let resp = ctx
+                    .client
+                    .query_with_retry::<api::admin::SuspendHubEP, _, _>(
+                        url,
+                        &api::Signed::<api::admin::SuspendHubReq>::new(
+                            &ctx.admin_key,
+                            &api::admin::SuspendHubReq { hub: hub.clone() },
+                            std::time::Duration::from_secs(10),
+                        )?,
+                    )
+                    .await?;
+
+                return match resp {
+                    api::admin::SuspendHubResp::Success { revoked_tickets } => {
+                        log::info!("suspended hub {hub}, revoking {revoked_tickets} ticket(s)");
+                        Ok(())
+                    }
+                    api::admin::SuspendHubResp::UnknownHub => anyhow::bail!("unknown hub"),
+                    api::admin::SuspendHubResp::ResignRequest => {
+                        anyhow::bail!("request expired unexpectedly quickly")
+                    }
+                    api::admin::SuspendHubResp::InvalidAdminKey => {
+                        anyhow::bail!("invalid admin key")
+                    }
+                };
+            }
+            HubsCommands::Unsuspend { hub } => {
+                let resp = ctx
+                    .client
+                    .query_with_retry::<api::admin::UnsuspendHubEP, _, _>(
+                        url,
+                        &api::Signed::<api::admin::UnsuspendHubReq>::new(
+                            &ctx.admin_key,
+                            &api::admin::UnsuspendHubReq { hub: hub.clone() },
+                            std::time::Duration::from_secs(10),
+                        )?,
+                    )
+                    .await?;
+
+                return match resp {
+                    api::admin::UnsuspendHubResp::Success => {
+                        log::info!("lifted the suspension of hub {hub}");
+                        Ok(())
+                    }
+                    api::admin::UnsuspendHubResp::UnknownHub => anyhow::bail!("unknown hub"),
+                    api::admin::UnsuspendHubResp::ResignRequest => {
+                        anyhow::bail!("request expired unexpectedly quickly")
+                    }
+                    api::admin::UnsuspendHubResp::InvalidAdminKey => {
+                        anyhow::bail!("invalid admin key")
+                    }
+                };
+            }
+        };
+
+        let stdout = std::io::stdout().lock();
+
+        serde_json::to_writer_pretty(stdout, &hub)?;
+
+        Ok(())
+    }
+}
@@ -113,6 +113,11 @@ pub struct EnterArgs {
     /// Don't create a new account if one of the supplied attributes already bans another account
     #[arg(long, conflicts_with = "auth_token")]
     register_only_with_unique_attrs: bool,
+
+    /// Write everything pubhubs central holds on you (in JSON) to this file after entering
+    /// pubhubs
+    #[arg(long, value_name = "FILE")]
+    export: Option<std::path::PathBuf>,
 }
 
 impl EnterArgs {
@@ -198,6 +203,30 @@ impl EnterArgs {
             }
         };
 
+        if let Some(export_path) = &self.export {
+            let export_resp = client
+                .query::<api::phc::user::ExportEP>(&constellation.phc_url, api::NoPayload)
+                .auth_header(auth_token.clone())
+                .with_retry()
+                .await
+                .context("failed to export data from phc")?;
+
+            let api::phc::user::ExportResp::Success(export) = export_resp else {
+                anyhow::bail!("failed to export data from phc: {export_resp:?}");
+            };
+
+            let file = std::fs::File::create(export_path)
+                .with_context(|| format!("failed to create {}", export_path.display()))?;
+
+            serde_json::to_writer_pretty(std::io::BufWriter::new(file), &export)?;
+
+            println!(
+                "exported {} stored object(s) to {}",
+                export.stored_objects.len(),
+                export_path.display()
+            );
+        }
+
         let Some(hub_handle) = self.hub_handle else {
             return Ok(());
         };
@@ -228,16 +257,15 @@ impl EnterArgs {
         };
 
         let ehpp_resp = client
-            .query::<api::tr::EhppEP>(
-                &constellation.transcryptor_url,
-                api::tr::EhppReq {
+            .ehpp(
+                &constellation,
+                &api::tr::EhppReq {
                     hub_nonce,
                     hub: hub_info.id,
                     ppp,
                     hub_mac_key,
                 },
             )
-            .with_retry()
             .await
             .context("failed to obtain ehpp from transcryptor")?;
 
@@ -369,11 +397,14 @@ impl EnterArgs {
             .query_with_retry::<api::auths::AuthStartEP, _, _>(
                 &constellation.auths_url,
                 api::auths::AuthStartReq {
-                    source: attr::Source::Yivi,
+                    source: Some(attr::Source::Yivi),
+                    sources: vec![],
                     yivi_chained_session: self.wait_for_card,
                     yivi_chained_session_drip: self.chained_session_drip,
+                    email: None,
                     attr_types: Default::default(),
                     attr_type_choices,
+                    optional_attr_type_choices: Default::default(),
                 },
             )
             .await
@@ -390,7 +421,10 @@ impl EnterArgs {
         let api::auths::AuthTask::Yivi {
             disclosure_request,
             yivi_requestor_url,
-        } = auth_task;
+        } = auth_task
+        else {
+            anyhow::bail!("expected a yivi authentication task, but got {auth_task:?}");
+        };
 
         // Getting the disclosure is a bit tricky, as it is retrieved in two different ways
         // depending on whether we're waiting for a card.
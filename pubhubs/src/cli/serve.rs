@@ -2,7 +2,7 @@ use crate::cli;
 use crate::servers::Config;
 use crate::servers::Server as _;
 
-use anyhow::Result;
+use anyhow::{Context as _, Result};
 
 #[derive(clap::Args, Debug)]
 pub struct ServeArgs {
@@ -12,6 +12,17 @@ pub struct ServeArgs {
     /// Run not all servers specified in the configuration file, but only these
     #[arg(value_enum, short, long, value_name = "SERVERS")]
     only: Option<Vec<crate::servers::Name>>,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Commands {
+    /// Runs a mock yivi server for the authentication server in the configuration file that
+    /// approves every session, so that, for example, `pubhubs enter` can be run offline.  Never
+    /// use this in production!
+    MockYivi(MockYiviArgs),
 }
 
 impl ServeArgs {
@@ -20,6 +31,10 @@ impl ServeArgs {
             env_logger::init();
         }
 
+        if let Some(Commands::MockYivi(args)) = self.command {
+            return args.run(self.common.load_config()?);
+        }
+
         let config = self.adjust_config(self.common.load_config()?)?;
 
         log::info!("version: {}", crate::servers::version::VERSION);
@@ -82,3 +97,88 @@ impl ServeArgs {
         config
     }
 }
+
+#[derive(clap::Args, Debug)]
+struct MockYiviArgs {
+    /// Address to bind the mock yivi server to
+    #[arg(long, value_name = "ADDR", default_value = "0.0.0.0:8188")]
+    bind_to: std::net::SocketAddr,
+
+    /// Url under which the end-user can reach the mock yivi server.  Defaults to the
+    /// authentication server's `yivi.requestor_url`.
+    #[arg(long, value_name = "URL")]
+    url: Option<url::Url>,
+
+    /// Disclose VALUE for the yivi attribute type ATTR_TYPE_ID, e.g.
+    /// `irma-demo.sidn-pbdf.email.email=alice@example.com`.  May be repeated.  Attribute types
+    /// without a value are not disclosed.
+    #[arg(long = "attr", value_name = "ATTR_TYPE_ID=VALUE", value_parser = parse_attr)]
+    attrs: Vec<(crate::servers::yivi::AttributeTypeIdentifier, String)>,
+
+    /// How long to wait for the authentication server to provide the next session of a
+    /// chained session
+    #[arg(long, value_name = "DURATION", default_value = "10m", value_parser = humantime::parse_duration)]
+    next_session_timeout: core::time::Duration,
+}
+
+fn parse_attr(s: &str) -> Result<(crate::servers::yivi::AttributeTypeIdentifier, String)> {
+    let (ati, value) = s.split_once('=').context("expected ATTR_TYPE_ID=VALUE")?;
+
+    Ok((ati.parse()?, value.to_string()))
+}
+
+impl MockYiviArgs {
+    fn run(self, config: Config) -> Result<()> {
+        use crate::misc::jwt;
+        use crate::servers::yivi;
+
+        let yivi_config = config
+            .auths
+            .as_ref()
+            .and_then(|auths| auths.yivi.as_ref())
+            .context(
+                "the configuration file does not configure yivi for the authentication server",
+            )?
+            .clone();
+
+        let url: url::Url = match self.url {
+            Some(url) => url,
+            None => {
+                // NOTE: host aliases were already resolved when the configuration was loaded
+                let mut requestor_url = yivi_config.requestor_url.clone();
+                config.host_aliases.dealias(&mut requestor_url);
+                requestor_url.as_ref().clone()
+            }
+        };
+
+        let server_key: yivi::SigningKey = match yivi_config.server_key {
+            Some(yivi::VerifyingKey::HS256(key)) => yivi::SigningKey::HS256(key),
+            Some(yivi::VerifyingKey::RS256(_)) => anyhow::bail!(
+                "the mock yivi server cannot sign session results with the RS256 yivi.server_key \
+                 configured for the authentication server;  unset it, so the authentication server \
+                 retrieves the mock yivi server's public key instead"
+            ),
+            None => yivi::SigningKey::RS256(Box::new(jwt::RS256Sk::random(2048)?)),
+        };
+
+        let mock_config = crate::servers::mock_yivi::MockYiviConfig {
+            url,
+            requestor_creds: yivi_config.requestor_creds.to_verifying_credentials(),
+            server_creds: yivi::Credentials {
+                name: yivi_config.server_name,
+                key: server_key,
+            },
+            attrs: self.attrs.into_iter().collect(),
+            next_session_timeout: self.next_session_timeout,
+        };
+
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?
+            .block_on(crate::servers::mock_yivi::run(
+                mock_config,
+                std::net::TcpListener::bind(self.bind_to)
+                    .with_context(|| format!("failed to bind to {}", self.bind_to))?,
+            ))
+    }
+}
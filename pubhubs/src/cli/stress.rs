@@ -230,11 +230,14 @@ mod enter {
         let bannable_type: handle::Handle =
             BANNABLE_ATTR.parse().expect("a valid attribute handle");
         api::auths::AuthStartReq {
-            source: attr::Source::Yivi,
+            source: Some(attr::Source::Yivi),
+            sources: vec![],
             attr_types: vec![identifying_type, bannable_type],
             attr_type_choices: Default::default(),
+            optional_attr_type_choices: Default::default(),
             yivi_chained_session: false,
             yivi_chained_session_drip: false,
+            email: None,
         }
     }
 
@@ -270,7 +273,10 @@ mod enter {
         };
         let api::auths::AuthTask::Yivi {
             disclosure_request, ..
-        } = task;
+        } = task
+        else {
+            anyhow::bail!("auth start did not return a yivi task");
+        };
         sprequest_unverified(&disclosure_request)
     }
 
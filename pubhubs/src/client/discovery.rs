@@ -66,6 +66,13 @@ impl crate::client::Client {
 
         crate::for_all_servers!(get_constellation_from_server);
 
+        for ct in constellation.co_transcryptors.iter() {
+            js.spawn_local(
+                self.query::<api::DiscoveryInfo>(&ct.url, NoPayload)
+                    .into_future(),
+            );
+        }
+
         'lp: loop {
             match js.join_next().await {
                 None => break 'lp,
@@ -1,6 +1,7 @@
 //! Helper functions to interact with the PubHubs backend servers
 
 pub mod discovery;
+pub mod sso;
 
 pub mod core;
 pub use core::{Agent, Client};
@@ -0,0 +1,41 @@
+//! Helpers for the single sign-on flow, see [`crate::api::sso`].
+use crate::api;
+use crate::servers::Constellation;
+
+impl crate::client::Client {
+    /// Obtains the [`api::sso::EncryptedHubPseudonymPackage`] from the transcryptor, passing the
+    /// request along the co-transcryptors listed in `constellation`, if there are any.
+    ///
+    /// Retries every request that can be retried.
+    pub async fn ehpp(
+        &self,
+        constellation: &Constellation,
+        req: &api::tr::EhppReq,
+    ) -> api::Result<api::tr::EhppResp> {
+        let mut resp = self
+            .query::<api::tr::EhppEP>(&constellation.transcryptor_url, req)
+            .with_retry()
+            .await?;
+
+        for ct in constellation.co_transcryptors.iter() {
+            let api::tr::EhppResp::Continue(partial_ehpp) = resp else {
+                return Ok(resp);
+            };
+
+            resp = self
+                .query::<api::tr::ContinueEhppEP>(
+                    &ct.url,
+                    api::tr::ContinueEhppReq { partial_ehpp },
+                )
+                .with_retry()
+                .await?;
+        }
+
+        if matches!(resp, api::tr::EhppResp::Continue(_)) {
+            log::error!("the last transcryptor did not return an encrypted hub pseudonym package");
+            return Err(api::ErrorCode::InternalError);
+        }
+
+        Ok(resp)
+    }
+}
@@ -293,6 +293,46 @@ impl jwt::VerifyingKey for VerifyingKey {
     }
 }
 
+/// A server's current [`VerifyingKey`] together with the next one, if it has staged one (see the
+/// `next_signing_key` server configuration).  Accepts signatures by either, so that
+/// [`Signed`](crate::api::Signed) messages remain valid while the server's signing key is rotated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RotatingVerifyingKey {
+    pub current: VerifyingKey,
+    pub next: Option<VerifyingKey>,
+}
+
+impl From<VerifyingKey> for RotatingVerifyingKey {
+    fn from(current: VerifyingKey) -> Self {
+        Self {
+            current,
+            next: None,
+        }
+    }
+}
+
+impl jwt::Key for RotatingVerifyingKey {
+    const ALG: &'static str = ALG;
+}
+
+impl jwt::VerifyingKey for RotatingVerifyingKey {
+    fn is_valid_signature(&self, message: &[u8], signature: Vec<u8>) -> bool {
+        let Some(next) = self.next.as_ref() else {
+            return self.current.is_valid_signature(message, signature);
+        };
+
+        self.current.is_valid_signature(message, signature.clone())
+            || next.is_valid_signature(message, signature)
+    }
+
+    fn describe(&self) -> String {
+        match self.next.as_ref() {
+            None => self.current.describe(),
+            Some(next) => format!("{} (or next {})", self.current.describe(), next.describe()),
+        }
+    }
+}
+
 /// [RFC 7638](https://www.rfc-editor.org/rfc/rfc7638) JWK thumbprint of the AKP public key:
 /// `base64url(SHA-256(canonical JSON of the required members alg, kty, pub, in lexicographic
 /// order))`.  `pub_bytes` is the composite public key `ML-DSA-65 pk ‖ ed25519 pk` (matching the JWK
@@ -462,6 +502,30 @@ mod tests {
         assert_eq!(signed.open(vk, None).unwrap(), message);
     }
 
+    #[test]
+    fn rotating_verifying_key() {
+        let (sk, next_sk, other_sk) = (
+            SigningKey::generate().unwrap(),
+            SigningKey::generate().unwrap(),
+            SigningKey::generate().unwrap(),
+        );
+        let message = b"msg";
+
+        let vk = RotatingVerifyingKey {
+            current: sk.verifying_key().clone(),
+            next: Some(next_sk.verifying_key().clone()),
+        };
+
+        assert!(vk.is_valid_signature(message, sk.sign(message).unwrap()));
+        assert!(vk.is_valid_signature(message, next_sk.sign(message).unwrap()));
+        assert!(!vk.is_valid_signature(message, other_sk.sign(message).unwrap()));
+
+        let vk = RotatingVerifyingKey::from(sk.verifying_key().clone());
+
+        assert!(vk.is_valid_signature(message, sk.sign(message).unwrap()));
+        assert!(!vk.is_valid_signature(message, next_sk.sign(message).unwrap()));
+    }
+
     #[test]
     fn encode_decode_roundtrip() {
         let sk = SigningKey::generate().unwrap();
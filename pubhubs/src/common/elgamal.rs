@@ -48,6 +48,14 @@ impl Triple {
         }
     }
 
+    /// Returns the public key this triple claims to be encrypted for.
+    ///
+    /// **Warning** Like [`Self::decrypt_and_check_pk`], this function can't check whether the
+    /// public key has been tampered with.
+    pub fn pk(&self) -> PublicKey {
+        self.pk.into()
+    }
+
     /// Changes the public key of this triple, likely resulting in garbage down the road.
     ///
     /// Used for demonstration purposes.
@@ -120,11 +128,38 @@ impl Triple {
     ///
     /// If you only need to specify `s` and `k`, use `triple.rsk_with_s(s).and_k(k)` instead.
     pub fn rsk(self, params: impl rsk::Params) -> Triple {
+        self.rsk_with_r(&params, &params.r())
+    }
+
+    /// Like [`Self::rsk`], but also returns a [`rsk::Proof`] that the resulting triple was
+    /// obtained from `self` in this way, without revealing `params`.
+    /// ```
+    /// use pubhubs::common::elgamal::{PrivateKey, random_point, random_scalar};
+    ///
+    /// let sk = PrivateKey::random();
+    /// let trip = sk.public_key().encrypt(random_point());
+    /// let (s, k) = (random_scalar(), random_scalar());
+    ///
+    /// let (result, proof) = trip.clone().rsk_with_s(&s).and_k_proven(&k);
+    /// assert!(proof.verify(&trip, &result));
+    ///
+    /// // The proof does not hold for any other result:
+    /// let other_result = trip.clone().rsk_with_s(&s).and_k(&k);
+    /// assert!(!proof.verify(&trip, &other_result));
+    /// ```
+    pub fn rsk_proven(self, params: impl rsk::Params) -> (Triple, rsk::Proof) {
         let r: Scalar = params.r();
+        let result = self.rsk_with_r(&params, &r);
+        let proof = rsk::Proof::new(&self, &result, params.s(), params.k(), &(r * params.k()));
+
+        (result, proof)
+    }
+
+    fn rsk_with_r(&self, params: &impl rsk::Params, r: &Scalar) -> Triple {
         let kpk = self.pk * params.k();
 
         Triple {
-            ek: params.s_over_k() * self.ek + &r * B,
+            ek: params.s_over_k() * self.ek + r * B,
             ct: params.s() * self.ct + r * kpk,
             pk: kpk,
         }
@@ -161,6 +196,93 @@ pub mod rsk {
         pub fn and_k(self, k: &Scalar) -> Triple {
             self.t.rsk(SAndK { s: self.s, k })
         }
+
+        /// Like [`WithS::and_k`], but using [`Triple::rsk_proven`].
+        pub fn and_k_proven(self, k: &Scalar) -> (Triple, Proof) {
+            self.t.rsk_proven(SAndK { s: self.s, k })
+        }
+    }
+
+    /// Non-interactive zero-knowledge proof that a [`Triple`] is the result of applying
+    /// [`Triple::rsk`] to another triple, obtained via [`Triple::rsk_proven`].
+    ///
+    /// Writing `(ek, ct, pk)` for the original triple and `(ek', ct', pk')` for the result, it is
+    /// a Chaum-Pedersen-style proof of knowledge of scalars `k`, `s` and `d` (which is `r k`) with
+    ///
+    /// ```text
+    ///   pk' = k pk,    ct' = s ct + d pk    and    k ek' = s ek + d B,
+    /// ```
+    ///
+    /// made non-interactive using the Fiat-Shamir heuristic.  These equations imply that
+    /// `(ek', ct', pk')` decrypts under `k` times the private key to `s` times the plaintext
+    /// of `(ek, ct, pk)`.  Note that `s` and `k` are not revealed.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Proof {
+        pub(super) c: Scalar,
+        pub(super) z_k: Scalar,
+        pub(super) z_s: Scalar,
+        pub(super) z_d: Scalar,
+    }
+
+    impl Proof {
+        pub(super) fn new(
+            input: &Triple,
+            output: &Triple,
+            s: &Scalar,
+            k: &Scalar,
+            d: &Scalar,
+        ) -> Self {
+            let (t_k, t_s, t_d) = (random_scalar(), random_scalar(), random_scalar());
+
+            let c = Self::challenge(
+                input,
+                output,
+                [
+                    t_k * input.pk,
+                    t_s * input.ct + t_d * input.pk,
+                    t_s * input.ek + &t_d * B - t_k * output.ek,
+                ],
+            );
+
+            Proof {
+                c,
+                z_k: t_k + c * k,
+                z_s: t_s + c * s,
+                z_d: t_d + c * d,
+            }
+        }
+
+        /// Checks that `output` was obtained by applying [`Triple::rsk`] to `input`.
+        pub fn verify(&self, input: &Triple, output: &Triple) -> bool {
+            let Proof { c, z_k, z_s, z_d } = self;
+
+            *c == Self::challenge(
+                input,
+                output,
+                [
+                    z_k * input.pk - c * output.pk,
+                    z_s * input.ct + z_d * input.pk - c * output.ct,
+                    z_s * input.ek + z_d * B - z_k * output.ek,
+                ],
+            )
+        }
+
+        fn challenge(input: &Triple, output: &Triple, commitments: [RistrettoPoint; 3]) -> Scalar {
+            use sha2::Digest as _;
+
+            let mut hasher = sha2::Sha512::new().chain_update(b"pubhubs-rsk-proof");
+
+            for point in [
+                input.ek, input.ct, input.pk, output.ek, output.ct, output.pk,
+            ]
+            .iter()
+            .chain(commitments.iter())
+            {
+                hasher.update(point.compress().as_bytes());
+            }
+
+            Scalar::from_hash(hasher)
+        }
     }
 
     /// Utilities for the [Triple::rsk] operation.
@@ -190,6 +312,52 @@ pub mod rsk {
     }
 }
 
+/// Utilities for [`PrivateKey::scale_proven`]
+pub mod scaling {
+    use super::*;
+
+    /// Non-interactive zero-knowledge proof that a [`PublicKey`] is a multiple of another, obtained
+    /// via [`PrivateKey::scale_proven`].
+    ///
+    /// Writing `pk` for the input and `pk'` for the result, it is a Schnorr proof of knowledge of a
+    /// scalar `x` with `pk' = x pk`, made non-interactive using the Fiat-Shamir heuristic.  Note
+    /// that `x` is not revealed.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Proof {
+        pub(super) c: Scalar,
+        pub(super) z: Scalar,
+    }
+
+    impl Proof {
+        pub(super) fn new(input: &PublicKey, output: &PublicKey, x: &Scalar) -> Self {
+            let t = random_scalar();
+
+            let c = Self::challenge(input, output, t * input.point);
+
+            Proof { c, z: t + c * x }
+        }
+
+        /// Checks that `output` is a multiple of `input` by a scalar known to the prover.
+        pub fn verify(&self, input: &PublicKey, output: &PublicKey) -> bool {
+            let Proof { c, z } = self;
+
+            *c == Self::challenge(input, output, z * input.point - c * output.point)
+        }
+
+        fn challenge(input: &PublicKey, output: &PublicKey, commitment: RistrettoPoint) -> Scalar {
+            use sha2::Digest as _;
+
+            Scalar::from_hash(
+                sha2::Sha512::new()
+                    .chain_update(b"pubhubs-scaling-proof")
+                    .chain_update(input.compressed.as_bytes())
+                    .chain_update(output.compressed.as_bytes())
+                    .chain_update(commitment.compress().as_bytes()),
+            )
+        }
+    }
+}
+
 macro_rules! osrng {
     () => {
         &mut aead::OsRng
@@ -243,6 +411,29 @@ impl PrivateKey {
         (self.scalar * pk.point).into()
     }
 
+    /// Like [`PrivateKey::scale`], but also returns a [`scaling::Proof`] that the result is a
+    /// multiple of `pk` by a scalar known to us.
+    ///
+    /// ```
+    /// use pubhubs::common::elgamal::{PrivateKey, PublicKey};
+    ///
+    /// let (sk, pk) = (PrivateKey::random(), PrivateKey::random().public_key().clone());
+    ///
+    /// let (result, proof) = sk.scale_proven(&pk);
+    /// assert_eq!(result, sk.scale(&pk));
+    /// assert!(proof.verify(&pk, &result));
+    ///
+    /// // The proof does not hold for any other input or result:
+    /// assert!(!proof.verify(&PublicKey::generator(), &result));
+    /// assert!(!proof.verify(&pk, &PrivateKey::random().scale(&pk)));
+    /// ```
+    pub fn scale_proven(&self, pk: &PublicKey) -> (PublicKey, scaling::Proof) {
+        let result = self.scale(pk);
+        let proof = scaling::Proof::new(pk, &result, &self.scalar);
+
+        (result, proof)
+    }
+
     /// Creates a Diffie-Hellman-type shared secret between this [`PrivateKey`] and the [`PublicKey`].
     pub fn shared_secret(&self, pk: &PublicKey) -> SharedSecret {
         SharedSecret {
@@ -283,6 +474,11 @@ impl PublicKey {
         CompressedRistretto::from_hex(hexstr)?.try_into().ok()
     }
 
+    /// The base point `B`, the public key of the private key `1`.
+    pub fn generator() -> Self {
+        B.basepoint().into()
+    }
+
     /// The identity element, which encodes as 32 zero bytes.
     pub fn zero() -> Self {
         use curve25519_dalek::traits::Identity as _;
@@ -497,7 +693,51 @@ impl Encoding<96> for Triple {
     }
 }
 
+impl Encoding<128> for rsk::Proof {
+    fn from_bytes(bytes: [u8; 128]) -> Option<rsk::Proof> {
+        Some(rsk::Proof {
+            c: Scalar::from_slice(&bytes[..32])?,
+            z_k: Scalar::from_slice(&bytes[32..64])?,
+            z_s: Scalar::from_slice(&bytes[64..96])?,
+            z_d: Scalar::from_slice(&bytes[96..])?,
+        })
+    }
+
+    fn to_bytes(&self) -> [u8; 128] {
+        let mut result = [0u8; 128];
+
+        // Note: `copy_to_slice` only fails when the slice's size is not 32, which it won't below
+        self.c.copy_to_slice(&mut result[..32]).unwrap();
+        self.z_k.copy_to_slice(&mut result[32..64]).unwrap();
+        self.z_s.copy_to_slice(&mut result[64..96]).unwrap();
+        self.z_d.copy_to_slice(&mut result[96..]).unwrap();
+
+        result
+    }
+}
+
+impl Encoding<64> for scaling::Proof {
+    fn from_bytes(bytes: [u8; 64]) -> Option<scaling::Proof> {
+        Some(scaling::Proof {
+            c: Scalar::from_slice(&bytes[..32])?,
+            z: Scalar::from_slice(&bytes[32..])?,
+        })
+    }
+
+    fn to_bytes(&self) -> [u8; 64] {
+        let mut result = [0u8; 64];
+
+        // Note: `copy_to_slice` only fails when the slice's size is not 32, which it won't below
+        self.c.copy_to_slice(&mut result[..32]).unwrap();
+        self.z.copy_to_slice(&mut result[32..]).unwrap();
+
+        result
+    }
+}
+
 mod serde_impls {
+    use super::rsk::Proof;
+    use super::scaling::Proof as ScalingProof;
     use super::*;
     use crate::misc::serde_ext;
     use serde::de::Error as _;
@@ -528,6 +768,8 @@ mod serde_impls {
     serde_impl! { PrivateKey, 32 }
     serde_impl! { PublicKey, 32 }
     serde_impl! { Triple, 96 }
+    serde_impl! { Proof, 128 }
+    serde_impl! { ScalingProof, 64 }
 }
 
 /// Shared secret created by combining a [`PrivateKey`] with a [`PublicKey`], which, although it is
@@ -547,6 +547,23 @@ impl JWT {
         Ok(claims)
     }
 
+    /// Returns the `kid` (key ID) from this JWT's header, if any, without checking the signature.
+    ///
+    /// Useful to pick the key to [`open`](Self::open) this JWT with, for example from a JWK Set.
+    pub fn kid(&self) -> Result<Option<String>, Error> {
+        let s = &self.inner;
+
+        let first_dot_pos: usize = s.find('.').ok_or(Error::MissingDot)?;
+
+        let header_vec: Vec<u8> =
+            Base64UrlUnpadded::decode_vec(&s[..first_dot_pos]).map_err(Error::InvalidBase64)?;
+
+        let header: Header =
+            serde_json::from_slice(&header_vec).map_err(Error::DeserializingHeader)?;
+
+        Ok(header.kid.map(Cow::into_owned))
+    }
+
     pub fn as_str(&self) -> &str {
         &self.inner
     }
@@ -672,6 +689,11 @@ struct Header<'a> {
 
     #[serde(borrow)]
     alg: Cow<'a, str>,
+
+    /// Identifies the key used to sign the JWT, see RFC7515, Section 4.1.4.  Only used by
+    /// [`JWT::kid`].
+    #[serde(borrow, default, skip_serializing)]
+    kid: Option<Cow<'a, str>>,
     // Add fields here when needed
 }
 
@@ -919,6 +941,15 @@ impl RS256Vk {
         ))
     }
 
+    /// Creates an [`RS256Vk`] from the (big endian) modulus `n` and exponent `e`, as found
+    /// (base64url encoded) in an RSA JSON Web Key, see RFC7518, Section 6.3.1.
+    pub fn from_jwk_components(n: &[u8], e: &[u8]) -> anyhow::Result<Self> {
+        Ok(Self::new(rsa::RsaPublicKey::new(
+            rsa::BigUint::from_bytes_be(n),
+            rsa::BigUint::from_bytes_be(e),
+        )?))
+    }
+
     pub fn to_public_key_pem(&self) -> anyhow::Result<String> {
         Ok(self.0.to_public_key_pem(Default::default())?)
     }
@@ -1150,9 +1181,14 @@ mod tests {
             serde_json::from_str::<Header>(r#"{"alg":"", "unknown_field": ""}"#)
                 .unwrap_err()
                 .to_string(),
-            "unknown field `unknown_field`, expected `typ` or `alg` at line 1 column 26"
+            "unknown field `unknown_field`, expected one of `typ`, `alg`, `kid` at line 1 column 26"
                 .to_string()
         );
+
+        // "kid" is optional
+        let header: Header = serde_json::from_str(r#"{"alg":"", "kid": "key1"}"#).unwrap();
+        assert_eq!(header.kid.as_deref(), Some("key1"));
+        assert!(header_a.kid.is_none());
     }
 
     #[test]
@@ -1295,5 +1331,16 @@ mod tests {
         let pk = RS256Vk::new(sk.as_rsa_pub().clone());
 
         let _ = jwt.open(&pk).unwrap();
+
+        assert_eq!(jwt.kid().unwrap(), None);
+
+        assert_eq!(
+            RS256Vk::from_jwk_components(
+                &sk.as_rsa_pub().n().to_bytes_be(),
+                &sk.as_rsa_pub().e().to_bytes_be()
+            )
+            .unwrap(),
+            pk
+        );
     }
 }
@@ -31,6 +31,23 @@ pub fn master_enc_key_part_hash(part: &elgamal::PublicKey) -> id::Id {
     )
 }
 
+/// Computes `x_new / x_old` from a server's previous master encryption key part `x_old` and its
+/// current part `x_new`.  Multiplying the rekey factors of PHC and the transcryptor gives the
+/// factor by which [`rekey_polymorphic_pseudonym`] moves a polymorphic pseudonym from the previous
+/// to the current master encryption key.
+pub fn master_enc_key_part_rekey_factor(
+    previous_part: &elgamal::PrivateKey,
+    current_part: &elgamal::PrivateKey,
+) -> Scalar {
+    current_part.as_scalar() * previous_part.as_scalar().invert()
+}
+
+/// Re-keys the given polymorphic pseudonym `pp` (`Id_U` elgamal encrypted for `x_old`) so that it
+/// is encrypted for `x_new`, where `k = x_new / x_old`.  The plaintext `Id_U` is left unchanged.
+pub fn rekey_polymorphic_pseudonym(pp: elgamal::Triple, k: &Scalar) -> elgamal::Triple {
+    pp.rsk_with_s(&Scalar::ONE).and_k(k)
+}
+
 /// Computes the **pseudonymisation factor** $g_H$ for the hub identified by `hub_id`,
 /// from the transcryptor's `pseud_factor_secret`.  See [`crate::api::sso`] for the
 /// exact formula.
@@ -42,15 +59,19 @@ pub fn pseud_factor_for_hub(pseud_factor_secret: impl DigestibleSecret, hub_id:
 }
 
 /// Turns the given polymorphic pseudonym `pp` (which should be `Id_U` elgamal encrypted for `x`)
-/// into an encrypted hub pseudonym (which should be `g_H Id_U` elgamal encrypted for `x_PHC`).
+/// into an encrypted hub pseudonym (which should be `g_H Id_U` elgamal encrypted for `x_PHC`),
+/// and returns it together with a proof for PHC that this was done correctly.
+///
+/// When the transcryptor is split, each transcryptor in the chain applies only its own factor
+/// and key part, see "Splitting the transcryptor" in [`crate::api::sso`].
 pub fn t_encrypted_hub_pseudonym(
     pp: elgamal::Triple,
     pseud_factor_secret: impl DigestibleSecret,
     master_enc_key_part_inv: &Scalar,
     hub_id: id::Id,
-) -> elgamal::Triple {
+) -> (elgamal::Triple, elgamal::rsk::Proof) {
     let g_h = pseud_factor_for_hub(pseud_factor_secret, hub_id);
-    pp.rsk_with_s(&g_h).and_k(master_enc_key_part_inv)
+    pp.rsk_with_s(&g_h).and_k_proven(master_enc_key_part_inv)
 }
 
 /// Combines a post-quantum ML-KEM and classical Ristretto-DH shared secret.
@@ -98,6 +119,15 @@ pub fn attr_id(attr: &attr::Attr, secret: impl secret::DigestibleSecret) -> crat
     )
 }
 
+/// Derives an [`id::Id`] for an admin key, so that it can be referred to (for example in
+/// [`api::admin::BanRecord`]s) without revealing the key itself.
+pub fn admin_key_id(admin_key: &jwt::HS256) -> id::Id {
+    admin_key
+        .0
+        .as_slice()
+        .derive_id(sha2::Sha256::new(), "pubhubs-admin-key-id")
+}
+
 /// Derives an [`id::Id`] for a [`constellation::Inner`].
 pub fn constellation_id(c: &constellation::Inner) -> id::Id {
     b"".as_slice()
@@ -120,6 +150,14 @@ pub fn jwt_id(jwt: &jwt::JWT) -> id::Id {
     b"".as_slice().derive_id(jwt.sha256(), "pubhubs-jwt-id")
 }
 
+/// Derives the [`id::Id`] by which a hub ticket is revoked, see [`api::phc::hub::RevocationsEP`].
+pub fn ticket_digest(ticket: &api::phc::hub::Ticket) -> id::Id {
+    b"".as_slice().derive_id(
+        sha2::Sha256::new().chain_update(ticket.as_str().as_bytes()),
+        "pubhubs-ticket-digest",
+    )
+}
+
 /// Derives an `hmac` for a user object stored at pubhubs central.
 ///
 /// See [`crate::api::phc::user::GetObjectEP`].
@@ -151,3 +189,96 @@ pub fn auths_attr_keys(
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotate_master_enc_key_parts() {
+        let hub_id = id::Id::random();
+        let pseud_factor_secret = b"pseud_factor_secret".as_slice();
+
+        let (previous_t, previous_phc) =
+            (elgamal::PrivateKey::random(), elgamal::PrivateKey::random());
+        let (current_t, current_phc) =
+            (elgamal::PrivateKey::random(), elgamal::PrivateKey::random());
+
+        let previous_master_enc_key =
+            combine_master_enc_key_parts(previous_t.public_key(), &previous_phc);
+        let current_master_enc_key =
+            combine_master_enc_key_parts(current_t.public_key(), &current_phc);
+
+        let id_u = elgamal::random_point();
+        let pp = previous_master_enc_key.encrypt(id_u);
+
+        let hub_pseudonym =
+            |pp: elgamal::Triple, t: &elgamal::PrivateKey, phc: &elgamal::PrivateKey| {
+                t_encrypted_hub_pseudonym(pp, pseud_factor_secret, &t.as_scalar().invert(), hub_id)
+                    .0
+                    .decrypt_and_check_pk(phc)
+                    .unwrap()
+            };
+
+        let expected = hub_pseudonym(pp.clone(), &previous_t, &previous_phc);
+
+        let k = master_enc_key_part_rekey_factor(&previous_t, &current_t)
+            * master_enc_key_part_rekey_factor(&previous_phc, &current_phc);
+
+        assert_eq!(
+            elgamal::PrivateKey::from(k).scale(&previous_master_enc_key),
+            current_master_enc_key
+        );
+
+        let rekeyed = rekey_polymorphic_pseudonym(pp, &k);
+        assert_eq!(rekeyed.pk(), current_master_enc_key);
+        assert_eq!(hub_pseudonym(rekeyed, &current_t, &current_phc), expected);
+    }
+    #[test]
+    fn transcryption_proofs() {
+        let hub_id = id::Id::random();
+        let (t1, t2, phc) = (
+            elgamal::PrivateKey::random(),
+            elgamal::PrivateKey::random(),
+            elgamal::PrivateKey::random(),
+        );
+        let master_enc_key =
+            combine_master_enc_key_parts(&combine_master_enc_key_parts(phc.public_key(), &t1), &t2);
+
+        let pp = master_enc_key.encrypt(elgamal::random_point());
+
+        // the second transcryptor is changing its pseudonymisation factor secret
+        let (ehp1, proof1) = t_encrypted_hub_pseudonym(
+            pp.clone(),
+            b"t1".as_slice(),
+            &t1.as_scalar().invert(),
+            hub_id,
+        );
+        let (ehp2, proof2) = t_encrypted_hub_pseudonym(
+            ehp1.clone(),
+            b"t2".as_slice(),
+            &t2.as_scalar().invert(),
+            hub_id,
+        );
+        let (previous_ehp2, previous_proof2) = t_encrypted_hub_pseudonym(
+            ehp1.clone(),
+            b"previous t2".as_slice(),
+            &t2.as_scalar().invert(),
+            hub_id,
+        );
+
+        let mut tp = api::sso::TranscryptionProof::new(proof1);
+        tp.push(ehp1.clone(), proof2);
+        let previous_tp = api::sso::TranscryptionProof::new(previous_proof2);
+
+        assert!(tp.verify(&pp, &ehp2));
+        assert!(!tp.verify(&pp, &previous_ehp2));
+        assert!(!tp.verify(&pp.clone().rerandomize(), &ehp2));
+
+        let fork = tp.step_count() - previous_tp.step_count();
+        assert_eq!(tp.input_of_step(&pp, fork), Some(&ehp1));
+        assert!(previous_tp.verify(&ehp1, &previous_ehp2));
+
+        assert!(ehp2.decrypt_and_check_pk(&phc).is_some());
+    }
+}
@@ -14,6 +14,42 @@ use crate::{
     misc::jwt,
 };
 
+/// Result of [`App::yivi_condiscon_for`]
+pub(super) struct YiviConDisCon {
+    pub cdc: servers::yivi::AttributeConDisCon,
+
+    /// For each 'discon' in [`YiviConDisCon::cdc`] a map from the yivi attribute type
+    /// identifiers to the handles of the attribute types they provide
+    pub ati2at: Vec<HashMap<yivi::AttributeTypeIdentifier, handle::Handle>>,
+}
+
+/// Why [`App::yivi_condiscon_for`] could not create a condiscon
+pub(super) enum YiviConDisConError {
+    UnknownAttrType(handle::Handle),
+    SourceNotAvailableFor(handle::Handle),
+    Conflict(handle::Handle, handle::Handle),
+}
+
+impl From<YiviConDisConError> for api::auths::AuthStartResp {
+    fn from(err: YiviConDisConError) -> Self {
+        match err {
+            YiviConDisConError::UnknownAttrType(h) => Self::UnknownAttrType(h),
+            YiviConDisConError::SourceNotAvailableFor(h) => Self::SourceNotAvailableFor(h),
+            YiviConDisConError::Conflict(h1, h2) => Self::Conflict(h1, h2),
+        }
+    }
+}
+
+impl From<YiviConDisConError> for api::auths::YiviSignResp {
+    fn from(err: YiviConDisConError) -> Self {
+        match err {
+            YiviConDisConError::UnknownAttrType(h) => Self::UnknownAttrType(h),
+            YiviConDisConError::SourceNotAvailableFor(h) => Self::SourceNotAvailableFor(h),
+            YiviConDisConError::Conflict(h1, h2) => Self::Conflict(h1, h2),
+        }
+    }
+}
+
 /// # Implementaton of endpoints
 impl App {
     /// Implements [`api::auths::AuthStartEP`]
@@ -23,11 +59,37 @@ impl App {
     ) -> api::Result<api::auths::AuthStartResp> {
         let req = req.into_inner();
 
-        if req.yivi_chained_session && req.source != attr::Source::Yivi {
+        let requested_sources: &[attr::Source] = match (&req.source, req.sources.is_empty()) {
+            (Some(source), true) => std::slice::from_ref(source),
+            (None, false) => &req.sources,
+            _ => {
+                log::debug!("authentication request must set exactly one of source and sources");
+                return Err(api::ErrorCode::BadRequest);
+            }
+        };
+
+        if req.yivi_chained_session && !requested_sources.contains(&attr::Source::Yivi) {
             log::debug!("yivi_chained_session set on non-yivi authentication request");
             return Err(api::ErrorCode::BadRequest);
         }
 
+        if !req.optional_attr_type_choices.is_empty()
+            && !requested_sources.contains(&attr::Source::Yivi)
+        {
+            log::debug!("optional_attr_type_choices set on non-yivi authentication request");
+            return Err(api::ErrorCode::BadRequest);
+        }
+
+        if req.email.is_some() && !requested_sources.contains(&attr::Source::Email) {
+            log::debug!("email set on authentication request without email source");
+            return Err(api::ErrorCode::BadRequest);
+        }
+
+        if req.source == Some(attr::Source::Email) && req.email.is_none() {
+            log::debug!("email not set on email authentication request");
+            return Err(api::ErrorCode::BadRequest);
+        }
+
         if req.yivi_chained_session_drip && !req.yivi_chained_session {
             log::debug!(
                 "yivi_chained_session_drip set on authentication request  while yivi_chained_session is not"
@@ -45,9 +107,11 @@ impl App {
         let max = app.max_attr_types_per_req;
         if req.attr_types.len() > max
             || req.attr_type_choices.len() > max
+            || req.optional_attr_type_choices.len() > max
             || req
                 .attr_type_choices
                 .iter()
+                .chain(req.optional_attr_type_choices.iter())
                 .any(|choices| choices.len() > max)
         {
             log::debug!(
@@ -64,15 +128,32 @@ impl App {
             req.attr_type_choices
         };
 
+        let source = match req.source {
+            Some(source) => source,
+            None => match app.choose_source(
+                &req.sources,
+                &attr_type_choices,
+                req.email.is_some(),
+                req.yivi_chained_session || !req.optional_attr_type_choices.is_empty(),
+            )? {
+                Ok(source) => source,
+                Err(resp) => return Ok(resp),
+            },
+        };
+
         let state = AuthState {
-            source: req.source,
+            source,
             attr_type_choices,
+            optional_attr_type_choices: req.optional_attr_type_choices,
             exp: api::NumericDate::now().add_clamp(app.auth_window.as_secs()),
             yivi_chained_session: None,
             yivi_ati2at: Default::default(),
+            email: None,
+            oidc: None,
+            webauthn: None,
         };
 
-        match req.source {
+        match source {
             attr::Source::Yivi => {
                 Self::handle_auth_start_yivi(
                     app,
@@ -82,9 +163,163 @@ impl App {
                 )
                 .await
             }
+            attr::Source::Email => {
+                Self::handle_auth_start_email(
+                    app,
+                    state,
+                    req.email.expect("email presence was checked above"),
+                )
+                .await
+            }
+            attr::Source::Oidc => Self::handle_auth_start_oidc(app, state).await,
+            attr::Source::WebAuthn => Self::handle_auth_start_webauthn(app, state).await,
         }
     }
 
+    /// Picks a source from [`api::auths::AuthStartReq::sources`], see there.  Returns the
+    /// [`api::auths::AuthStartResp`] to send when none of the sources will do.
+    ///
+    /// With `yivi_only` set only [`attr::Source::Yivi`] is considered, as is needed for
+    /// yivi-specific features like chained sessions and optional disclosures.
+    fn choose_source(
+        &self,
+        sources: &[attr::Source],
+        attr_type_choices: &[Vec<handle::Handle>],
+        email: bool,
+        yivi_only: bool,
+    ) -> api::Result<Result<attr::Source, api::auths::AuthStartResp>> {
+        let Some(first_attr_ty_handle) = attr_type_choices.first().and_then(|c| c.first()) else {
+            log::debug!("authentication requested with an empty list of attribute types");
+            return Err(api::ErrorCode::BadRequest);
+        };
+
+        // (rank, source) of the best source found so far
+        let mut best: Option<(usize, attr::Source)> = None;
+
+        'sources: for &source in sources {
+            if (source == attr::Source::Email && !email)
+                || (source != attr::Source::Yivi && yivi_only)
+            {
+                continue;
+            }
+
+            // only yivi can provide more than one attribute at a time
+            if source != attr::Source::Yivi && attr_type_choices.len() != 1 {
+                continue;
+            }
+
+            let mut rank = usize::MAX;
+
+            for attr_ty_options in attr_type_choices.iter() {
+                let mut available = false;
+
+                for attr_ty_handle in attr_ty_options.iter() {
+                    let Some(attr_ty) = self.attr_type_from_handle(attr_ty_handle) else {
+                        return Ok(Err(api::auths::AuthStartResp::UnknownAttrType(
+                            attr_ty_handle.clone(),
+                        )));
+                    };
+
+                    if let Some(source_rank) = attr_ty.source_rank(source) {
+                        available = true;
+                        rank = rank.min(source_rank);
+                    }
+                }
+
+                if !available {
+                    continue 'sources;
+                }
+            }
+
+            if best.is_none_or(|(best_rank, _)| rank < best_rank) {
+                best = Some((rank, source));
+            }
+        }
+
+        let Some((_, source)) = best else {
+            log::debug!(
+                "got authentication start request for {first_attr_ty_handle}, but none of the sources {sources:?} will do",
+            );
+            return Ok(Err(api::auths::AuthStartResp::SourceNotAvailableFor(
+                first_attr_ty_handle.clone(),
+            )));
+        };
+
+        Ok(Ok(source))
+    }
+
+    /// For sources that can only provide one attribute at a time: returns the first attribute
+    /// type from the single list of attribute type choices in `state` that can be obtained via
+    /// `source`.  Returns the [`api::auths::AuthStartResp`] to send when there's no such type.
+    pub(super) fn single_attr_type_with_source(
+        &self,
+        state: &AuthState,
+        source: attr::Source,
+    ) -> api::Result<Result<(handle::Handle, &attr::Type), api::auths::AuthStartResp>> {
+        let [attr_ty_options] = state.attr_type_choices.as_slice() else {
+            log::debug!(
+                "{source:?} authentication requested for more (or less) than one attribute"
+            );
+            return Err(api::ErrorCode::BadRequest);
+        };
+
+        let Some(first_attr_ty_handle) = attr_ty_options.first() else {
+            log::debug!(
+                "{source:?} authentication requested with an empty list of attribute types"
+            );
+            return Err(api::ErrorCode::BadRequest);
+        };
+
+        let mut chosen: Option<(handle::Handle, &attr::Type)> = None;
+
+        for attr_ty_handle in attr_ty_options.iter() {
+            let Some(attr_ty) = self.attr_type_from_handle(attr_ty_handle) else {
+                return Ok(Err(api::auths::AuthStartResp::UnknownAttrType(
+                    attr_ty_handle.clone(),
+                )));
+            };
+
+            if chosen.is_none() && attr_ty.has_source(source) {
+                chosen = Some((attr_ty_handle.clone(), attr_ty));
+            }
+        }
+
+        let Some(chosen) = chosen else {
+            log::debug!(
+                "got {source:?} authentication start request for {first_attr_ty_handle}, but {source:?} is not supported for this attribute type",
+            );
+            return Ok(Err(api::auths::AuthStartResp::SourceNotAvailableFor(
+                first_attr_ty_handle.clone(),
+            )));
+        };
+
+        Ok(Ok(chosen))
+    }
+
+    /// Returns the attribute type chosen by [`Self::single_attr_type_with_source`], or `None`
+    /// if it no longer exists.
+    pub(super) fn single_attr_type_of<'s>(
+        &'s self,
+        state: &'s AuthState,
+    ) -> api::Result<Option<(&'s handle::Handle, &'s attr::Type)>> {
+        let Some(attr_type_handle) = state.attr_type_choices.first().and_then(|c| c.first()) else {
+            log::error!(
+                "{:?} authentication state without attribute type",
+                state.source
+            );
+            return Err(api::ErrorCode::InternalError);
+        };
+
+        let Some(attr_type) = self.attr_type_from_handle(attr_type_handle) else {
+            log::warn!(
+                "Attribute type with handle {attr_type_handle} mentioned in authentication state can no longer be found."
+            );
+            return Ok(None);
+        };
+
+        Ok(Some((attr_type_handle, attr_type)))
+    }
+
     /// Creates a disclosure 'conjunction' for the given yivi attribute type identifier.
     ///
     /// This is almost always just the attibute type idenfitier itself, unless we're dealing with
@@ -140,33 +375,31 @@ impl App {
         Ok(result)
     }
 
-    async fn handle_auth_start_yivi(
-        app: Rc<Self>,
-        mut state: AuthState,
-        yivi_chained_session: bool,
-        yivi_chained_session_drip: bool,
-    ) -> api::Result<api::auths::AuthStartResp> {
-        let yivi = app.get_yivi()?;
-
-        let mut sealed_state: Option<api::auths::AuthState> = None;
-
-        let seal_state = |state: &AuthState| -> api::Result<api::auths::AuthState> {
-            state.seal(&app.auth_state_secret)
+    /// Creates the yivi [`AttributeConDisCon`](servers::yivi::AttributeConDisCon) requesting
+    /// one attribute type from each of the lists of alternatives in `mandatory`, and, optionally,
+    /// from each of those in `optional`.
+    pub(super) fn yivi_condiscon_for(
+        &self,
+        mandatory: &[Vec<handle::Handle>],
+        optional: &[Vec<handle::Handle>],
+    ) -> api::Result<Result<YiviConDisCon, YiviConDisConError>> {
+        let mut result = YiviConDisCon {
+            cdc: Default::default(),
+            ati2at: Default::default(),
         };
 
-        // Create ConDisCon for our attributes
-        let mut cdc: servers::yivi::AttributeConDisCon = Default::default(); // empty
-
-        for attr_ty_options in state.attr_type_choices.iter() {
+        // NOTE: the optional attribute types come after the mandatory ones, so that the indices
+        // of `ati2at` match the positions in the disclosure result
+        for (i, attr_ty_options) in mandatory.iter().chain(optional.iter()).enumerate() {
             let mut dc: Vec<Vec<servers::yivi::AttributeRequest>> = Default::default();
             let mut ati2at: HashMap<yivi::AttributeTypeIdentifier, handle::Handle> =
                 Default::default();
 
             for attr_ty_handle in attr_ty_options.iter() {
-                let Some(attr_ty) = app.attr_type_from_handle(attr_ty_handle) else {
-                    return Ok(api::auths::AuthStartResp::UnknownAttrType(
+                let Some(attr_ty) = self.attr_type_from_handle(attr_ty_handle) else {
+                    return Ok(Err(YiviConDisConError::UnknownAttrType(
                         attr_ty_handle.clone(),
-                    ));
+                    )));
                 };
 
                 let mut had_one: bool = false;
@@ -178,31 +411,64 @@ impl App {
                         log::debug!(
                             "attribute types {existing_at_handle} and {attr_ty_handle} both rely on the same yivi attribute type identifier {ati}"
                         );
-                        return Ok(api::auths::AuthStartResp::Conflict(
+                        return Ok(Err(YiviConDisConError::Conflict(
                             existing_at_handle,
                             attr_ty_handle.clone(),
-                        ));
+                        )));
                     }
 
                     had_one = true;
 
-                    dc.push(app.create_disclosure_con_for(ati)?);
+                    dc.push(self.create_disclosure_con_for(ati)?);
                 }
 
                 if !had_one {
                     log::debug!(
-                        "got yivi authentication start request for {attr_ty_handle}, but yivi is not supported for this attribute type",
+                        "got yivi request for {attr_ty_handle}, but yivi is not supported for this attribute type",
                     );
-                    return Ok(api::auths::AuthStartResp::SourceNotAvailableFor(
+                    return Ok(Err(YiviConDisConError::SourceNotAvailableFor(
                         attr_ty_handle.clone(),
-                    ));
+                    )));
                 }
             }
 
-            state.yivi_ati2at.push(ati2at);
-            cdc.push(dc);
+            if i >= mandatory.len() {
+                // an empty inner conjunction makes the disclosure optional; it comes last so
+                // that disclosing the attribute remains the default
+                dc.push(Vec::new());
+            }
+
+            result.ati2at.push(ati2at);
+            result.cdc.push(dc);
         }
 
+        Ok(Ok(result))
+    }
+
+    async fn handle_auth_start_yivi(
+        app: Rc<Self>,
+        mut state: AuthState,
+        yivi_chained_session: bool,
+        yivi_chained_session_drip: bool,
+    ) -> api::Result<api::auths::AuthStartResp> {
+        let yivi = app.get_yivi()?;
+
+        let mut sealed_state: Option<api::auths::AuthState> = None;
+
+        let seal_state = |state: &AuthState| -> api::Result<api::auths::AuthState> {
+            state.seal(&app.auth_state_secret)
+        };
+
+        // Create ConDisCon for our attributes
+        let YiviConDisCon { cdc, ati2at } = match app
+            .yivi_condiscon_for(&state.attr_type_choices, &state.optional_attr_type_choices)?
+        {
+            Ok(ycdc) => ycdc,
+            Err(err) => return Ok(err.into()),
+        };
+
+        state.yivi_ati2at = ati2at;
+
         let disclosure_request: jwt::JWT = {
             let mut dr = servers::yivi::ExtendedSessionRequest::disclosure(cdc);
 
@@ -286,15 +552,94 @@ impl App {
                     state,
                     match req.proof {
                         api::auths::AuthProof::Yivi { disclosure } => disclosure,
-                        #[expect(unreachable_patterns)]
                         _ => return Err(api::ErrorCode::BadRequest),
                     },
                 )
                 .await
             }
+            attr::Source::Email => {
+                Self::handle_auth_complete_email(
+                    app,
+                    state,
+                    match req.proof {
+                        api::auths::AuthProof::Email { code } => code,
+                        _ => return Err(api::ErrorCode::BadRequest),
+                    },
+                )
+                .await
+            }
+            attr::Source::Oidc => {
+                let api::auths::AuthProof::Oidc {
+                    code,
+                    state: oauth_state,
+                } = req.proof
+                else {
+                    return Err(api::ErrorCode::BadRequest);
+                };
+
+                Self::handle_auth_complete_oidc(app, state, code, oauth_state).await
+            }
+            attr::Source::WebAuthn => {
+                let api::auths::AuthProof::WebAuthn {
+                    public_key,
+                    authenticator_data,
+                    client_data_json,
+                    signature,
+                } = req.proof
+                else {
+                    return Err(api::ErrorCode::BadRequest);
+                };
+
+                Self::handle_auth_complete_webauthn(
+                    app,
+                    state,
+                    public_key,
+                    &authenticator_data,
+                    &client_data_json,
+                    &signature,
+                )
+                .await
+            }
         }
     }
 
+    /// Issues an [`attr::Attr`] of the given type and value, obtained from `source`, signed for
+    /// pubhubs central.  The value is normalized according to [`attr::Type::normalizer`];  values
+    /// that cannot be normalized result in an [`api::ErrorCode::BadRequest`].
+    pub(super) fn issue_attr(
+        &self,
+        attr_type: &attr::Type,
+        source: attr::Source,
+        value: String,
+    ) -> api::Result<api::Signed<attr::Attr>> {
+        let running_state = self.running_state_or_internal_error()?;
+
+        let normalized_value = attr_type.normalizer.normalize(&value).map_err(|err| {
+            log::debug!(
+                "could not normalize value of {source:?} attribute of type {attr_type}: {err:#}"
+            );
+            api::ErrorCode::BadRequest
+        })?;
+
+        let unnormalized_value = (normalized_value != value).then_some(value);
+
+        // TODO: attr_signing_key is constellation-dependent;  provide a mechanism
+        // for the client to detect constellation change
+        api::Signed::<attr::Attr>::new(
+            &running_state.attr_signing_key,
+            &attr::Attr {
+                attr_type: attr_type.id,
+                value: normalized_value,
+                bannable: attr_type.bannable,
+                not_identifying: !attr_type.identifying,
+                not_addable: attr_type.not_addable_by_default,
+                source: Some(source),
+                unnormalized_value,
+            },
+            self.auth_window,
+        )
+    }
+
     async fn handle_auth_complete_yivi(
         app: Rc<Self>,
         state: AuthState,
@@ -308,20 +653,40 @@ impl App {
                 api::ErrorCode::BadRequest
             })?;
 
-        let mut attrs: IndexMap<handle::Handle, api::Signed<attr::Attr>> =
-            IndexMap::with_capacity(state.attr_type_choices.len());
+        // NOTE: the result of a signature session (see `api::auths::YiviSignEP`) is handed to
+        // hubs, and so must not be usable to authenticate
+        if ssr.session_type != yivi::SessionType::Disclosing {
+            log::debug!(
+                "session result of type {} submitted instead of a disclosure",
+                ssr.session_type
+            );
+            return Err(api::ErrorCode::BadRequest);
+        }
 
-        let running_state = app.running_state_or_internal_error()?;
+        let mut attrs: IndexMap<handle::Handle, api::Signed<attr::Attr>> =
+            IndexMap::with_capacity(state.yivi_ati2at.len());
 
-        for (i, result) in ssr
+        let results: Vec<_> = ssr
             .validate_and_extract_raw_singles()
             .map_err(|err| {
                 log::debug!("invalid session result submitted: {err}");
                 api::ErrorCode::BadRequest
             })?
-            .enumerate()
-        {
-            let (yati, raw_value): (&yivi::AttributeTypeIdentifier, &str) =
+            .collect();
+
+        if results.len() != state.yivi_ati2at.len() {
+            // NOTE: debug! and BadRequest, and not warn! and InternalError,
+            // because clients can swap result JWTs from different yivi sessions
+            log::debug!(
+                "submitted session result discloses {} attributes, but {} were requested",
+                results.len(),
+                state.yivi_ati2at.len()
+            );
+            return Err(api::ErrorCode::BadRequest);
+        }
+
+        for (i, result) in results.into_iter().enumerate() {
+            let disclosed: Option<(&yivi::AttributeTypeIdentifier, &str)> =
                 result.map_err(|err| {
                     log::debug!(
                         "problem with attribute number {i} of submitted session result: {err}",
@@ -329,11 +694,19 @@ impl App {
                     api::ErrorCode::BadRequest
                 })?;
 
+            let Some((yati, raw_value)) = disclosed else {
+                if i < state.attr_type_choices.len() {
+                    log::debug!("mandatory attribute number {i} not disclosed in session result");
+                    return Err(api::ErrorCode::BadRequest);
+                }
+
+                // the user chose not to disclose this optional attribute
+                continue;
+            };
+
             let Some(ati2at) = state.yivi_ati2at.get(i) else {
-                // NOTE: debug! and BadRequest, and not warn! and InternalError,
-                // because clients can swap result JWTs from different yivi sessions
-                log::debug!("extra attributes disclosed in submitted session result");
-                return Err(api::ErrorCode::BadRequest);
+                log::error!("expected to have already erred on the number of disclosed attributes");
+                return Err(api::ErrorCode::InternalError);
             };
 
             let Some(attr_type_handle) = ati2at.get(yati) else {
@@ -359,19 +732,7 @@ impl App {
 
             let old_value = attrs.insert(
                 attr_type_handle.clone(),
-                // TODO: attr_signing_key is constellation-dependent;  provide a mechanism
-                // for the client to detect constellation change
-                api::Signed::<attr::Attr>::new(
-                    &running_state.attr_signing_key,
-                    &attr::Attr {
-                        attr_type: attr_type.id,
-                        value: raw_value.to_string(),
-                        bannable: attr_type.bannable,
-                        not_identifying: !attr_type.identifying,
-                        not_addable: attr_type.not_addable_by_default,
-                    },
-                    app.auth_window,
-                )?,
+                app.issue_attr(attr_type, attr::Source::Yivi, raw_value.to_string())?,
             );
 
             if old_value.is_some() {
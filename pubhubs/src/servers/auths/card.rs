@@ -1,4 +1,4 @@
-//! User endpoints related to the issuance of cards
+//! Endpoints related to the issuance of cards and other credentials
 use crate::api;
 use crate::api::OpenError;
 use crate::attr;
@@ -8,7 +8,7 @@ use crate::servers::yivi;
 
 use actix_web::web;
 
-use std::collections::{BTreeSet, VecDeque};
+use std::collections::{BTreeSet, HashSet, VecDeque};
 use std::rc::Rc;
 
 use super::server::*;
@@ -35,6 +35,12 @@ pub struct CardConfig {
     /// Use [`App::registration_source()`] to get the default.
     #[serde(default)]
     registration_source: Option<String>,
+
+    /// Whether to issue cards with a yivi revocation key, so that they can be revoked at the yivi
+    /// server (see [`api::auths::CardRevocationEP`]).  Requires the card's credential type to
+    /// support revocation, and the yivi server to be configured for it.
+    #[serde(default)]
+    pub revocation: bool,
 }
 
 /// Configuration of PubHubs card issuance
@@ -149,6 +155,33 @@ impl Default for CardConfig {
     }
 }
 
+/// Configuration of a hub-specific credential, see [`api::auths::HubCredentialEP`].
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct HubCredentialConfig {
+    /// The yivi credential type to issue
+    pub credential: yivi::CredentialTypeIdentifier,
+
+    /// Handles of the hubs that may have this credential issued.  The hub must use a ticket
+    /// obtained for one of these handles.
+    pub hubs: HashSet<Handle>,
+
+    /// The attribute of the credential that is set to the user's hub pseudonym
+    pub pseudonym_attribute: String,
+
+    /// The attribute of the credential, if any, that is set to the handle of the hub
+    #[serde(default)]
+    pub hub_attribute: Option<String>,
+
+    /// The other attributes of the credential, whose values are provided by the hub
+    #[serde(default)]
+    pub attributes: BTreeSet<String>,
+
+    /// For how long is the credential valid?  Like [`CardConfig::valid_for`].
+    #[serde(default)]
+    pub valid_for: CardValidFor,
+}
+
 /// The different types of PubHubs cards
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, Default)]
 #[serde(rename_all = "snake_case")]
@@ -195,6 +228,18 @@ impl App {
         self.phc_url.as_str()
     }
 
+    /// Returns the yivi credential type of the PubHubs card
+    fn card_credential(yivi: &YiviCtx) -> api::Result<yivi::CredentialTypeIdentifier> {
+        yivi.card_config
+            .card_type
+            .credential()
+            .parse()
+            .map_err(|err| {
+                log::error!("failed to parse pubhubs card yivi credential: {err}");
+                api::ErrorCode::InternalError
+            })
+    }
+
     /// Creates a yivi issuance request and pubhubs attribute for a PubHubs card
     pub(crate) fn issue_card(
         &self,
@@ -215,29 +260,28 @@ impl App {
 
         let card_pseud = card_pseud_package.card_pseud.to_string();
 
-        let credential = yivi::CredentialToBeIssued::new(
-            yivi.card_config
-                .card_type
-                .credential()
-                .parse()
-                .map_err(|err| {
-                    log::error!("failed to parse pubhubs card yivi credential: {err}");
-                    api::ErrorCode::InternalError
-                })?,
-        )
-        .valid_for(yivi.card_config.valid_for.now())
-        .attribute(
-            yivi.card_config.card_type.id().to_string(),
-            card_pseud.clone(),
-        )
-        .attribute(
-            yivi.card_config.card_type.source().to_string(),
-            self.registration_source(yivi).to_string(),
-        )
-        .attribute(
-            yivi.card_config.card_type.date().to_string(),
-            registration_date,
-        );
+        let mut credential = yivi::CredentialToBeIssued::new(Self::card_credential(yivi)?)
+            .valid_for(yivi.card_config.valid_for.now())
+            .attribute(
+                yivi.card_config.card_type.id().to_string(),
+                card_pseud.clone(),
+            )
+            .attribute(
+                yivi.card_config.card_type.source().to_string(),
+                self.registration_source(yivi).to_string(),
+            )
+            .attribute(
+                yivi.card_config.card_type.date().to_string(),
+                registration_date,
+            );
+
+        if yivi.card_config.revocation {
+            if let Some(revocation_nonce) = card_pseud_package.revocation_nonce {
+                credential = credential.revocation_key(revocation_nonce.to_string());
+            } else {
+                log::warn!("issuing irrevocable card, because PHC provided no revocation nonce");
+            }
+        }
 
         let esr = yivi::ExtendedSessionRequest::issuance(vec![credential]);
 
@@ -255,6 +299,8 @@ impl App {
             bannable: attr_type.bannable,
             not_identifying: !attr_type.identifying,
             not_addable: false,
+            source: None,
+            unnormalized_value: None,
         };
 
         Ok((esr, attr))
@@ -305,6 +351,150 @@ impl App {
             yivi_requestor_url: yivi.requestor_url.clone(),
         })
     }
+
+    /// Implements [`api::auths::CardRevocationEP`].
+    pub async fn handle_card_revocation(
+        app: Rc<Self>,
+        signed_req: web::Json<api::Signed<api::auths::CardRevocationReq>>,
+    ) -> api::Result<api::auths::CardRevocationResp> {
+        let yivi = app.get_yivi()?;
+        let running_state = app.running_state_or_please_retry()?;
+
+        let req = match signed_req
+            .into_inner()
+            .open(&running_state.phc_verifying_key, None)
+        {
+            Ok(req) => req,
+            Err(OpenError::OtherConstellation(..)) | Err(OpenError::InternalError) => {
+                return Err(api::ErrorCode::InternalError);
+            }
+            Err(OpenError::OtherwiseInvalid) | Err(OpenError::InvalidSignature) => {
+                return Err(api::ErrorCode::BadRequest);
+            }
+            Err(OpenError::Expired) => {
+                return Ok(api::auths::CardRevocationResp::ResignRequest);
+            }
+        };
+
+        if !yivi.card_config.revocation {
+            return Ok(api::auths::CardRevocationResp::RevocationNotEnabled);
+        }
+
+        let revocation_request = yivi::RevocationRequest::new(
+            Self::card_credential(yivi)?,
+            req.revocation_nonce.to_string(),
+        )
+        .sign(&yivi.requestor_creds)
+        .map_err(|err| {
+            log::error!("failed to sign card revocation request: {err:?}");
+            api::ErrorCode::InternalError
+        })?;
+
+        let url = yivi.requestor_url.join("revocation").map_err(|err| {
+            log::error!("failed to form yivi revocation url: {err}");
+            api::ErrorCode::InternalError
+        })?;
+
+        let resp = awc::Client::default()
+            .post(url.as_str())
+            .send_body(revocation_request.as_str().to_string())
+            .await
+            .map_err(|err| {
+                log::error!("failed to reach yivi server at {url} to revoke cards: {err}");
+                api::ErrorCode::InternalError
+            })?;
+
+        if !resp.status().is_success() {
+            log::error!(
+                "yivi server at {url} refused to revoke cards with status {}",
+                resp.status()
+            );
+            return Err(api::ErrorCode::InternalError);
+        }
+
+        log::info!(
+            "revoked cards with revocation nonce {}",
+            req.revocation_nonce
+        );
+
+        Ok(api::auths::CardRevocationResp::Success)
+    }
+
+    /// Implements [`api::auths::HubCredentialEP`].
+    pub async fn handle_hub_credential(
+        app: Rc<Self>,
+        signed_req: web::Json<api::phc::hub::TicketSigned<api::auths::HubCredentialReq>>,
+    ) -> api::Result<api::auths::HubCredentialResp> {
+        let yivi = app.get_yivi()?;
+        let running_state = app.running_state_or_please_retry()?;
+
+        let ts_req = signed_req.into_inner();
+
+        if app
+            .revocations
+            .get(&app.base)
+            .await?
+            .is_revoked(&ts_req.ticket)
+        {
+            return Ok(api::auths::HubCredentialResp::RetryWithNewTicket);
+        }
+
+        let (req, hub_handle) = match ts_req.open(&running_state.phc_verifying_key) {
+            Ok(opened) => opened,
+            Err(toe) => {
+                return toe.default_verdict(api::auths::HubCredentialResp::RetryWithNewTicket);
+            }
+        };
+
+        let Some(hc_config) = yivi
+            .hub_credentials
+            .get(&req.credential)
+            .filter(|hc_config| hc_config.hubs.contains(&hub_handle))
+        else {
+            log::debug!(
+                "hub {hub_handle} requested hub credential {} that it may not issue",
+                req.credential
+            );
+            return Ok(api::auths::HubCredentialResp::UnknownCredential);
+        };
+
+        if req.attributes.len() != hc_config.attributes.len()
+            || !hc_config
+                .attributes
+                .iter()
+                .all(|attr| req.attributes.contains_key(attr))
+        {
+            return Ok(api::auths::HubCredentialResp::InvalidAttributes);
+        }
+
+        let mut credential = yivi::CredentialToBeIssued::new(hc_config.credential.clone())
+            .valid_for(hc_config.valid_for.now());
+
+        for (attr, value) in req.attributes {
+            credential = credential.attribute(attr, value);
+        }
+
+        // NOTE: set after the attributes provided by the hub, so that these cannot be overridden
+        credential = credential.attribute(hc_config.pseudonym_attribute.clone(), req.hub_pseudonym);
+
+        if let Some(hub_attribute) = hc_config.hub_attribute.as_ref() {
+            credential = credential.attribute(hub_attribute.clone(), hub_handle.to_string());
+        }
+
+        let issuance_request = yivi::ExtendedSessionRequest::issuance(vec![credential])
+            .sign(&yivi.requestor_creds)
+            .map_err(|err| {
+                log::error!(
+                    "failed to sign extended session request for issuance of hub credential: {err:?}"
+                );
+                api::ErrorCode::InternalError
+            })?;
+
+        Ok(api::auths::HubCredentialResp::Success {
+            issuance_request,
+            yivi_requestor_url: yivi.requestor_url.clone(),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,297 @@
+//! Implementation of the [`attr::Source::Email`] source: one-time codes sent by email.
+use super::server::*;
+
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+use indexmap::IndexMap;
+use lettre::AsyncTransport as _;
+use subtle::ConstantTimeEq as _;
+
+use crate::servers::config::auths::EmailConfig;
+use crate::{api, attr, handle, id};
+
+/// Details on how this authentication server sends one-time codes.
+///
+/// Cheaply cloneable;  clones share [`EmailCtx::attempts`] and [`EmailCtx::sent`].
+#[derive(Clone)]
+pub struct EmailCtx {
+    transport: lettre::AsyncSmtpTransport<lettre::Tokio1Executor>,
+    from: lettre::message::Mailbox,
+    subject: String,
+    code_validity: core::time::Duration,
+    max_attempts: u8,
+    resend_interval: core::time::Duration,
+    max_codes_per_hour: u8,
+
+    /// When codes were sent to each (normalized) address during the past hour, so that the
+    /// authentication server can not be used to flood someone's inbox.
+    ///
+    /// Kept in memory only:  the authentication server has no object store.
+    sent: Arc<Mutex<HashMap<String, Vec<std::time::Instant>>>>,
+
+    /// Keeps track of the number of attempts made to enter the one-time code of each
+    /// [`EmailAuthState`], by [`EmailAuthState::nonce`].  This is needed because the client can
+    /// submit the same sealed [`AuthState`] over and over again.
+    attempts: Arc<Mutex<HashMap<id::Id, CodeAttempts>>>,
+}
+
+struct CodeAttempts {
+    /// When the code expires, after which this entry can be forgotten
+    exp: api::NumericDate,
+
+    /// Number of incorrect codes submitted
+    incorrect: u8,
+
+    /// Whether the code has already been used
+    used: bool,
+}
+
+/// Outcome of [`EmailCtx::check_code`]
+enum CodeCheck {
+    Correct,
+    Incorrect,
+
+    /// The code was already used, or too many incorrect codes were submitted
+    Exhausted,
+}
+
+/// Part of [`AuthState`] for [`attr::Source::Email`]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub(super) struct EmailAuthState {
+    /// The email address the code was sent to
+    pub address: String,
+
+    /// The one-time code - not visible to the client, because [`AuthState`] is sealed.
+    pub code: String,
+
+    /// Identifies this authentication attempt in [`EmailCtx::attempts`]
+    pub nonce: id::Id,
+}
+
+impl EmailCtx {
+    pub fn new(cfg: &EmailConfig) -> anyhow::Result<Self> {
+        let transport = lettre::AsyncSmtpTransport::<lettre::Tokio1Executor>::from_url(
+            cfg.relay_url.as_ref().as_str(),
+        )
+        .map_err(|err| anyhow::anyhow!("invalid email relay_url: {err}"))?
+        .build();
+
+        let from: lettre::message::Mailbox = cfg
+            .from
+            .parse()
+            .map_err(|err| anyhow::anyhow!("invalid email from address {}: {err}", cfg.from))?;
+
+        Ok(Self {
+            transport,
+            from,
+            subject: cfg.subject.clone(),
+            code_validity: cfg.code_validity,
+            max_attempts: cfg.max_attempts,
+            resend_interval: cfg.resend_interval,
+            max_codes_per_hour: cfg.max_codes_per_hour,
+            attempts: Default::default(),
+            sent: Default::default(),
+        })
+    }
+
+    /// Records that a code is about to be sent to `address`, unless too many codes were sent to
+    /// it recently, in which case `false` is returned.
+    ///
+    /// Addresses are compared after [`attr::Normalizer::Email`], so that case variations do not
+    /// evade the limit.  Unlike the normalizer, `+tags` are removed for every domain here: counting
+    /// two mailboxes as one only makes the limit stricter.
+    fn may_send_code_to(&self, address: &lettre::Address) -> bool {
+        const HOUR: core::time::Duration = core::time::Duration::from_secs(3600);
+
+        let normalized = attr::Normalizer::Email
+            .normalize(address.as_ref())
+            .unwrap_or_else(|_| address.to_string());
+
+        let key = match normalized.rsplit_once('@') {
+            Some((local, domain)) if !local.starts_with('+') => {
+                let local = local.split_once('+').map_or(local, |(local, _tag)| local);
+                format!("{local}@{domain}")
+            }
+            _ => normalized,
+        };
+
+        let mut sent = self.sent.lock().unwrap();
+
+        sent.retain(|_, sent_at| {
+            sent_at.retain(|at| at.elapsed() < HOUR);
+            !sent_at.is_empty()
+        });
+
+        let sent_at = sent.entry(key).or_default();
+
+        if sent_at.len() >= self.max_codes_per_hour as usize
+            || sent_at
+                .last()
+                .is_some_and(|at| at.elapsed() < self.resend_interval)
+        {
+            return false;
+        }
+
+        sent_at.push(std::time::Instant::now());
+
+        true
+    }
+
+    /// Generates a random one-time code of 6 digits.
+    fn generate_code() -> String {
+        use rand::RngExt as _;
+
+        let code: u32 = rand::rand_core::UnwrapErr(rand::rngs::SysRng).random_range(0..1_000_000);
+
+        format!("{code:06}")
+    }
+
+    async fn send_code(&self, to: lettre::Address, code: &str) -> api::Result<()> {
+        let message = lettre::Message::builder()
+            .from(self.from.clone())
+            .to(lettre::message::Mailbox::new(None, to))
+            .subject(&self.subject)
+            .header(lettre::message::header::ContentType::TEXT_PLAIN)
+            .body(format!(
+                "Your PubHubs code is {code}.\n\n\
+                This code is valid for {}.  If you did not request this code, \
+                you can ignore this email.\n",
+                humantime::format_duration(self.code_validity)
+            ))
+            .map_err(|err| {
+                log::error!("failed to compose one-time code email: {err}");
+                api::ErrorCode::InternalError
+            })?;
+
+        self.transport.send(message).await.map_err(|err| {
+            log::error!("failed to send one-time code email: {err}");
+            api::ErrorCode::InternalError
+        })?;
+
+        Ok(())
+    }
+
+    /// Records an attempt to enter the one-time code of the given [`EmailAuthState`].
+    fn check_code(
+        &self,
+        email_state: &EmailAuthState,
+        exp: api::NumericDate,
+        code: &str,
+    ) -> CodeCheck {
+        let now = api::NumericDate::now();
+
+        let mut attempts = self.attempts.lock().unwrap();
+
+        attempts.retain(|_, ca| ca.exp >= now);
+
+        let ca = attempts
+            .entry(email_state.nonce)
+            .or_insert_with(|| CodeAttempts {
+                exp,
+                incorrect: 0,
+                used: false,
+            });
+
+        if ca.used || ca.incorrect >= self.max_attempts {
+            return CodeCheck::Exhausted;
+        }
+
+        if bool::from(code.as_bytes().ct_eq(email_state.code.as_bytes())) {
+            ca.used = true;
+            return CodeCheck::Correct;
+        }
+
+        ca.incorrect += 1;
+
+        if ca.incorrect >= self.max_attempts {
+            return CodeCheck::Exhausted;
+        }
+
+        CodeCheck::Incorrect
+    }
+}
+
+/// # Implementation of endpoints
+impl App {
+    pub(super) async fn handle_auth_start_email(
+        app: Rc<Self>,
+        mut state: AuthState,
+        address: String,
+    ) -> api::Result<api::auths::AuthStartResp> {
+        let email = app.get_email()?;
+
+        // NOTE: an email address proves only one attribute
+        let (attr_ty_handle, _) =
+            match app.single_attr_type_with_source(&state, attr::Source::Email)? {
+                Ok(chosen) => chosen,
+                Err(resp) => return Ok(resp),
+            };
+
+        let Ok(to) = address.parse::<lettre::Address>() else {
+            return Ok(api::auths::AuthStartResp::InvalidEmailAddress);
+        };
+
+        if !email.may_send_code_to(&to) {
+            log::debug!("not sending another one-time code to {to} so soon");
+            return Ok(api::auths::AuthStartResp::TooManyCodesRequested);
+        }
+
+        let code = EmailCtx::generate_code();
+
+        state.attr_type_choices = vec![vec![attr_ty_handle]];
+        state.exp = api::NumericDate::now().add_clamp(email.code_validity.as_secs());
+        state.email = Some(EmailAuthState {
+            address: to.to_string(),
+            code: code.clone(),
+            nonce: id::Id::random(),
+        });
+
+        let sealed_state = state.seal(&app.auth_state_secret)?;
+
+        email.send_code(to, &code).await?;
+
+        Ok(api::auths::AuthStartResp::Success {
+            task: api::auths::AuthTask::Email {},
+            state: sealed_state,
+        })
+    }
+
+    pub(super) async fn handle_auth_complete_email(
+        app: Rc<Self>,
+        state: AuthState,
+        code: String,
+    ) -> api::Result<api::auths::AuthCompleteResp> {
+        let email = app.get_email()?;
+
+        let Some(email_state) = state.email.as_ref() else {
+            log::error!("email authentication state without email details");
+            return Err(api::ErrorCode::InternalError);
+        };
+
+        match email.check_code(email_state, state.exp, &code) {
+            CodeCheck::Correct => {}
+            CodeCheck::Incorrect => {
+                return Ok(api::auths::AuthCompleteResp::IncorrectCode);
+            }
+            CodeCheck::Exhausted => {
+                log::debug!("one-time code submitted for exhausted email authentication");
+                return Ok(api::auths::AuthCompleteResp::PleaseRestartAuth);
+            }
+        }
+
+        let Some((attr_type_handle, attr_type)) = app.single_attr_type_of(&state)? else {
+            return Ok(api::auths::AuthCompleteResp::PleaseRestartAuth);
+        };
+
+        let mut attrs: IndexMap<handle::Handle, api::Signed<attr::Attr>> = IndexMap::new();
+
+        attrs.insert(
+            attr_type_handle.clone(),
+            app.issue_attr(attr_type, attr::Source::Email, email_state.address.clone())?,
+        );
+
+        Ok(api::auths::AuthCompleteResp::Success { attrs })
+    }
+}
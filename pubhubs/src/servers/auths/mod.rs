@@ -1,8 +1,11 @@
 //! Server: Authentication Server
 mod auth;
 pub(crate) mod card;
+mod email;
 mod keys;
+mod oidc;
 mod server;
+mod webauthn;
 pub(crate) mod yivi;
 
 pub use server::{Details, Server};
@@ -0,0 +1,512 @@
+//! Implementation of the [`attr::Source::Oidc`] source: the OpenID Connect authorization code flow
+//! with PKCE (RFC7636).
+use super::server::*;
+
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+use base64ct::{Base64UrlUnpadded, Encoding as _};
+use indexmap::IndexMap;
+use sha2::Digest as _;
+
+use crate::misc::{
+    crypto,
+    jwt::{self, Key as _},
+};
+use crate::servers::config::auths::OidcConfig;
+use crate::{api, attr, handle};
+
+/// Details on how this authentication server talks to OpenID Connect providers.
+///
+/// Cheaply cloneable;  clones share [`OidcCtx::providers`].
+#[derive(Clone)]
+pub struct OidcCtx {
+    redirect_url: url::Url,
+    client_secrets: HashMap<String, String>,
+    provider_cache: core::time::Duration,
+
+    /// Discovered providers, by issuer
+    providers: Arc<Mutex<HashMap<url::Url, CachedProvider>>>,
+}
+
+struct CachedProvider {
+    fetched_at: std::time::Instant,
+    provider: Arc<Provider>,
+}
+
+/// What we need to know about an OpenID Connect provider, obtained via discovery.
+struct Provider {
+    metadata: ProviderMetadata,
+
+    /// The provider's RS256 keys, by key id
+    keys: Vec<(Option<String>, jwt::RS256Vk)>,
+}
+
+/// The parts of the provider's `/.well-known/openid-configuration` we use.
+#[derive(serde::Deserialize, Debug)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: url::Url,
+    token_endpoint: url::Url,
+    jwks_uri: url::Url,
+}
+
+/// JSON Web Key Set, see RFC7517, Section 5.
+#[derive(serde::Deserialize, Debug)]
+struct Jwks {
+    keys: Vec<Jwk>,
+}
+
+/// The parts of a JSON Web Key we use to obtain an RSA public key.
+#[derive(serde::Deserialize, Debug)]
+struct Jwk {
+    kty: String,
+    kid: Option<String>,
+    #[serde(rename = "use")]
+    use_: Option<String>,
+    alg: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
+}
+
+/// The parts of the token endpoint's response we use.
+#[derive(serde::Deserialize, Debug)]
+struct TokenResp {
+    id_token: jwt::JWT,
+}
+
+/// Part of [`AuthState`] for [`attr::Source::Oidc`]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub(super) struct OidcAuthState {
+    /// PKCE code verifier - not visible to the client, because [`AuthState`] is sealed.
+    pub code_verifier: String,
+
+    /// The OAuth 2.0 `state` parameter the provider must return
+    pub state: String,
+
+    /// The `nonce` the ID token must contain
+    pub nonce: String,
+}
+
+/// Checks whether the issuer identifiers `a` and `b` are the same, ignoring a trailing slash,
+/// which [`url::Url`] adds to urls without path.
+fn same_issuer(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/') == b.trim_end_matches('/')
+}
+
+/// Retrieves and parses json from an OpenID Connect provider.
+async fn get_json<T: serde::de::DeserializeOwned>(
+    client: &awc::Client,
+    url: &url::Url,
+) -> api::Result<T> {
+    let mut resp = client.get(url.as_str()).send().await.map_err(|err| {
+        log::warn!("failed to reach OpenID Connect provider at {url}: {err}");
+        api::ErrorCode::PleaseRetry
+    })?;
+
+    if !resp.status().is_success() {
+        log::warn!(
+            "OpenID Connect provider returned status {} for {url}",
+            resp.status()
+        );
+        return Err(api::ErrorCode::PleaseRetry);
+    }
+
+    resp.json::<T>().await.map_err(|err| {
+        log::warn!("invalid response from OpenID Connect provider at {url}: {err}");
+        api::ErrorCode::InternalError
+    })
+}
+
+impl Provider {
+    async fn discover(issuer: &url::Url) -> api::Result<Self> {
+        let client = awc::Client::default();
+
+        let discovery_url: url::Url = format!(
+            "{}/.well-known/openid-configuration",
+            issuer.as_str().trim_end_matches('/')
+        )
+        .parse()
+        .map_err(|err| {
+            log::error!("failed to form discovery url for OpenID Connect issuer {issuer}: {err}");
+            api::ErrorCode::InternalError
+        })?;
+
+        let metadata: ProviderMetadata = get_json(&client, &discovery_url).await?;
+
+        if !same_issuer(&metadata.issuer, issuer.as_str()) {
+            log::warn!(
+                "OpenID Connect provider at {issuer} claims to be issuer {}",
+                metadata.issuer
+            );
+            return Err(api::ErrorCode::InternalError);
+        }
+
+        let jwks: Jwks = get_json(&client, &metadata.jwks_uri).await?;
+
+        let keys = jwks
+            .keys
+            .into_iter()
+            .filter(|jwk| {
+                jwk.kty == "RSA"
+                    && jwk.use_.as_deref().unwrap_or("sig") == "sig"
+                    && jwk.alg.as_deref().unwrap_or(jwt::RS256Vk::ALG) == jwt::RS256Vk::ALG
+            })
+            .filter_map(|jwk| {
+                let (Some(n), Some(e)) = (jwk.n.as_deref(), jwk.e.as_deref()) else {
+                    log::debug!("skipping RSA key without n or e from {issuer}");
+                    return None;
+                };
+
+                let vk = Base64UrlUnpadded::decode_vec(n)
+                    .ok()
+                    .zip(Base64UrlUnpadded::decode_vec(e).ok())
+                    .and_then(|(n, e)| jwt::RS256Vk::from_jwk_components(&n, &e).ok());
+
+                if vk.is_none() {
+                    log::debug!("skipping invalid RSA key from {issuer}");
+                }
+
+                Some((jwk.kid, vk?))
+            })
+            .collect();
+
+        Ok(Self { metadata, keys })
+    }
+
+    /// Returns the key with the given key id, or all keys when the key id is not given.
+    fn keys_for<'s>(&'s self, kid: Option<&'s str>) -> impl Iterator<Item = &'s jwt::RS256Vk> {
+        self.keys
+            .iter()
+            .filter(move |(key_kid, _)| kid.is_none() || key_kid.as_deref() == kid)
+            .map(|(_, vk)| vk)
+    }
+}
+
+impl OidcCtx {
+    pub fn new(cfg: &OidcConfig) -> Self {
+        Self {
+            redirect_url: cfg.redirect_url.as_ref().clone(),
+            client_secrets: cfg.client_secrets.clone(),
+            provider_cache: cfg.provider_cache,
+            providers: Default::default(),
+        }
+    }
+
+    /// Returns the provider for `issuer`, discovering it when it's not cached, or when `refresh`
+    /// is set.
+    async fn provider(&self, issuer: &url::Url, refresh: bool) -> api::Result<Arc<Provider>> {
+        if !refresh
+            && let Some(cached) = self.providers.lock().unwrap().get(issuer)
+            && cached.fetched_at.elapsed() < self.provider_cache
+        {
+            return Ok(cached.provider.clone());
+        }
+
+        let provider = Arc::new(Provider::discover(issuer).await?);
+
+        self.providers.lock().unwrap().insert(
+            issuer.clone(),
+            CachedProvider {
+                fetched_at: std::time::Instant::now(),
+                provider: provider.clone(),
+            },
+        );
+
+        Ok(provider)
+    }
+
+    /// Exchanges the authorization `code` for an ID token.  Returns `None` when the provider
+    /// rejects the code.
+    async fn exchange_code(
+        &self,
+        provider: &Provider,
+        source: &attr::OidcSource,
+        oidc_state: &OidcAuthState,
+        code: &str,
+    ) -> api::Result<Option<jwt::JWT>> {
+        let mut form: Vec<(&str, &str)> = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.redirect_url.as_str()),
+            ("client_id", &source.client_id),
+            ("code_verifier", &oidc_state.code_verifier),
+        ];
+
+        if let Some(client_secret) = self.client_secrets.get(&source.client_id) {
+            form.push(("client_secret", client_secret));
+        }
+
+        let token_endpoint = &provider.metadata.token_endpoint;
+
+        let mut resp = awc::Client::default()
+            .post(token_endpoint.as_str())
+            .insert_header((awc::http::header::ACCEPT, "application/json"))
+            .send_form(&form)
+            .await
+            .map_err(|err| {
+                log::warn!("failed to reach OpenID Connect token endpoint {token_endpoint}: {err}");
+                api::ErrorCode::PleaseRetry
+            })?;
+
+        if resp.status().is_client_error() {
+            log::debug!(
+                "OpenID Connect token endpoint {token_endpoint} rejected authorization code with status {}",
+                resp.status()
+            );
+            return Ok(None);
+        }
+
+        if !resp.status().is_success() {
+            log::warn!(
+                "OpenID Connect token endpoint {token_endpoint} returned status {}",
+                resp.status()
+            );
+            return Err(api::ErrorCode::PleaseRetry);
+        }
+
+        let token_resp: TokenResp = resp.json().await.map_err(|err| {
+            log::warn!(
+                "invalid response from OpenID Connect token endpoint {token_endpoint}: {err}"
+            );
+            api::ErrorCode::InternalError
+        })?;
+
+        Ok(Some(token_resp.id_token))
+    }
+
+    /// Verifies the given ID token, and returns the value of the claim configured in `source`.
+    async fn verify_id_token(
+        &self,
+        mut provider: Arc<Provider>,
+        source: &attr::OidcSource,
+        oidc_state: &OidcAuthState,
+        id_token: &jwt::JWT,
+    ) -> api::Result<String> {
+        let invalid = |err: jwt::Error| {
+            log::warn!(
+                "invalid ID token from OpenID Connect provider {}: {err:#}",
+                source.issuer
+            );
+            api::ErrorCode::InternalError
+        };
+
+        let kid: Option<String> = id_token.kid().map_err(invalid)?;
+
+        // The provider may have rotated its keys since we discovered it
+        if provider.keys_for(kid.as_deref()).next().is_none() {
+            provider = self.provider(&source.issuer, true).await?;
+        }
+
+        // Without key id, we try all keys, keeping the last error
+        let mut result: Option<Result<jwt::Claims, jwt::Error>> = None;
+
+        for vk in provider.keys_for(kid.as_deref()) {
+            result = Some(id_token.open(vk));
+
+            if matches!(result, Some(Ok(_))) {
+                break;
+            }
+        }
+
+        let Some(result) = result else {
+            log::warn!(
+                "OpenID Connect provider {} has no RS256 key with key id {kid:?}",
+                source.issuer
+            );
+            return Err(api::ErrorCode::InternalError);
+        };
+
+        let claims = result
+            .map_err(invalid)?
+            .check_iss(
+                |claim_name: &'static str, iss: Option<String>| -> Result<(), jwt::Error> {
+                    let Some(iss) = iss else {
+                        return Err(jwt::Error::MissingClaim(claim_name));
+                    };
+
+                    if !same_issuer(&iss, source.issuer.as_str()) {
+                        return Err(jwt::Error::InvalidClaim {
+                            claim_name,
+                            source: anyhow::anyhow!("expected {}; got {iss}", source.issuer),
+                        });
+                    }
+
+                    Ok(())
+                },
+            )
+            .map_err(invalid)?
+            .check_present_and(
+                "aud",
+                |claim_name: &'static str, aud: serde_json::Value| -> Result<(), jwt::Error> {
+                    // `aud` is either a single string, or an array of strings
+                    let ok = match &aud {
+                        serde_json::Value::String(aud) => *aud == source.client_id,
+                        serde_json::Value::Array(auds) => auds
+                            .iter()
+                            .any(|aud| aud.as_str() == Some(&source.client_id)),
+                        _ => false,
+                    };
+
+                    if !ok {
+                        return Err(jwt::Error::InvalidClaim {
+                            claim_name,
+                            source: anyhow::anyhow!("expected {}; got {aud}", source.client_id),
+                        });
+                    }
+
+                    Ok(())
+                },
+            )
+            .map_err(invalid)?
+            .check("nonce", jwt::expecting::exactly(oidc_state.nonce.as_str()))
+            .map_err(|err| {
+                // the nonce might not match when the client mixes up authentication attempts
+                log::debug!("ID token with unexpected nonce: {err:#}");
+                api::ErrorCode::BadRequest
+            })?
+            .default_check_timestamps()
+            .map_err(invalid)?;
+
+        // NOTE: `Claims::extract` only takes `&'static str`s
+        let serde_json::Value::Object(mut claims) =
+            serde_json::to_value(&claims).map_err(|err| {
+                log::error!("failed to convert ID token claims to json: {err}");
+                api::ErrorCode::InternalError
+            })?
+        else {
+            log::error!("expected ID token claims to convert to a json object");
+            return Err(api::ErrorCode::InternalError);
+        };
+
+        match claims.remove(&source.claim) {
+            Some(serde_json::Value::String(value)) => Ok(value),
+            Some(value) => {
+                log::warn!(
+                    "ID token claim {} from OpenID Connect provider {} is not a string, but {value}",
+                    source.claim,
+                    source.issuer
+                );
+                Err(api::ErrorCode::InternalError)
+            }
+            None => {
+                log::warn!(
+                    "ID token from OpenID Connect provider {} lacks the {} claim;  \
+                    perhaps an additional scope is required?",
+                    source.issuer,
+                    source.claim
+                );
+                Err(api::ErrorCode::InternalError)
+            }
+        }
+    }
+}
+
+/// # Implementation of endpoints
+impl App {
+    pub(super) async fn handle_auth_start_oidc(
+        app: Rc<Self>,
+        mut state: AuthState,
+    ) -> api::Result<api::auths::AuthStartResp> {
+        let oidc = app.get_oidc()?;
+
+        let (attr_ty_handle, attr_ty) =
+            match app.single_attr_type_with_source(&state, attr::Source::Oidc)? {
+                Ok(chosen) => chosen,
+                Err(resp) => return Ok(resp),
+            };
+
+        let source = attr_ty
+            .oidc_source()
+            .expect("attribute type to have been chosen for its oidc source");
+
+        let provider = oidc.provider(&source.issuer, false).await?;
+
+        let code_verifier = Base64UrlUnpadded::encode_string(&crypto::random_32_bytes());
+        let code_challenge =
+            Base64UrlUnpadded::encode_string(&sha2::Sha256::digest(code_verifier.as_bytes()));
+
+        let oidc_state = OidcAuthState {
+            code_verifier,
+            state: crypto::random_alphanumeric(),
+            nonce: crypto::random_alphanumeric(),
+        };
+
+        let scope: String = std::iter::once("openid")
+            .chain(source.scopes.iter().map(String::as_str))
+            .collect::<Vec<&str>>()
+            .join(" ");
+
+        let mut authorization_url = provider.metadata.authorization_endpoint.clone();
+
+        authorization_url
+            .query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &source.client_id)
+            .append_pair("redirect_uri", oidc.redirect_url.as_str())
+            .append_pair("scope", &scope)
+            .append_pair("state", &oidc_state.state)
+            .append_pair("nonce", &oidc_state.nonce)
+            .append_pair("code_challenge", &code_challenge)
+            .append_pair("code_challenge_method", "S256");
+
+        state.attr_type_choices = vec![vec![attr_ty_handle]];
+        state.oidc = Some(oidc_state);
+
+        Ok(api::auths::AuthStartResp::Success {
+            task: api::auths::AuthTask::Oidc { authorization_url },
+            state: state.seal(&app.auth_state_secret)?,
+        })
+    }
+
+    pub(super) async fn handle_auth_complete_oidc(
+        app: Rc<Self>,
+        state: AuthState,
+        code: String,
+        oauth_state: String,
+    ) -> api::Result<api::auths::AuthCompleteResp> {
+        let oidc = app.get_oidc()?;
+
+        let Some(oidc_state) = state.oidc.as_ref() else {
+            log::error!("oidc authentication state without oidc details");
+            return Err(api::ErrorCode::InternalError);
+        };
+
+        if oauth_state != oidc_state.state {
+            log::debug!("OAuth state returned by OpenID Connect provider does not match");
+            return Err(api::ErrorCode::BadRequest);
+        }
+
+        let Some((attr_type_handle, attr_type)) = app.single_attr_type_of(&state)? else {
+            return Ok(api::auths::AuthCompleteResp::PleaseRestartAuth);
+        };
+
+        let Some(source) = attr_type.oidc_source() else {
+            log::warn!("attribute type {attr_type_handle} is no longer available via oidc");
+            return Ok(api::auths::AuthCompleteResp::PleaseRestartAuth);
+        };
+
+        let provider = oidc.provider(&source.issuer, false).await?;
+
+        let Some(id_token) = oidc
+            .exchange_code(&provider, source, oidc_state, &code)
+            .await?
+        else {
+            return Ok(api::auths::AuthCompleteResp::PleaseRestartAuth);
+        };
+
+        let value = oidc
+            .verify_id_token(provider, source, oidc_state, &id_token)
+            .await?;
+
+        let mut attrs: IndexMap<handle::Handle, api::Signed<attr::Attr>> = IndexMap::new();
+
+        attrs.insert(
+            attr_type_handle.clone(),
+            app.issue_attr(attr_type, attr::Source::Oidc, value)?,
+        );
+
+        Ok(api::auths::AuthCompleteResp::Success { attrs })
+    }
+}
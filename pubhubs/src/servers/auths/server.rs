@@ -7,8 +7,8 @@ use actix_web::web;
 use sha2::digest::Digest as _;
 
 use crate::servers::{
-    self, AppBase, AppCreatorBase, Constellation, DiscoverVerdict, Handle, Server as _,
-    constellation, yivi,
+    self, AppBase, AppCreatorBase, Constellation, DiscoverVerdict, Handle, RevocationsCache,
+    Server as _, constellation, yivi,
 };
 use crate::{
     api::{self, EndpointDetails as _},
@@ -19,6 +19,9 @@ use crate::{
     phcrypto,
 };
 
+use super::email::{EmailAuthState, EmailCtx};
+use super::oidc::{OidcAuthState, OidcCtx};
+use super::webauthn::{WebAuthnAuthState, WebAuthnCtx};
 use super::yivi::ChainedSessionsCtl;
 
 /// Authentication server type
@@ -108,12 +111,18 @@ pub struct ExtraRunningState {
 pub struct App {
     pub base: AppBase<Server>,
     pub yivi: Option<YiviCtx>,
+    pub email: Option<EmailCtx>,
+    pub oidc: Option<OidcCtx>,
+    pub webauthn: Option<WebAuthnCtx>,
     pub auth_state_secret: crypto::SealingKey,
     pub auth_window: core::time::Duration,
     pub max_attr_types_per_req: usize,
     pub attr_key_secret: Vec<u8>,
     pub chained_sessions_ctl: Option<ChainedSessionsCtl>,
     pub encap_key: kem::EncapKeyBytes,
+
+    /// Used to refuse [`api::auths::HubCredentialEP`] requests with revoked tickets
+    pub revocations: RevocationsCache,
 }
 
 impl Deref for App {
@@ -133,6 +142,7 @@ pub struct YiviCtx {
 
     pub chained_sessions_config: super::yivi::ChainedSessionsConfig,
     pub card_config: super::card::CardConfig,
+    pub hub_credentials: HashMap<handle::Handle, super::card::HubCredentialConfig>,
 }
 
 /// # Helper functions
@@ -144,6 +154,27 @@ impl App {
         })
     }
 
+    pub fn get_email(&self) -> Result<&EmailCtx, api::ErrorCode> {
+        self.email.as_ref().ok_or_else(|| {
+            log::debug!("email requested, but not configured");
+            api::ErrorCode::BadRequest
+        })
+    }
+
+    pub fn get_oidc(&self) -> Result<&OidcCtx, api::ErrorCode> {
+        self.oidc.as_ref().ok_or_else(|| {
+            log::debug!("oidc requested, but not configured");
+            api::ErrorCode::BadRequest
+        })
+    }
+
+    pub fn get_webauthn(&self) -> Result<&WebAuthnCtx, api::ErrorCode> {
+        self.webauthn.as_ref().ok_or_else(|| {
+            log::debug!("webauthn requested, but not configured");
+            api::ErrorCode::BadRequest
+        })
+    }
+
     /// Get [`attr::Type`] by [`handle::Handle`], returning [`None`]
     /// when it cannot be found.
     pub fn attr_type_from_handle<'s>(
@@ -160,16 +191,29 @@ pub(super) struct AuthState {
     pub source: attr::Source,
     pub attr_type_choices: Vec<Vec<handle::Handle>>,
 
+    /// See [`api::auths::AuthStartReq::optional_attr_type_choices`].
+    #[serde(default)]
+    pub optional_attr_type_choices: Vec<Vec<handle::Handle>>,
+
     /// When this request expires
     pub exp: api::NumericDate,
 
     /// Set when [`api::auths::AuthStartReq::yivi_chained_session`] is enabled.
     pub yivi_chained_session: Option<ChainedSessionSetup>,
 
-    /// Under [`attr::Source::Yivi`] this will contain for each `AuthState::attr_type_choice`
-    /// a map using which the original [`attr::Type`] handle can be recovered from the yivi
+    /// Under [`attr::Source::Yivi`] this will contain for each `AuthState::attr_type_choice`,
+    /// followed by each `AuthState::optional_attr_type_choice`, a map using which the original [`attr::Type`] handle can be recovered from the yivi
     /// attribute type identifier.
     pub yivi_ati2at: Vec<HashMap<yivi::AttributeTypeIdentifier, handle::Handle>>,
+
+    /// Set under [`attr::Source::Email`].
+    pub email: Option<EmailAuthState>,
+
+    /// Set under [`attr::Source::Oidc`].
+    pub oidc: Option<OidcAuthState>,
+
+    /// Set under [`attr::Source::WebAuthn`].
+    pub webauthn: Option<WebAuthnAuthState>,
 }
 
 /// Type of [`AuthState::yivi_chained_session`].
@@ -248,7 +292,10 @@ impl crate::servers::App<Server> for App {
         api::auths::AttrKeysEP::add_to(self, sc, App::handle_attr_keys);
 
         api::auths::CardEP::add_to(self, sc, App::handle_card);
+        api::auths::CardRevocationEP::add_to(self, sc, App::handle_card_revocation);
+        api::auths::HubCredentialEP::add_to(self, sc, App::handle_hub_credential);
 
+        api::auths::YiviSignEP::add_to(self, sc, App::handle_yivi_sign);
         api::auths::YiviWaitForResultEP::add_to(self, sc, App::handle_yivi_wait_for_result);
         api::auths::YiviReleaseNextSessionEP::add_to(
             self,
@@ -272,19 +319,25 @@ impl crate::servers::App<Server> for App {
                 constellation::Inner {
                     // These fields we must check:
                     auths_verifying_key,
+                    auths_next_verifying_key,
                     auths_encap_key_id,
 
                     // These fields we don't care about:
                     auths_url: _,
                     auths_ss_encap: _,
                     transcryptor_verifying_key: _,
+                    transcryptor_next_verifying_key: _,
                     transcryptor_url: _,
                     transcryptor_master_enc_key_part_hash: _,
+                    transcryptor_previous_master_enc_key_part_hash: _,
                     transcryptor_encap_key_id: _,
                     transcryptor_ss_encap: _,
+                    co_transcryptors: _,
                     phc_jwt_key: _,
                     phc_verifying_key: _,
+                    phc_next_verifying_key: _,
                     phc_master_enc_key_part_hash: _,
+                    phc_previous_master_enc_key_part_hash: _,
                     phc_url: _,
                     global_client_url: _,
                     ph_version: _, // (already checked)
@@ -300,6 +353,7 @@ impl crate::servers::App<Server> for App {
         }
 
         auths_verifying_key == &self.shared.verifying_key_bytes
+            && auths_next_verifying_key.as_ref() == self.shared.next_verifying_key()
     }
 
     fn encap_key(&self) -> Option<&kem::EncapKeyBytes> {
@@ -319,6 +373,9 @@ impl crate::servers::App<Server> for App {
 pub struct AppCreator {
     base: AppCreatorBase<Server>,
     yivi: Option<YiviCtx>,
+    email: Option<EmailCtx>,
+    oidc: Option<OidcCtx>,
+    webauthn: Option<WebAuthnCtx>,
     auth_state_secret: crypto::SealingKey,
     auth_window: core::time::Duration,
     max_attr_types_per_req: usize,
@@ -364,8 +421,15 @@ impl crate::servers::AppCreator<Server> for AppCreator {
             server_creds: cfg.server_creds(),
             chained_sessions_config: cfg.chained_sessions.clone(),
             card_config: cfg.card.clone(),
+            hub_credentials: cfg.hub_credentials.clone(),
         });
 
+        let email: Option<EmailCtx> = xconf.email.as_ref().map(EmailCtx::new).transpose()?;
+
+        let oidc: Option<OidcCtx> = xconf.oidc.as_ref().map(OidcCtx::new);
+
+        let webauthn: Option<WebAuthnCtx> = xconf.webauthn.as_ref().map(WebAuthnCtx::new);
+
         let enc_key: &[u8] = &base.enc_key;
         let auth_state_secret: crypto::SealingKey =
             enc_key.derive_sealing_key(sha2::Sha256::new(), "pubhubs-auths-auth-state");
@@ -395,6 +459,9 @@ impl crate::servers::AppCreator<Server> for AppCreator {
         Ok(Self {
             base,
             yivi,
+            email,
+            oidc,
+            webauthn,
             auth_state_secret,
             auth_window,
             max_attr_types_per_req,
@@ -413,12 +480,16 @@ impl crate::servers::AppCreator<Server> for AppCreator {
         App {
             base: AppBase::new(self.base, handle, generation),
             yivi: self.yivi,
+            email: self.email,
+            oidc: self.oidc,
+            webauthn: self.webauthn,
             auth_state_secret: self.auth_state_secret,
             auth_window: self.auth_window,
             max_attr_types_per_req: self.max_attr_types_per_req,
             attr_key_secret: self.attr_key_secret,
             chained_sessions_ctl: self.chained_sessions_ctl,
             encap_key: self.encap_key,
+            revocations: Default::default(),
         }
     }
 }
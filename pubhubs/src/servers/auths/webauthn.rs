@@ -0,0 +1,418 @@
+//! Implementation of the [`attr::Source::WebAuthn`] source: passkeys.
+//!
+//! The authentication server keeps no record of registered passkeys.  Instead, the value of the
+//! resulting attribute is the passkey's public key, which pubhubs central ties to the user's
+//! account like any other identifying attribute.
+use super::server::*;
+
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+use aws_lc_rs::encoding::{AsDer as _, PublicKeyX509Der};
+use aws_lc_rs::signature;
+use base64ct::{Base64UrlUnpadded, Encoding as _};
+use indexmap::IndexMap;
+use sha2::Digest as _;
+
+use crate::api::auths::WebAuthnPublicKey;
+use crate::servers::config::auths::WebAuthnConfig;
+use crate::{api, attr, handle, id};
+
+/// User present flag of the authenticator data
+const FLAG_UP: u8 = 0x01;
+
+/// User verified flag of the authenticator data
+const FLAG_UV: u8 = 0x04;
+
+/// Details on the passkeys accepted by this authentication server.
+///
+/// Cheaply cloneable;  clones share [`WebAuthnCtx::used_challenges`].
+#[derive(Clone)]
+pub struct WebAuthnCtx {
+    rp_id: String,
+
+    /// Allowed origins, serialized as in the `origin` field of the client data.  When empty,
+    /// the global client's origin is used.
+    origins: Vec<String>,
+
+    require_user_verification: bool,
+
+    /// The challenges for which an assertion was accepted, together with the moment they
+    /// expire.  This is needed because the client can submit the same sealed [`AuthState`]
+    /// over and over again.
+    used_challenges: Arc<Mutex<HashMap<id::Id, api::NumericDate>>>,
+}
+
+/// Part of [`AuthState`] for [`attr::Source::WebAuthn`]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub(super) struct WebAuthnAuthState {
+    pub challenge: id::Id,
+}
+
+/// The parts of the `clientDataJSON` we check
+#[derive(serde::Deserialize)]
+struct ClientData {
+    #[serde(rename = "type")]
+    ty: String,
+    challenge: String,
+    origin: String,
+    #[serde(default, rename = "crossOrigin")]
+    cross_origin: bool,
+}
+
+/// What an assertion is checked against, see [`Assertion::verify`].
+struct Expected<'a> {
+    challenge: &'a id::Id,
+    rp_id: &'a str,
+    origins: &'a [String],
+    require_user_verification: bool,
+}
+
+/// A WebAuthn assertion, see [`api::auths::AuthProof::WebAuthn`].
+struct Assertion<'a> {
+    public_key: &'a WebAuthnPublicKey,
+    authenticator_data: &'a [u8],
+    client_data_json: &'a [u8],
+    signature: &'a [u8],
+}
+
+impl Assertion<'_> {
+    /// Verifies this assertion, returning the public key, encoded as the value of the attribute.
+    ///
+    /// Since we do not keep track of passkeys, the signature counter is not checked.
+    fn verify(&self, expected: &Expected<'_>) -> anyhow::Result<String> {
+        let client_data: ClientData = serde_json::from_slice(self.client_data_json)
+            .map_err(|err| anyhow::anyhow!("invalid client data: {err}"))?;
+
+        anyhow::ensure!(
+            client_data.ty == "webauthn.get",
+            "client data has unexpected type {:?}",
+            client_data.ty
+        );
+
+        let challenge: id::Id = client_data
+            .challenge
+            .parse()
+            .map_err(|err| anyhow::anyhow!("invalid challenge in client data: {err}"))?;
+
+        anyhow::ensure!(
+            &challenge == expected.challenge,
+            "client data contains the wrong challenge"
+        );
+
+        anyhow::ensure!(
+            expected.origins.contains(&client_data.origin),
+            "assertion made from unexpected origin {:?}",
+            client_data.origin
+        );
+
+        anyhow::ensure!(!client_data.cross_origin, "cross-origin assertion");
+
+        // rpIdHash (32 bytes) || flags (1 byte) || signCount (4 bytes) || extensions...
+        anyhow::ensure!(
+            self.authenticator_data.len() >= 37,
+            "authenticator data too short"
+        );
+
+        anyhow::ensure!(
+            self.authenticator_data[..32] == *sha2::Sha256::digest(expected.rp_id.as_bytes()),
+            "authenticator data is for another relying party"
+        );
+
+        let flags = self.authenticator_data[32];
+
+        anyhow::ensure!(flags & FLAG_UP != 0, "user was not present");
+
+        anyhow::ensure!(
+            !expected.require_user_verification || flags & FLAG_UV != 0,
+            "user was not verified"
+        );
+
+        let (alg, spki): (&'static dyn signature::VerificationAlgorithm, &[u8]) =
+            match self.public_key {
+                WebAuthnPublicKey::ES256(spki) => (&signature::ECDSA_P256_SHA256_ASN1, spki),
+                WebAuthnPublicKey::EdDSA(spki) => (&signature::ED25519, spki),
+            };
+
+        let pk = signature::ParsedPublicKey::new(alg, spki)
+            .map_err(|_| anyhow::anyhow!("invalid public key"))?;
+
+        let mut signed: Vec<u8> = Vec::with_capacity(self.authenticator_data.len() + 32);
+        signed.extend_from_slice(self.authenticator_data);
+        signed.extend_from_slice(&sha2::Sha256::digest(self.client_data_json));
+
+        pk.verify_sig(&signed, self.signature)
+            .map_err(|_| anyhow::anyhow!("invalid signature"))?;
+
+        // use the normalized encoding, so that the same passkey always yields the same attribute
+        let spki: PublicKeyX509Der<'static> = pk
+            .as_der()
+            .map_err(|_| anyhow::anyhow!("failed to encode public key"))?;
+
+        Ok(Base64UrlUnpadded::encode_string(spki.as_ref()))
+    }
+}
+
+impl WebAuthnCtx {
+    pub fn new(cfg: &WebAuthnConfig) -> Self {
+        Self {
+            rp_id: cfg.rp_id.clone(),
+            origins: cfg
+                .origins
+                .iter()
+                .map(|url| url.origin().ascii_serialization())
+                .collect(),
+            require_user_verification: cfg.require_user_verification,
+            used_challenges: Default::default(),
+        }
+    }
+
+    /// Marks the given challenge as used, returning `false` if it was already used.
+    fn use_challenge(&self, challenge: id::Id, exp: api::NumericDate) -> bool {
+        let now = api::NumericDate::now();
+
+        let mut used_challenges = self.used_challenges.lock().unwrap();
+
+        used_challenges.retain(|_, challenge_exp| *challenge_exp >= now);
+
+        used_challenges.insert(challenge, exp).is_none()
+    }
+}
+
+/// # Implementation of endpoints
+impl App {
+    pub(super) async fn handle_auth_start_webauthn(
+        app: Rc<Self>,
+        mut state: AuthState,
+    ) -> api::Result<api::auths::AuthStartResp> {
+        let webauthn = app.get_webauthn()?;
+
+        // NOTE: an assertion proves only one attribute
+        let (attr_ty_handle, _) =
+            match app.single_attr_type_with_source(&state, attr::Source::WebAuthn)? {
+                Ok(chosen) => chosen,
+                Err(resp) => return Ok(resp),
+            };
+
+        let challenge = id::Id::random();
+
+        state.attr_type_choices = vec![vec![attr_ty_handle]];
+        state.webauthn = Some(WebAuthnAuthState { challenge });
+
+        Ok(api::auths::AuthStartResp::Success {
+            task: api::auths::AuthTask::WebAuthn {
+                challenge,
+                rp_id: webauthn.rp_id.clone(),
+                user_verification: webauthn.require_user_verification,
+            },
+            state: state.seal(&app.auth_state_secret)?,
+        })
+    }
+
+    pub(super) async fn handle_auth_complete_webauthn(
+        app: Rc<Self>,
+        state: AuthState,
+        public_key: WebAuthnPublicKey,
+        authenticator_data: &[u8],
+        client_data_json: &[u8],
+        signature: &[u8],
+    ) -> api::Result<api::auths::AuthCompleteResp> {
+        let webauthn = app.get_webauthn()?;
+
+        let Some(webauthn_state) = state.webauthn.as_ref() else {
+            log::error!("webauthn authentication state without webauthn details");
+            return Err(api::ErrorCode::InternalError);
+        };
+
+        let global_client_origin: [String; 1];
+
+        let origins: &[String] = if webauthn.origins.is_empty() {
+            global_client_origin = [app
+                .running_state_or_please_retry()?
+                .constellation
+                .global_client_url
+                .origin()
+                .ascii_serialization()];
+            &global_client_origin
+        } else {
+            &webauthn.origins
+        };
+
+        let value = Assertion {
+            public_key: &public_key,
+            authenticator_data,
+            client_data_json,
+            signature,
+        }
+        .verify(&Expected {
+            challenge: &webauthn_state.challenge,
+            rp_id: &webauthn.rp_id,
+            origins,
+            require_user_verification: webauthn.require_user_verification,
+        })
+        .map_err(|err| {
+            log::debug!("invalid webauthn assertion submitted: {err:#}");
+            api::ErrorCode::BadRequest
+        })?;
+
+        if !webauthn.use_challenge(webauthn_state.challenge, state.exp) {
+            log::debug!("webauthn assertion submitted for already used challenge");
+            return Ok(api::auths::AuthCompleteResp::PleaseRestartAuth);
+        }
+
+        let Some((attr_type_handle, attr_type)) = app.single_attr_type_of(&state)? else {
+            return Ok(api::auths::AuthCompleteResp::PleaseRestartAuth);
+        };
+
+        let mut attrs: IndexMap<handle::Handle, api::Signed<attr::Attr>> = IndexMap::new();
+
+        attrs.insert(
+            attr_type_handle.clone(),
+            app.issue_attr(attr_type, attr::Source::WebAuthn, value)?,
+        );
+
+        Ok(api::auths::AuthCompleteResp::Success { attrs })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use aws_lc_rs::signature::KeyPair as _;
+
+    const RP_ID: &str = "pubhubs.example";
+    const ORIGIN: &str = "https://app.pubhubs.example";
+
+    fn authenticator_data(rp_id: &str, flags: u8) -> Vec<u8> {
+        let mut ad = sha2::Sha256::digest(rp_id.as_bytes()).to_vec();
+        ad.push(flags);
+        ad.extend_from_slice(&[0, 0, 0, 0]);
+        ad
+    }
+
+    fn client_data_json(ty: &str, challenge: &id::Id, origin: &str) -> Vec<u8> {
+        serde_json::to_vec(&serde_json::json!({
+            "type": ty,
+            "challenge": challenge.to_string(),
+            "origin": origin,
+            "crossOrigin": false,
+        }))
+        .unwrap()
+    }
+
+    fn signed_data(ad: &[u8], cdj: &[u8]) -> Vec<u8> {
+        let mut signed = ad.to_vec();
+        signed.extend_from_slice(&sha2::Sha256::digest(cdj));
+        signed
+    }
+
+    #[test]
+    fn test_verify_assertion() {
+        let rng = aws_lc_rs::rand::SystemRandom::new();
+
+        let es256_kp =
+            signature::EcdsaKeyPair::generate(&signature::ECDSA_P256_SHA256_ASN1_SIGNING).unwrap();
+        let es256_pk = WebAuthnPublicKey::ES256(
+            serde_bytes::ByteBuf::from(es256_kp.public_key().as_der().unwrap().as_ref()).into(),
+        );
+
+        let ed25519_kp = signature::Ed25519KeyPair::generate().unwrap();
+        let ed25519_pk = WebAuthnPublicKey::EdDSA(
+            serde_bytes::ByteBuf::from(ed25519_kp.public_key().as_der().unwrap().as_ref()).into(),
+        );
+
+        let challenge = id::Id::random();
+        let origins = [ORIGIN.to_string()];
+        let expected = Expected {
+            challenge: &challenge,
+            rp_id: RP_ID,
+            origins: &origins,
+            require_user_verification: true,
+        };
+
+        let good_ad = authenticator_data(RP_ID, FLAG_UP | FLAG_UV);
+        let good_cdj = client_data_json("webauthn.get", &challenge, ORIGIN);
+
+        let es256_sign = |ad: &[u8], cdj: &[u8]| -> Vec<u8> {
+            es256_kp
+                .sign(&rng, &signed_data(ad, cdj))
+                .unwrap()
+                .as_ref()
+                .to_vec()
+        };
+
+        let check = |pk: &WebAuthnPublicKey, ad: &[u8], cdj: &[u8], sig: &[u8]| {
+            Assertion {
+                public_key: pk,
+                authenticator_data: ad,
+                client_data_json: cdj,
+                signature: sig,
+            }
+            .verify(&expected)
+        };
+
+        // ES256
+        let value = check(
+            &es256_pk,
+            &good_ad,
+            &good_cdj,
+            &es256_sign(&good_ad, &good_cdj),
+        )
+        .unwrap();
+        assert_eq!(
+            value,
+            Base64UrlUnpadded::encode_string(es256_kp.public_key().as_der().unwrap().as_ref())
+        );
+
+        // EdDSA
+        let sig = ed25519_kp.sign(&signed_data(&good_ad, &good_cdj));
+        check(&ed25519_pk, &good_ad, &good_cdj, sig.as_ref()).unwrap();
+
+        // wrong key type
+        check(
+            &ed25519_pk,
+            &good_ad,
+            &good_cdj,
+            &es256_sign(&good_ad, &good_cdj),
+        )
+        .unwrap_err();
+
+        // tampered authenticator data
+        let mut bad_ad = good_ad.clone();
+        bad_ad[36] = 1;
+        check(
+            &es256_pk,
+            &bad_ad,
+            &good_cdj,
+            &es256_sign(&good_ad, &good_cdj),
+        )
+        .unwrap_err();
+
+        // properly signed, but otherwise invalid assertions
+        for (ad, cdj) in [
+            (
+                authenticator_data("other.example", FLAG_UP | FLAG_UV),
+                good_cdj.clone(),
+            ),
+            (authenticator_data(RP_ID, FLAG_UP), good_cdj.clone()),
+            (authenticator_data(RP_ID, FLAG_UV), good_cdj.clone()),
+            (
+                good_ad.clone(),
+                client_data_json("webauthn.create", &challenge, ORIGIN),
+            ),
+            (
+                good_ad.clone(),
+                client_data_json("webauthn.get", &id::Id::random(), ORIGIN),
+            ),
+            (
+                good_ad.clone(),
+                client_data_json("webauthn.get", &challenge, "https://evil.example"),
+            ),
+            (good_ad[..36].to_vec(), good_cdj.clone()),
+        ] {
+            check(&es256_pk, &ad, &cdj, &es256_sign(&ad, &cdj)).unwrap_err();
+        }
+    }
+}
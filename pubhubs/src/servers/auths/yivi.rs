@@ -25,6 +25,52 @@ impl App {
         })
     }
 
+    /// Implements the [`api::auths::YiviSignEP`] endpoint.
+    pub async fn handle_yivi_sign(
+        app: Rc<Self>,
+        req: web::Json<api::auths::YiviSignReq>,
+    ) -> api::Result<api::auths::YiviSignResp> {
+        let yivi = app.get_yivi()?;
+
+        let api::auths::YiviSignReq {
+            message,
+            attr_type_choices,
+        } = req.into_inner();
+
+        if message.len() > api::auths::YiviSignReq::MAX_MESSAGE_LEN {
+            log::debug!("message to be signed is too long ({} bytes)", message.len());
+            return Err(api::ErrorCode::BadRequest);
+        }
+
+        let max = app.max_attr_types_per_req;
+        if attr_type_choices.is_empty()
+            || attr_type_choices.len() > max
+            || attr_type_choices.iter().any(|choices| choices.len() > max)
+        {
+            log::debug!(
+                "rejecting YiviSignReq: it requests no attribute types, or more than {max} (or alternatives per type)"
+            );
+            return Err(api::ErrorCode::BadRequest);
+        }
+
+        let cdc = match app.yivi_condiscon_for(&attr_type_choices, &[])? {
+            Ok(ycdc) => ycdc.cdc,
+            Err(err) => return Ok(err.into()),
+        };
+
+        let signature_request = yivi::ExtendedSessionRequest::signature(message, cdc)
+            .sign(&yivi.requestor_creds)
+            .map_err(|err| {
+                log::error!("failed to create signed signature request: {err:#}");
+                api::ErrorCode::InternalError
+            })?;
+
+        Ok(api::auths::YiviSignResp::Success {
+            signature_request,
+            yivi_requestor_url: yivi.requestor_url.clone(),
+        })
+    }
+
     /// Implements the [`api::auths::YiviWaitForResultEP`] endpoint.
     pub async fn handle_yivi_wait_for_result(
         app: Rc<Self>,
@@ -57,16 +57,31 @@ pub struct Inner {
     /// The transcryptor's hybrid post-quantum verifying key, used to verify its JWTs and signatures.
     pub transcryptor_verifying_key: api::VerifyingKeyBytes,
 
+    /// The verifying key the transcryptor will switch to, see [`api::NextVerifyingKey`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transcryptor_next_verifying_key: Option<api::NextVerifyingKey>,
+
     /// Hash of the transcryptor's master encryption key part `x_T B`, so the transcryptor can check
     /// that the correct keypart was used without `x_T B` being exposed in the clear.
     pub transcryptor_master_enc_key_part_hash: id::Id,
 
+    /// Hash of the transcryptor's previous master encryption key part, set while it is being
+    /// rotated, so the transcryptor can check PHC is aware of the rotation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transcryptor_previous_master_enc_key_part_hash: Option<id::Id>,
+
     /// [`kem::EncapKeyBytes::id`] of the transcryptor's encapsulation key.
     pub transcryptor_encap_key_id: id::Id,
 
     /// Shared secret PHC encapsulated against the transcryptor's encap key.
     pub transcryptor_ss_encap: kem::CiphertextBytes,
 
+    /// The co-transcryptors that share the role of the transcryptor, in the order in which they
+    /// process [`api::sso::PartialEhpp`]s.  Empty unless the transcryptor role is split, see
+    /// [`api::sso`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub co_transcryptors: Vec<CoTranscryptor>,
+
     pub phc_url: url::Url,
 
     /// PHC's ed25519 public key (the `ed` half of [`phc_verifying_key`](Self::phc_verifying_key)),
@@ -80,16 +95,29 @@ pub struct Inner {
     /// PHC's hybrid post-quantum verifying key, used to verify its JWTs and signatures.
     pub phc_verifying_key: api::VerifyingKeyBytes,
 
+    /// The verifying key PHC will switch to, see [`api::NextVerifyingKey`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phc_next_verifying_key: Option<api::NextVerifyingKey>,
+
     /// Hash of PHC's master encryption key part `x_PHC B`.  Published so that a change of PHC's
     /// part churns the constellation id (the real master key is held off-wire by PHC).
     pub phc_master_enc_key_part_hash: id::Id,
 
+    /// Hash of PHC's previous master encryption key part, set while it is being rotated, so the
+    /// transcryptor can recognize polymorphic pseudonyms that have not been re-keyed yet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phc_previous_master_enc_key_part_hash: Option<id::Id>,
+
     pub auths_url: url::Url,
 
     /// The authentication server's hybrid post-quantum verifying key, used to verify its JWTs and
     /// signatures.
     pub auths_verifying_key: api::VerifyingKeyBytes,
 
+    /// The verifying key the authentication server will switch to, see [`api::NextVerifyingKey`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auths_next_verifying_key: Option<api::NextVerifyingKey>,
+
     /// [`kem::EncapKeyBytes::id`] of the authentication server's encapsulation key.
     pub auths_encap_key_id: id::Id,
 
@@ -102,6 +130,30 @@ pub struct Inner {
     pub ph_version: Option<String>,
 }
 
+/// Public details on a co-transcryptor, see [`Inner::co_transcryptors`].
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct CoTranscryptor {
+    pub url: url::Url,
+
+    /// The co-transcryptor's hybrid post-quantum verifying key.
+    pub verifying_key: api::VerifyingKeyBytes,
+
+    /// The verifying key the co-transcryptor will switch to, see [`api::NextVerifyingKey`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_verifying_key: Option<api::NextVerifyingKey>,
+
+    /// Hash of the co-transcryptor's master encryption key part `x_Ti B`.
+    pub master_enc_key_part_hash: id::Id,
+
+    /// The co-transcryptor's encapsulation key, used by PHC and by the preceding transcryptor to
+    /// establish shared secrets with it.
+    pub encap_key: kem::EncapKeyBytes,
+
+    /// Shared secret PHC encapsulated against [`Self::encap_key`].
+    pub ss_encap: kem::CiphertextBytes,
+}
+
 /// Extension methods on [`sha2::Sha256`] used by [`Inner::sha256`] to give the constellation an
 /// unambiguous byte encoding before hashing.
 trait DigestExt: Sized {
@@ -116,6 +168,13 @@ trait DigestExt: Sized {
 
     /// Both length-prefixed halves (ed25519 ‖ ML-DSA) of a hybrid verifying key.
     fn chain_vk(self, vk: &api::VerifyingKeyBytes) -> Self;
+
+    /// A 1/0 presence byte, followed by the verifying key (see `chain_vk`) and the 8-byte
+    /// big-endian activation time when present.
+    fn chain_next_vk(self, nvk: Option<&api::NextVerifyingKey>) -> Self;
+
+    /// The number of co-transcryptors (8-byte big-endian), followed by their fields.
+    fn chain_co_transcryptors(self, cts: &[CoTranscryptor]) -> Self;
 }
 
 impl DigestExt for sha2::Sha256 {
@@ -140,6 +199,41 @@ impl DigestExt for sha2::Sha256 {
         self.chain_varlen(vk.ed.as_ref())
             .chain_varlen(vk.ml.as_ref())
     }
+
+    fn chain_next_vk(self, nvk: Option<&api::NextVerifyingKey>) -> Self {
+        match nvk {
+            Some(nvk) => self
+                .chain_update([1u8])
+                .chain_vk(&nvk.verifying_key)
+                .chain_update(nvk.active_from.timestamp().to_be_bytes()),
+            None => self.chain_update([0u8]),
+        }
+    }
+
+    fn chain_co_transcryptors(self, cts: &[CoTranscryptor]) -> Self {
+        cts.iter().fold(
+            self.chain_update(secret::encode_usize(cts.len())),
+            |digest, ct| {
+                let CoTranscryptor {
+                    url,
+                    verifying_key,
+                    next_verifying_key,
+                    master_enc_key_part_hash,
+                    encap_key,
+                    ss_encap,
+                } = ct;
+
+                digest
+                    .chain_varlen(url.as_str().as_bytes())
+                    .chain_vk(verifying_key)
+                    .chain_next_vk(next_verifying_key.as_ref())
+                    .chain_update(master_enc_key_part_hash.as_slice())
+                    .chain_varlen(encap_key.ml.as_ref())
+                    .chain_varlen(encap_key.ec.as_ref())
+                    .chain_ct(ss_encap)
+            },
+        )
+    }
 }
 
 impl Inner {
@@ -157,16 +251,23 @@ impl Inner {
         let Inner {
             transcryptor_url,
             transcryptor_verifying_key,
+            transcryptor_next_verifying_key,
             transcryptor_master_enc_key_part_hash,
+            transcryptor_previous_master_enc_key_part_hash,
             transcryptor_encap_key_id,
             transcryptor_ss_encap,
 
+            co_transcryptors,
+
             phc_url,
             phc_verifying_key,
+            phc_next_verifying_key,
             phc_master_enc_key_part_hash,
+            phc_previous_master_enc_key_part_hash,
 
             auths_url,
             auths_verifying_key,
+            auths_next_verifying_key,
             auths_encap_key_id,
             auths_ss_encap,
 
@@ -185,8 +286,8 @@ impl Inner {
         // Framing (see `DigestExt`): fixed-length fields (32-byte id hashes) are hashed directly;
         // variable-length fields, and the two halves of each hybrid verifying key / KEM ciphertext,
         // are length-prefixed (`chain_varlen`/`chain_vk`/`chain_ct`) so one field's bytes can't be
-        // read as part of an adjacent one.  The only optional field is `ph_version` (`chain_opt`, a
-        // 1/0 presence byte).
+        // read as part of an adjacent one.  Optional fields, such as `ph_version`, are hashed using
+        // `chain_opt` (a 1/0 presence byte).
 
         sha2::Sha256::new()
             // Hash-format version - BUMP THIS on any change to the framing or fields below, so the
@@ -194,17 +295,34 @@ impl Inner {
             // v2: jwt keys became hybrid post-quantum (ed25519 ‖ ML-DSA).
             // v3: dropped the deprecated enc_key / master_enc_key / `*_jwt_key` placeholder fields,
             // and the verifying-key / KEM / master-key-part-hash fields are no longer optional.
-            .chain_update(3u16.to_be_bytes())
+            // v4: added the previous master-key-part-hash fields.
+            // v5: added the next verifying key fields.
+            // v6: added the co-transcryptors.
+            .chain_update(6u16.to_be_bytes())
             .chain_varlen(transcryptor_url.as_str().as_bytes())
             .chain_vk(transcryptor_verifying_key)
+            .chain_next_vk(transcryptor_next_verifying_key.as_ref())
             .chain_update(transcryptor_master_enc_key_part_hash.as_slice())
+            .chain_opt(
+                transcryptor_previous_master_enc_key_part_hash
+                    .as_ref()
+                    .map(id::Id::as_slice),
+            )
             .chain_update(transcryptor_encap_key_id.as_slice())
             .chain_ct(transcryptor_ss_encap)
+            .chain_co_transcryptors(co_transcryptors)
             .chain_varlen(phc_url.as_str().as_bytes())
             .chain_vk(phc_verifying_key)
+            .chain_next_vk(phc_next_verifying_key.as_ref())
             .chain_update(phc_master_enc_key_part_hash.as_slice())
+            .chain_opt(
+                phc_previous_master_enc_key_part_hash
+                    .as_ref()
+                    .map(id::Id::as_slice),
+            )
             .chain_varlen(auths_url.as_str().as_bytes())
             .chain_vk(auths_verifying_key)
+            .chain_next_vk(auths_next_verifying_key.as_ref())
             .chain_update(auths_encap_key_id.as_slice())
             .chain_ct(auths_ss_encap)
             .chain_varlen(global_client_url.as_str().as_bytes())
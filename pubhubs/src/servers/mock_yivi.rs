@@ -0,0 +1,451 @@
+//! A mock yivi server that auto-approves every session, run via `pubhubs serve mock-yivi`.
+//!
+//! Implements just enough of the [requestor API](https://docs.yivi.app/api-irma-server) and
+//! frontend endpoints of the yivi server for the authentication server and `pubhubs enter` to
+//! run their flows offline, including chained sessions via `nextSession`.  Disclosed attribute
+//! values are taken from [`MockYiviConfig::attrs`], and no actual proofs are involved.
+//!
+//! **Never use this in production:**  anyone can obtain any (configured) attribute.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use actix_web::web;
+use anyhow::Context as _;
+
+use crate::api;
+use crate::misc::jwt;
+use crate::servers::yivi;
+
+/// Configuration of the mock yivi server
+#[derive(Debug, Clone)]
+pub struct MockYiviConfig {
+    /// Where the mock yivi server can be reached by the end-user's client;  used in session
+    /// pointers
+    pub url: url::Url,
+
+    /// Used to check signed session requests
+    pub requestor_creds: yivi::Credentials<yivi::VerifyingKey>,
+
+    /// Used to sign session results.  If the key is [`yivi::SigningKey::RS256`], its public key
+    /// is served at `/publickey`.
+    pub server_creds: yivi::Credentials<yivi::SigningKey>,
+
+    /// The values disclosed for these yivi attribute types.  Other attribute types are never
+    /// disclosed.
+    pub attrs: HashMap<yivi::AttributeTypeIdentifier, String>,
+
+    /// How long to wait for the response of the `nextSession` url of a chained session
+    pub next_session_timeout: core::time::Duration,
+}
+
+/// How long sessions are remembered
+const SESSION_LIFETIME: core::time::Duration = core::time::Duration::from_secs(60 * 60);
+
+/// Pause between polls of the session status for the status events
+const STATUS_POLL_INTERVAL: core::time::Duration = core::time::Duration::from_millis(100);
+
+struct Ctx {
+    config: MockYiviConfig,
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+struct Session {
+    /// Token used by the end-user's client to refer to this session, see [`SessionPackage`]
+    client_token: String,
+
+    request: yivi::ExtendedSessionRequest,
+    status: yivi::Status,
+
+    /// Set once the session is done
+    result: Option<yivi::SessionResult>,
+
+    /// When this session may be forgotten
+    exp: api::NumericDate,
+}
+
+/// What's returned when a session is started.
+///
+/// <https://github.com/privacybydesign/irmago/blob/f9718c334af76a3ad2fa23019d17957878cd2032/server/api.go#L30>
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionPackage {
+    session_ptr: SessionPtr,
+    token: yivi::RequestorToken,
+    frontend_request: FrontendSessionRequest,
+}
+
+#[derive(serde::Serialize)]
+struct SessionPtr {
+    #[serde(rename = "u")]
+    url: url::Url,
+
+    #[serde(rename = "irmaqr")]
+    session_type: yivi::SessionType,
+}
+
+#[derive(serde::Serialize)]
+struct FrontendSessionRequest {
+    authorization: String,
+}
+
+/// <https://github.com/privacybydesign/irmago/blob/773a229329a063043831a4c21e72b139b9600f4b/messages.go#L571>
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FrontendSessionStatus {
+    status: yivi::Status,
+    next_session: Option<serde_json::Value>,
+}
+
+/// Runs the mock yivi server on `listener` until it is stopped.
+pub async fn run(config: MockYiviConfig, listener: std::net::TcpListener) -> anyhow::Result<()> {
+    let local_addr = listener.local_addr()?;
+
+    let ctx = Arc::new(Ctx {
+        config,
+        sessions: Default::default(),
+    });
+
+    log::warn!(
+        "running a MOCK yivi server on {local_addr} (reachable at {}) that discloses {} configured attribute(s) to anyone",
+        ctx.config.url,
+        ctx.config.attrs.len()
+    );
+
+    actix_web::HttpServer::new(move || {
+        actix_web::App::new()
+            .app_data(web::Data::new(ctx.clone()))
+            .route("/publickey", web::get().to(handle_publickey))
+            .route("/session", web::post().to(handle_session_start))
+            .route("/revocation", web::post().to(handle_revocation))
+            .route("/session/{token}/status", web::get().to(handle_status))
+            .route(
+                "/session/{token}/statusevents",
+                web::get().to(handle_statusevents),
+            )
+            .route("/session/{token}/result", web::get().to(handle_result))
+            .route(
+                "/session/{token}/result-jwt",
+                web::get().to(handle_result_jwt),
+            )
+            .route(
+                "/irma/session/{client_token}/frontend/statusevents",
+                web::get().to(handle_frontend_statusevents),
+            )
+    })
+    .workers(1)
+    .listen(listener)
+    .context("failed to listen on mock yivi server socket")?
+    .run()
+    .await
+    .context("mock yivi server failed")
+}
+
+/// Generates a random token of 20 alphanumeric characters, like a [`yivi::RequestorToken`].
+fn random_token() -> String {
+    use rand::distr::SampleString as _;
+
+    rand::distr::Alphanumeric.sample_string(&mut rand::rand_core::UnwrapErr(rand::rngs::SysRng), 20)
+}
+
+impl Ctx {
+    /// Registers a new session for `request`, and auto-approves it in the background.
+    fn start_session(self: &Arc<Self>, request: yivi::ExtendedSessionRequest) -> SessionPackage {
+        let token = random_token();
+        let client_token = random_token();
+        let session_type = request.session_type();
+
+        {
+            let now = api::NumericDate::now();
+            let mut sessions = self.sessions.lock().unwrap();
+
+            sessions.retain(|_, session| session.exp >= now);
+
+            sessions.insert(
+                token.clone(),
+                Session {
+                    client_token: client_token.clone(),
+                    request,
+                    status: yivi::Status::Initialized,
+                    result: None,
+                    exp: now.add_clamp(SESSION_LIFETIME.as_secs()),
+                },
+            );
+        }
+
+        log::info!("started {session_type} session {token}");
+
+        actix_web::rt::spawn(self.clone().approve(token.clone()));
+
+        let mut url = self.config.url.clone();
+        url.path_segments_mut()
+            .expect("mock yivi server url cannot be a base")
+            .pop_if_empty()
+            .extend(["irma", "session", &client_token]);
+
+        SessionPackage {
+            session_ptr: SessionPtr { url, session_type },
+            token: token
+                .parse()
+                .expect("random token should be a valid requestor token"),
+            frontend_request: FrontendSessionRequest {
+                authorization: random_token(),
+            },
+        }
+    }
+
+    /// Completes the session with the given `token` as if the end-user approved it.
+    async fn approve(self: Arc<Self>, token: String) {
+        let Some(request) = self.update_session(&token, |session| {
+            session.status = yivi::Status::Connected;
+            session.request.clone()
+        }) else {
+            return;
+        };
+
+        let (status, result) = match self.approve_inner(&token, &request).await {
+            Ok(result) => (yivi::Status::Done, Some(result)),
+            Err(err) => {
+                log::warn!("cancelling session {token}: {err:#}");
+                (yivi::Status::Cancelled, None)
+            }
+        };
+
+        self.update_session(&token, |session| {
+            session.status = status;
+            session.result = result;
+        });
+
+        log::info!("session {token} is {status}");
+    }
+
+    async fn approve_inner(
+        self: &Arc<Self>,
+        token: &str,
+        request: &yivi::ExtendedSessionRequest,
+    ) -> anyhow::Result<yivi::SessionResult> {
+        let mut result = request.mock_response(|ati| self.config.attrs.get(ati).cloned())?;
+
+        result.token = token.parse()?;
+
+        let Some(next_session) = request.next_session.as_ref() else {
+            return Ok(result);
+        };
+
+        log::info!(
+            "posting result of session {token} to next session url {}",
+            next_session.url
+        );
+
+        let mut resp = awc::Client::default()
+            .post(next_session.url.as_str())
+            .timeout(self.config.next_session_timeout)
+            .insert_header(("Content-Type", "text/plain"))
+            .send_body(self.sign_result(result.clone())?.as_str().to_string())
+            .await
+            .map_err(|err| anyhow::anyhow!("failed to reach next session url: {err}"))?;
+
+        match resp.status() {
+            actix_web::http::StatusCode::NO_CONTENT => return Ok(result),
+            actix_web::http::StatusCode::OK => {}
+            status => anyhow::bail!("next session url returned status {status}"),
+        }
+
+        // NOTE: the body may be preceded by spaces, see `api::auths::AuthStartReq::yivi_chained_session_drip`
+        let body = resp
+            .body()
+            .limit(1024 * 1024)
+            .await
+            .context("failed to read next session request")?;
+
+        let next_request = yivi::ExtendedSessionRequest::open_signed(
+            &jwt::JWT::from(std::str::from_utf8(&body)?.trim().to_string()),
+            &self.config.requestor_creds,
+        )
+        .context("invalid next session request")?;
+
+        let next_token = self.start_session(next_request).token;
+
+        result.next_session = Some(next_token);
+
+        Ok(result)
+    }
+
+    /// Applies `f` to the session with the given `token`, if it exists.
+    fn update_session<T>(&self, token: &str, f: impl FnOnce(&mut Session) -> T) -> Option<T> {
+        self.sessions.lock().unwrap().get_mut(token).map(f)
+    }
+
+    fn sign_result(&self, result: yivi::SessionResult) -> anyhow::Result<jwt::JWT> {
+        result.sign(&self.config.server_creds, SESSION_LIFETIME)
+    }
+
+    /// Returns the [`yivi::SessionResult`] of the given session, which is only partially filled
+    /// when the session is not done yet.
+    fn result(&self, token: &str) -> Option<yivi::SessionResult> {
+        self.update_session(token, |session| {
+            session
+                .result
+                .clone()
+                .unwrap_or_else(|| yivi::SessionResult {
+                    token: token
+                        .parse()
+                        .expect("token of existing session should be valid"),
+                    status: session.status,
+                    session_type: session.request.session_type(),
+                    proof_status: None,
+                    disclosed: None,
+                    signature: None,
+                    error: None,
+                    next_session: None,
+                })
+        })
+    }
+
+    /// Streams the status of the session with the given `token` as server-sent events, until
+    /// the session is finished.
+    fn statusevents(self: Arc<Self>, token: String) -> actix_web::HttpResponse {
+        let stream = futures::stream::unfold(
+            (self, token, None::<yivi::Status>),
+            |(ctx, token, last_status)| async move {
+                loop {
+                    if last_status.is_some() {
+                        tokio::time::sleep(STATUS_POLL_INTERVAL).await;
+                    }
+
+                    let status = ctx.update_session(&token, |session| session.status)?;
+
+                    if last_status == Some(status) {
+                        if matches!(status, yivi::Status::Done | yivi::Status::Cancelled) {
+                            return None;
+                        }
+                        continue;
+                    }
+
+                    let event = serde_json::to_string(&FrontendSessionStatus {
+                        status,
+                        next_session: None,
+                    })
+                    .expect("status should serialize");
+
+                    return Some((
+                        Ok::<_, actix_web::Error>(web::Bytes::from(format!("data: {event}\n\n"))),
+                        (ctx, token, Some(status)),
+                    ));
+                }
+            },
+        );
+
+        actix_web::HttpResponse::Ok()
+            .content_type("text/event-stream")
+            .streaming(stream)
+    }
+}
+
+type CtxData = web::Data<Arc<Ctx>>;
+
+async fn handle_publickey(ctx: CtxData) -> actix_web::HttpResponse {
+    let yivi::SigningKey::RS256(sk) = &ctx.config.server_creds.key else {
+        return actix_web::HttpResponse::NotFound().finish();
+    };
+
+    match jwt::RS256Vk::new(sk.as_rsa_pub().clone()).to_public_key_pem() {
+        Ok(pem) => actix_web::HttpResponse::Ok()
+            .content_type("text/plain")
+            .body(pem),
+        Err(err) => {
+            log::error!("failed to encode public key: {err}");
+            actix_web::HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+async fn handle_session_start(ctx: CtxData, body: String) -> actix_web::HttpResponse {
+    let request = match yivi::ExtendedSessionRequest::open_signed(
+        &jwt::JWT::from(body.trim().to_string()),
+        &ctx.config.requestor_creds,
+    ) {
+        Ok(request) => request,
+        Err(err) => {
+            log::warn!("refusing session request: {err:#}");
+            return actix_web::HttpResponse::Unauthorized().finish();
+        }
+    };
+
+    actix_web::HttpResponse::Ok().json(ctx.start_session(request))
+}
+
+/// Accepts every properly signed [`yivi::RevocationRequest`];  there is nothing to revoke.
+async fn handle_revocation(ctx: CtxData, body: String) -> actix_web::HttpResponse {
+    match yivi::RevocationRequest::open_signed(
+        &jwt::JWT::from(body.trim().to_string()),
+        &ctx.config.requestor_creds,
+    ) {
+        Ok(rr) => {
+            log::info!(
+                "revoked {} credentials with key {}",
+                rr.credential,
+                rr.revocation_key
+            );
+            actix_web::HttpResponse::Ok().finish()
+        }
+        Err(err) => {
+            log::warn!("refusing revocation request: {err:#}");
+            actix_web::HttpResponse::Unauthorized().finish()
+        }
+    }
+}
+
+async fn handle_status(ctx: CtxData, token: web::Path<String>) -> actix_web::HttpResponse {
+    match ctx.update_session(&token, |session| session.status) {
+        Some(status) => actix_web::HttpResponse::Ok().json(status),
+        None => actix_web::HttpResponse::NotFound().finish(),
+    }
+}
+
+async fn handle_statusevents(ctx: CtxData, token: web::Path<String>) -> actix_web::HttpResponse {
+    let ctx: Arc<Ctx> = Arc::clone(&ctx);
+    ctx.statusevents(token.into_inner())
+}
+
+async fn handle_frontend_statusevents(
+    ctx: CtxData,
+    client_token: web::Path<String>,
+) -> actix_web::HttpResponse {
+    let token = ctx
+        .sessions
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(_, session)| session.client_token == *client_token)
+        .map(|(token, _)| token.clone());
+
+    let Some(token) = token else {
+        return actix_web::HttpResponse::NotFound().finish();
+    };
+
+    let ctx: Arc<Ctx> = Arc::clone(&ctx);
+    ctx.statusevents(token)
+}
+
+async fn handle_result(ctx: CtxData, token: web::Path<String>) -> actix_web::HttpResponse {
+    match ctx.result(&token) {
+        Some(result) => actix_web::HttpResponse::Ok().json(result),
+        None => actix_web::HttpResponse::NotFound().finish(),
+    }
+}
+
+async fn handle_result_jwt(ctx: CtxData, token: web::Path<String>) -> actix_web::HttpResponse {
+    let Some(result) = ctx.result(&token) else {
+        return actix_web::HttpResponse::NotFound().finish();
+    };
+
+    match ctx.sign_result(result) {
+        Ok(jwt) => actix_web::HttpResponse::Ok()
+            .content_type("text/plain")
+            .body(jwt.as_str().to_string()),
+        Err(err) => {
+            log::error!("failed to sign session result: {err:#}");
+            actix_web::HttpResponse::InternalServerError().finish()
+        }
+    }
+}
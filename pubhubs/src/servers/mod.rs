@@ -3,6 +3,7 @@
 pub mod config;
 pub mod constellation;
 pub mod macros;
+pub mod mock_yivi;
 mod object_store;
 mod run;
 pub(super) mod server;
@@ -16,11 +17,13 @@ pub use auths::card::CardConfig;
 pub use config::Config;
 pub use constellation::Constellation;
 pub use macros::for_all_servers;
+pub use object_store::DefaultObjectStore;
 pub(super) use run::Handle;
 pub use run::{Set, SetOpts};
 pub use server::Name;
 pub(super) use server::{
-    App, AppBase, AppCreator, AppCreatorBase, Command, Details, DiscoverVerdict, Server, ServerImpl,
+    App, AppBase, AppCreator, AppCreatorBase, Command, Details, DiscoverVerdict, RevocationsCache,
+    Server, ServerImpl,
 };
 
 pub(crate) mod version;
@@ -125,6 +125,8 @@ impl<'a> TryFrom<&'a Option<ObjectStoreConfig>> for DefaultObjectStore {
                 })?)
             }
 
+            object_store::ObjectStoreScheme::Memory => Box::new(memory_store(url)),
+
             // Non-S3 object store
             _ => {
                 let (store, _path) = object_store::parse_url_opts(url, c.options.iter())
@@ -145,6 +147,26 @@ impl<'a> TryFrom<&'a Option<ObjectStoreConfig>> for DefaultObjectStore {
     }
 }
 
+/// Returns the in-memory object store for the given `memory://` url.
+///
+/// Servers in the same process using the same url share the same store, so that its contents
+/// survive a restart of the server, for example after its configuration was changed via
+/// [`api::admin::UpdateConfigEP`].
+fn memory_store(url: &url::Url) -> std::sync::Arc<object_store::memory::InMemory> {
+    static STORES: std::sync::LazyLock<
+        std::sync::Mutex<
+            std::collections::HashMap<url::Url, std::sync::Arc<object_store::memory::InMemory>>,
+        >,
+    > = std::sync::LazyLock::new(Default::default);
+
+    STORES
+        .lock()
+        .unwrap()
+        .entry(url.clone())
+        .or_default()
+        .clone()
+}
+
 /// Details on how to store this type in the object store.
 ///
 /// You probably want to implement this trait via [`JsonObjectDetails`].
@@ -337,6 +359,62 @@ where
         }
     }
 
+    /// Returns the identifiers of at most `limit` objects of type `T` in the object store, in
+    /// the order of their paths, starting after the object with identifier `after` (if given).
+    ///
+    /// Pass the last identifier returned as `after` to get the next page;  an empty page means
+    /// there are no more objects.  Relies on the object store listing objects in lexicographical
+    /// order, as S3 and the in-memory store do.
+    pub async fn list_object_ids<T>(
+        &self,
+        after: Option<&T::Identifier>,
+        limit: usize,
+    ) -> api::Result<Vec<T::Identifier>>
+    where
+        T: ObjectDetails,
+        T::Identifier: std::str::FromStr,
+    {
+        use futures::{StreamExt as _, TryStreamExt as _};
+
+        let os = self.shared.object_store.as_object_store();
+
+        let prefix = object_store::path::Path::from(T::PREFIX);
+
+        log::debug!("listing {limit} objects under {prefix}");
+
+        let metas: Vec<object_store::ObjectMeta> = match after {
+            Some(after) => os.list_with_offset(Some(&prefix), &T::path_for(after)),
+            None => os.list(Some(&prefix)),
+        }
+        .take(limit)
+        .try_collect()
+        .await
+        .map_err(|err| {
+            log::error!(
+                "{}'s object store: unexpected error listing {prefix}: {err:#}",
+                S::NAME
+            );
+            api::ErrorCode::InternalError
+        })?;
+
+        metas
+            .iter()
+            .map(|meta| {
+                meta.location
+                    .filename()
+                    .and_then(|filename| filename.parse().ok())
+                    .ok_or_else(|| {
+                        log::error!(
+                            "{}'s object store: unexpected object at {}",
+                            S::NAME,
+                            meta.location
+                        );
+                        api::ErrorCode::InternalError
+                    })
+            })
+            .collect()
+    }
+
     /// Attempts to delete an object with the given [`Id`]; returns `true` when an object was
     /// deleted, and false when no object with the given `id` was found.
     pub async fn delete_object<T>(&self, id: T::Identifier) -> api::Result<bool>
@@ -386,3 +464,30 @@ impl JsonObjectDetails for crate::servers::phc::UserState {
         &self.id
     }
 }
+
+impl JsonObjectDetails for crate::servers::phc::BanLog {
+    type Identifier = Id;
+    const PREFIX: &str = "ban-log";
+
+    fn object_id(&self) -> &Id {
+        &self.attr
+    }
+}
+
+impl JsonObjectDetails for crate::servers::phc::HubRegistry {
+    type Identifier = &'static str;
+    const PREFIX: &str = "hub-registry";
+
+    fn object_id(&self) -> &&'static str {
+        &crate::servers::phc::HUB_REGISTRY_ID
+    }
+}
+
+impl JsonObjectDetails for crate::servers::phc::IssuedTickets {
+    type Identifier = Id;
+    const PREFIX: &str = "hub-tickets";
+
+    fn object_id(&self) -> &Id {
+        &self.hub
+    }
+}
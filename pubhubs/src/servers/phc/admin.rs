@@ -0,0 +1,856 @@
+//! PHC-specific `.ph/admin/...` endpoints, such as [`BanEP`] and [`AddHubEP`].
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use actix_web::web;
+
+use crate::api::{self, ApiResultExt as _, NoPayload, OpenError};
+use crate::attr::{Attr, AttrState};
+use crate::handle;
+use crate::hub;
+use crate::id::Id;
+use crate::phcrypto;
+
+use super::hub_registry::{IssuedTickets, TicketRecord};
+use super::server::*;
+use super::user::UserState;
+use api::admin::*;
+
+/// How long the requests to revoke cards sent to the authentication server are valid
+const CARD_REVOCATION_REQ_VALIDITY: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// The append-only log of [`BanRecord`]s of an attribute, stored in PHC's object store.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct BanLog {
+    /// The [`Id`] of the attribute this log is about
+    pub attr: Id,
+
+    /// Oldest first
+    pub records: Vec<BanRecord>,
+}
+
+impl App {
+    /// Implements [`BanEP`]
+    pub(super) async fn handle_admin_ban(
+        app: Rc<Self>,
+        signed_req: web::Json<api::Signed<BanReq>>,
+    ) -> api::Result<BanResp> {
+        let signed_req = signed_req.into_inner();
+        let request_id = signed_req.id();
+
+        let req = match app.open_admin_req(
+            signed_req,
+            BanResp::ResignRequest,
+            BanResp::InvalidAdminKey,
+        )? {
+            Ok(req) => req,
+            Err(resp) => return Ok(resp),
+        };
+
+        let Some(attr_id) = app.attr_id_from_handle(&req.attr_type, req.value).await? else {
+            return Ok(BanResp::UnknownAttrType);
+        };
+
+        let attr_state = app
+            .set_attr_banned(attr_id, BanAction::Ban, req.reason, request_id)
+            .await?;
+
+        let mut banned_users: Vec<Id> = Vec::with_capacity(attr_state.bans_users.len());
+
+        for user_id in attr_state.bans_users.iter() {
+            if app.ban_user_state(*user_id).await? {
+                banned_users.push(*user_id);
+            }
+        }
+
+        log::info!(
+            "admin banned attribute {attr_id} of type {}, banning {} user(s)",
+            req.attr_type,
+            banned_users.len()
+        );
+
+        Ok(BanResp::Success { banned_users })
+    }
+
+    /// Implements [`UnbanEP`]
+    pub(super) async fn handle_admin_unban(
+        app: Rc<Self>,
+        signed_req: web::Json<api::Signed<UnbanReq>>,
+    ) -> api::Result<UnbanResp> {
+        let signed_req = signed_req.into_inner();
+        let request_id = signed_req.id();
+
+        let req = match app.open_admin_req(
+            signed_req,
+            UnbanResp::ResignRequest,
+            UnbanResp::InvalidAdminKey,
+        )? {
+            Ok(req) => req,
+            Err(resp) => return Ok(resp),
+        };
+
+        let Some(attr_id) = app.attr_id_from_handle(&req.attr_type, req.value).await? else {
+            return Ok(UnbanResp::UnknownAttrType);
+        };
+
+        let attr_state = app
+            .set_attr_banned(attr_id, BanAction::Unban, req.reason, request_id)
+            .await?;
+
+        let mut unbanned_users: Vec<Id> = Default::default();
+        let mut still_banned_users: Vec<Id> = Default::default();
+
+        // We re-evaluate the users even when the attribute was not banned, so that an unban
+        // that failed halfway can be completed by retrying it.
+        for user_id in attr_state.bans_users.iter() {
+            match app.reevaluate_user_ban(*user_id).await? {
+                Some(false) => unbanned_users.push(*user_id),
+                Some(true) => still_banned_users.push(*user_id),
+                None => {}
+            }
+        }
+
+        log::info!(
+            "admin unbanned attribute {attr_id} of type {}, unbanning {} user(s), \
+            while {} user(s) remain banned",
+            req.attr_type,
+            unbanned_users.len(),
+            still_banned_users.len(),
+        );
+
+        Ok(UnbanResp::Success {
+            unbanned_users,
+            still_banned_users,
+        })
+    }
+
+    /// Implements [`BanLogEP`]
+    pub(super) async fn handle_admin_ban_log(
+        app: Rc<Self>,
+        signed_req: web::Json<api::Signed<BanLogReq>>,
+    ) -> api::Result<BanLogResp> {
+        let req = match app.open_admin_req(
+            signed_req.into_inner(),
+            BanLogResp::ResignRequest,
+            BanLogResp::InvalidAdminKey,
+        )? {
+            Ok(req) => req,
+            Err(resp) => return Ok(resp),
+        };
+
+        let attr_ids: Vec<Id> = match req {
+            BanLogReq::Attr { attr_type, value } => {
+                let Some(attr_id) = app.attr_id_from_handle(&attr_type, value).await? else {
+                    return Ok(BanLogResp::UnknownAttrType);
+                };
+                vec![attr_id]
+            }
+            BanLogReq::User(user_id) => {
+                let Some((user_state, _)) = app.get_object::<UserState>(&user_id).await? else {
+                    return Ok(BanLogResp::UnknownUser);
+                };
+                user_state.could_be_banned_by.into_iter().collect()
+            }
+        };
+
+        let mut records: HashMap<Id, Vec<BanRecord>> = Default::default();
+
+        for attr_id in attr_ids {
+            if let Some((ban_log, _)) = app.get_object::<BanLog>(&attr_id).await? {
+                records.insert(attr_id, ban_log.records);
+            }
+        }
+
+        Ok(BanLogResp::Success { records })
+    }
+
+    /// Implements [`RevokeCardsEP`]
+    pub(super) async fn handle_admin_revoke_cards(
+        app: Rc<Self>,
+        signed_req: web::Json<api::Signed<RevokeCardsReq>>,
+    ) -> api::Result<RevokeCardsResp> {
+        let req = match app.open_admin_req(
+            signed_req.into_inner(),
+            RevokeCardsResp::ResignRequest,
+            RevokeCardsResp::InvalidAdminKey,
+        )? {
+            Ok(req) => req,
+            Err(resp) => return Ok(resp),
+        };
+
+        let Some((mut user_state, version)) = app.get_object::<UserState>(&req.user).await? else {
+            return Ok(RevokeCardsResp::UnknownUser);
+        };
+
+        if !user_state.banned {
+            return Ok(RevokeCardsResp::UserNotBanned);
+        }
+
+        let revoked_cards = user_state.revoke_cards().to_vec();
+
+        // We have the yivi server revoke the cards before recording the revocation, so that
+        // the admin can simply retry when the former fails.
+        let mut revoked_at_yivi = true;
+
+        for rc in revoked_cards.iter() {
+            revoked_at_yivi &= match rc.revocation_nonce {
+                Some(revocation_nonce) => app.revoke_cards_at_yivi(revocation_nonce).await?,
+                None => false,
+            };
+        }
+
+        if app
+            .put_object::<UserState>(&user_state, Some(version))
+            .await?
+            .is_none()
+        {
+            log::debug!("user state {} changed while revoking its cards", req.user);
+            return Err(api::ErrorCode::PleaseRetry);
+        }
+
+        log::info!(
+            "admin revoked cards of user {} (revoked at yivi: {revoked_at_yivi})",
+            req.user
+        );
+
+        let mut card_pseuds = revoked_cards.into_iter().map(|rc| rc.card_pseud);
+
+        Ok(RevokeCardsResp::Success {
+            card_pseud: card_pseuds.next().unwrap(),
+            merged_card_pseuds: card_pseuds.collect(),
+            revoked_at_yivi,
+        })
+    }
+
+    /// Implements [`AddHubEP`]
+    pub(super) async fn handle_admin_add_hub(
+        app: Rc<Self>,
+        signed_req: web::Json<api::Signed<AddHubReq>>,
+    ) -> api::Result<AddHubResp> {
+        let req = match app.open_admin_req(
+            signed_req.into_inner(),
+            AddHubResp::ResignRequest,
+            AddHubResp::InvalidAdminKey,
+        )? {
+            Ok(req) => req,
+            Err(resp) => return Ok(resp),
+        };
+
+        if !req.url.as_str().ends_with('/') {
+            return Ok(AddHubResp::InvalidUrl);
+        }
+
+        let (mut registry, version, _) = app.get_hub_registry().await?;
+
+        let id = req.id.unwrap_or_else(Id::random);
+
+        if registry.id_taken(&id) {
+            return Ok(AddHubResp::IdTaken);
+        }
+
+        for (i, handle) in req.handles.iter().enumerate() {
+            if registry.handle_taken(handle) || req.handles[..i].contains(handle) {
+                return Ok(AddHubResp::HandleTaken(handle.clone()));
+            }
+        }
+
+        let hub = hub::BasicInfo {
+            handles: req.handles,
+            name: req.name,
+            description: req.description,
+            url: req.url,
+            id,
+        };
+
+        registry.active.push(hub.clone());
+
+        app.put_hub_registry(registry, version).await?;
+
+        log::info!("admin added hub {}", hub.handles.preferred());
+
+        Ok(AddHubResp::Success(hub))
+    }
+
+    /// Implements [`UpdateHubEP`]
+    pub(super) async fn handle_admin_update_hub(
+        app: Rc<Self>,
+        signed_req: web::Json<api::Signed<UpdateHubReq>>,
+    ) -> api::Result<UpdateHubResp> {
+        let req = match app.open_admin_req(
+            signed_req.into_inner(),
+            UpdateHubResp::ResignRequest,
+            UpdateHubResp::InvalidAdminKey,
+        )? {
+            Ok(req) => req,
+            Err(resp) => return Ok(resp),
+        };
+
+        if req
+            .url
+            .as_ref()
+            .is_some_and(|url| !url.as_str().ends_with('/'))
+        {
+            return Ok(UpdateHubResp::InvalidUrl);
+        }
+
+        let (mut registry, version, _) = app.get_hub_registry().await?;
+
+        let Some(mut hub) = registry.hub_by_handle(&req.hub, false).cloned() else {
+            return Ok(UpdateHubResp::UnknownHub);
+        };
+
+        // Handles may be added, but not removed, see [`hub::BasicInfo::handles`].
+        let mut handles: Vec<handle::Handle> = hub.handles.to_vec();
+
+        for handle in req.add_handles {
+            if handles.contains(&handle) {
+                continue;
+            }
+
+            if registry.handle_taken(&handle) {
+                return Ok(UpdateHubResp::HandleTaken(handle));
+            }
+
+            handles.push(handle);
+        }
+
+        hub.handles = handles.into();
+
+        if let Some(name) = req.name {
+            hub.name = name;
+        }
+
+        if let Some(description) = req.description {
+            hub.description = description;
+        }
+
+        if let Some(url) = req.url {
+            hub.url = url;
+        }
+
+        let Some(registered_hub) = registry.active.iter_mut().find(|h| h.id == hub.id) else {
+            // `hub` was found in this very copy of the registry, so this should not happen
+            log::error!("bug: hub {} disappeared from the hub registry", hub.id);
+            return Err(api::ErrorCode::InternalError);
+        };
+
+        if *registered_hub == hub {
+            return Ok(UpdateHubResp::Success(hub));
+        }
+
+        *registered_hub = hub.clone();
+
+        app.put_hub_registry(registry, version).await?;
+
+        log::info!("admin updated hub {}", hub.handles.preferred());
+
+        Ok(UpdateHubResp::Success(hub))
+    }
+
+    /// Implements [`RetireHubEP`]
+    pub(super) async fn handle_admin_retire_hub(
+        app: Rc<Self>,
+        signed_req: web::Json<api::Signed<RetireHubReq>>,
+    ) -> api::Result<RetireHubResp> {
+        let req = match app.open_admin_req(
+            signed_req.into_inner(),
+            RetireHubResp::ResignRequest,
+            RetireHubResp::InvalidAdminKey,
+        )? {
+            Ok(req) => req,
+            Err(resp) => return Ok(resp),
+        };
+
+        let (mut registry, version, _) = app.get_hub_registry().await?;
+
+        let Some(index) = registry
+            .active
+            .iter()
+            .position(|hub| hub.handles.contains(&req.hub))
+        else {
+            return Ok(RetireHubResp::UnknownHub);
+        };
+
+        let hub = registry.active.remove(index);
+
+        log::info!("admin retires hub {}", hub.handles.preferred());
+
+        registry.retired.push(hub);
+
+        app.put_hub_registry(registry, version).await?;
+
+        Ok(RetireHubResp::Success)
+    }
+
+    /// Implements [`PendingHubsEP`]
+    pub(super) async fn handle_admin_pending_hubs(
+        app: Rc<Self>,
+        signed_req: web::Json<api::Signed<PendingHubsReq>>,
+    ) -> api::Result<PendingHubsResp> {
+        if let Err(resp) = app.open_admin_req(
+            signed_req.into_inner(),
+            PendingHubsResp::ResignRequest,
+            PendingHubsResp::InvalidAdminKey,
+        )? {
+            return Ok(resp);
+        }
+
+        let (registry, ..) = app.get_hub_registry().await?;
+
+        Ok(PendingHubsResp::Success {
+            pending: registry.pending,
+        })
+    }
+
+    /// Implements [`ApproveHubEP`]
+    pub(super) async fn handle_admin_approve_hub(
+        app: Rc<Self>,
+        signed_req: web::Json<api::Signed<ApproveHubReq>>,
+    ) -> api::Result<ApproveHubResp> {
+        let req = match app.open_admin_req(
+            signed_req.into_inner(),
+            ApproveHubResp::ResignRequest,
+            ApproveHubResp::InvalidAdminKey,
+        )? {
+            Ok(req) => req,
+            Err(resp) => return Ok(resp),
+        };
+
+        let (mut registry, version, _) = app.get_hub_registry().await?;
+
+        let Some(index) = registry.pending_by_handle(&req.handle) else {
+            return Ok(ApproveHubResp::UnknownPendingHub);
+        };
+
+        let hub = registry.pending.remove(index).info;
+
+        if registry.id_taken(&hub.id) || hub.handles.iter().any(|h| registry.handle_taken(h)) {
+            return Ok(ApproveHubResp::HandleTaken);
+        }
+
+        registry.active.push(hub.clone());
+
+        app.put_hub_registry(registry, version).await?;
+
+        log::info!("admin approved hub {}", req.handle);
+
+        Ok(ApproveHubResp::Success(hub))
+    }
+
+    /// Implements [`RejectHubEP`]
+    pub(super) async fn handle_admin_reject_hub(
+        app: Rc<Self>,
+        signed_req: web::Json<api::Signed<RejectHubReq>>,
+    ) -> api::Result<RejectHubResp> {
+        let req = match app.open_admin_req(
+            signed_req.into_inner(),
+            RejectHubResp::ResignRequest,
+            RejectHubResp::InvalidAdminKey,
+        )? {
+            Ok(req) => req,
+            Err(resp) => return Ok(resp),
+        };
+
+        let (mut registry, version, _) = app.get_hub_registry().await?;
+
+        let Some(index) = registry.pending_by_handle(&req.handle) else {
+            return Ok(RejectHubResp::UnknownPendingHub);
+        };
+
+        registry.pending.remove(index);
+
+        app.put_hub_registry(registry, version).await?;
+
+        log::info!("admin rejected hub {}", req.handle);
+
+        Ok(RejectHubResp::Success)
+    }
+
+    /// Implements [`SuspendHubEP`]
+    pub(super) async fn handle_admin_suspend_hub(
+        app: Rc<Self>,
+        signed_req: web::Json<api::Signed<SuspendHubReq>>,
+    ) -> api::Result<SuspendHubResp> {
+        let req = match app.open_admin_req(
+            signed_req.into_inner(),
+            SuspendHubResp::ResignRequest,
+            SuspendHubResp::InvalidAdminKey,
+        )? {
+            Ok(req) => req,
+            Err(resp) => return Ok(resp),
+        };
+
+        let (mut registry, version, _) = app.get_hub_registry().await?;
+
+        let Some(hub_id) = registry.hub_by_handle(&req.hub, false).map(|hub| hub.id) else {
+            return Ok(SuspendHubResp::UnknownHub);
+        };
+
+        if !registry.suspended.contains(&hub_id) {
+            registry.suspended.push(hub_id);
+            app.put_hub_registry(registry, version).await?;
+
+            log::info!("admin suspended hub {}", req.hub);
+        }
+
+        // Now that the suspension has been published, no new tickets will be handed out to the
+        // hub, so we can revoke the ones issued so far.  This is also done when the hub was
+        // already suspended, so that a suspension that failed halfway can be completed by
+        // retrying it.
+        let revoked_tickets = app.revoke_issued_tickets(hub_id).await?;
+
+        Ok(SuspendHubResp::Success { revoked_tickets })
+    }
+
+    /// Implements [`UnsuspendHubEP`]
+    pub(super) async fn handle_admin_unsuspend_hub(
+        app: Rc<Self>,
+        signed_req: web::Json<api::Signed<UnsuspendHubReq>>,
+    ) -> api::Result<UnsuspendHubResp> {
+        let req = match app.open_admin_req(
+            signed_req.into_inner(),
+            UnsuspendHubResp::ResignRequest,
+            UnsuspendHubResp::InvalidAdminKey,
+        )? {
+            Ok(req) => req,
+            Err(resp) => return Ok(resp),
+        };
+
+        let (mut registry, version, _) = app.get_hub_registry().await?;
+
+        let Some(hub_id) = registry.hub_by_handle(&req.hub, false).map(|hub| hub.id) else {
+            return Ok(UnsuspendHubResp::UnknownHub);
+        };
+
+        if !registry.suspended.contains(&hub_id) {
+            return Ok(UnsuspendHubResp::Success);
+        }
+
+        registry.suspended.retain(|id| *id != hub_id);
+
+        app.put_hub_registry(registry, version).await?;
+
+        log::info!("admin lifted the suspension of hub {}", req.hub);
+
+        Ok(UnsuspendHubResp::Success)
+    }
+
+    /// Adds the unexpired [`IssuedTickets`] of the hub with the given [`Id`] to
+    /// [`HubRegistry::revoked_tickets`], returning their number.
+    ///
+    /// Returns [`api::ErrorCode::PleaseRetry`] when the hub registry was changed concurrently.
+    ///
+    /// [`HubRegistry::revoked_tickets`]: super::HubRegistry::revoked_tickets
+    async fn revoke_issued_tickets(&self, hub_id: Id) -> api::Result<usize> {
+        let Some((issued_tickets, _)) = self.get_object::<IssuedTickets>(&hub_id).await? else {
+            return Ok(0);
+        };
+
+        let (mut registry, version, _) = self.get_hub_registry().await?;
+
+        let now = api::NumericDate::now();
+
+        let to_revoke: Vec<TicketRecord> = issued_tickets
+            .tickets
+            .into_iter()
+            .filter(|record| record.not_valid_after >= now)
+            .collect();
+
+        let count = to_revoke.len();
+
+        let mut changed = false;
+
+        registry.revoked_tickets.retain(|record| {
+            let keep = record.not_valid_after >= now;
+            changed |= !keep;
+            keep
+        });
+
+        for record in to_revoke {
+            if !registry.revoked_tickets.contains(&record) {
+                registry.revoked_tickets.push(record);
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.put_hub_registry(registry, version).await?;
+        }
+
+        Ok(count)
+    }
+
+    /// Opens a request signed using the admin key, returning `Ok(Err(resign))` when the signature
+    /// expired, and `Ok(Err(invalid_admin_key))` when the signature is invalid.
+    fn open_admin_req<T: api::Signable, R>(
+        &self,
+        signed_req: api::Signed<T>,
+        resign: R,
+        invalid_admin_key: R,
+    ) -> api::Result<Result<T, R>> {
+        match signed_req.open(&self.admin_key, None) {
+            Ok(req) => Ok(Ok(req)),
+            Err(OpenError::OtherConstellation(..)) | Err(OpenError::InternalError) => {
+                Err(api::ErrorCode::InternalError)
+            }
+            Err(OpenError::OtherwiseInvalid) => Err(api::ErrorCode::BadRequest),
+            Err(OpenError::Expired) => Ok(Err(resign)),
+            Err(OpenError::InvalidSignature) => Ok(Err(invalid_admin_key)),
+        }
+    }
+
+    /// Computes the [`Id`] of the attribute with the given value and type, where the type is
+    /// looked up by `attr_type` at the authentication server.  The value is normalized like the
+    /// authentication server would (see [`crate::attr::Type::normalizer`]), and an
+    /// [`api::ErrorCode::BadRequest`] is returned when it cannot be.
+    ///
+    /// Returns `Ok(None)` when the authentication server knows no attribute type with that handle.
+    pub(super) async fn attr_id_from_handle(
+        &self,
+        attr_type: &handle::Handle,
+        value: String,
+    ) -> api::Result<Option<Id>> {
+        let welcome = self
+            .client
+            .query::<api::auths::WelcomeEP>(&self.auths_url, NoPayload)
+            .await
+            .into_server_result()?;
+
+        let Some(attr_type) = welcome
+            .attr_types
+            .into_values()
+            .find(|at| at.handles.contains(attr_type))
+        else {
+            return Ok(None);
+        };
+
+        let normalized_value = attr_type.normalizer.normalize(&value).map_err(|err| {
+            log::debug!("admin supplied {attr_type} value that cannot be normalized: {err:#}");
+            api::ErrorCode::BadRequest
+        })?;
+
+        let attr = Attr {
+            attr_type: attr_type.id,
+            unnormalized_value: (normalized_value != value).then_some(value),
+            value: normalized_value,
+            bannable: attr_type.bannable,
+            not_identifying: !attr_type.identifying,
+            not_addable: false,
+            source: None,
+        };
+
+        Ok(Some(self.attr_id(&attr).await?))
+    }
+
+    /// Sets [`AttrState::banned`] according to `action` for the attribute with the given [`Id`],
+    /// returning the updated [`AttrState`].
+    ///
+    /// When banning, the [`AttrState`] is created if it did not exist yet, so that the attribute
+    /// can not be used in the future either.
+    ///
+    /// When the ban status actually changes, a [`BanRecord`] is appended to the attribute's
+    /// [`BanLog`] first, identified by the `request_id` of the signed admin request.
+    ///
+    /// Returns [`api::ErrorCode::PleaseRetry`] when the attribute state was changed concurrently.
+    async fn set_attr_banned(
+        &self,
+        attr_id: Id,
+        action: BanAction,
+        reason: String,
+        request_id: Id,
+    ) -> api::Result<AttrState> {
+        let banned = action == BanAction::Ban;
+
+        let (mut attr_state, version) = match self.get_object::<AttrState>(&attr_id).await? {
+            Some((attr_state, version)) => (attr_state, Some(version)),
+            None => (
+                AttrState {
+                    attr: attr_id,
+                    banned: false,
+                    may_identify_user: None,
+                    bans_users: Default::default(),
+                },
+                None,
+            ),
+        };
+
+        if attr_state.banned == banned {
+            return Ok(attr_state);
+        }
+
+        self.append_ban_record(
+            attr_id,
+            BanRecord {
+                action,
+                at: api::NumericDate::now(),
+                reason,
+                admin_key_id: phcrypto::admin_key_id(&self.admin_key),
+                request_id: Some(request_id),
+            },
+        )
+        .await?;
+
+        attr_state.banned = banned;
+
+        if self
+            .put_object::<AttrState>(&attr_state, version)
+            .await?
+            .is_none()
+        {
+            log::debug!("attribute state {attr_id} changed while (un)banning it");
+            return Err(api::ErrorCode::PleaseRetry);
+        }
+
+        Ok(attr_state)
+    }
+
+    /// Appends the given [`BanRecord`] to the [`BanLog`] of the attribute with the given [`Id`],
+    /// unless the log already has a record of the same request.  The latter happens when the
+    /// request is retried after [`Self::set_attr_banned`] appended the record, but could not
+    /// update the [`AttrState`].
+    ///
+    /// Returns [`api::ErrorCode::PleaseRetry`] when the log was changed concurrently.
+    async fn append_ban_record(&self, attr_id: Id, record: BanRecord) -> api::Result<()> {
+        let (mut ban_log, version) = match self.get_object::<BanLog>(&attr_id).await? {
+            Some((ban_log, version)) => (ban_log, Some(version)),
+            None => (
+                BanLog {
+                    attr: attr_id,
+                    records: Default::default(),
+                },
+                None,
+            ),
+        };
+
+        if record.request_id.is_some()
+            && ban_log
+                .records
+                .iter()
+                .any(|earlier| earlier.request_id == record.request_id)
+        {
+            log::debug!(
+                "ban log of attribute {attr_id} already records this {action:?}",
+                action = record.action
+            );
+            return Ok(());
+        }
+
+        ban_log.records.push(record);
+
+        if self
+            .put_object::<BanLog>(&ban_log, version)
+            .await?
+            .is_none()
+        {
+            log::debug!("ban log of attribute {attr_id} changed while appending to it");
+            return Err(api::ErrorCode::PleaseRetry);
+        }
+
+        Ok(())
+    }
+
+    /// Asks the authentication server to have the yivi server revoke the cards issued with the
+    /// given revocation nonce.  Returns whether it did.
+    async fn revoke_cards_at_yivi(&self, revocation_nonce: Id) -> api::Result<bool> {
+        let resp = self
+            .client
+            .query::<api::auths::CardRevocationEP>(
+                &self.auths_url,
+                api::Signed::new(
+                    self.shared.active_signing_key(),
+                    &api::auths::CardRevocationReq { revocation_nonce },
+                    CARD_REVOCATION_REQ_VALIDITY,
+                )?,
+            )
+            .await
+            .into_server_result()?;
+
+        Ok(match resp {
+            api::auths::CardRevocationResp::Success => true,
+            api::auths::CardRevocationResp::RevocationNotEnabled => {
+                log::warn!(
+                    "cards were issued with a revocation key, but the authentication server \
+                    no longer has revocation enabled"
+                );
+                false
+            }
+            api::auths::CardRevocationResp::ResignRequest => {
+                log::warn!(
+                    "authentication server considers our fresh card revocation request expired"
+                );
+                return Err(api::ErrorCode::PleaseRetry);
+            }
+        })
+    }
+
+    /// Sets [`UserState::banned`] for the user with the given [`Id`].
+    ///
+    /// Returns `Ok(false)` when no such user exists, which may happen for orphaned
+    /// registrations, and [`api::ErrorCode::PleaseRetry`] when the user state was changed
+    /// concurrently.
+    async fn ban_user_state(&self, user_id: Id) -> api::Result<bool> {
+        let Some((mut user_state, version)) = self.get_object::<UserState>(&user_id).await? else {
+            log::warn!("banned attribute refers to user {user_id} that does not exist");
+            return Ok(false);
+        };
+
+        if user_state.banned {
+            return Ok(true);
+        }
+
+        user_state.banned = true;
+
+        if self
+            .put_object::<UserState>(&user_state, Some(version))
+            .await?
+            .is_none()
+        {
+            log::debug!("user state {user_id} changed while banning it");
+            return Err(api::ErrorCode::PleaseRetry);
+        }
+
+        Ok(true)
+    }
+
+    /// Unbans the user with the given [`Id`] unless one of the attributes in
+    /// [`UserState::could_be_banned_by`] is still banned.  Returns whether the user remains
+    /// banned, or `Ok(None)` when no such user exists.
+    ///
+    /// Returns [`api::ErrorCode::PleaseRetry`] when the user state was changed concurrently.
+    async fn reevaluate_user_ban(&self, user_id: Id) -> api::Result<Option<bool>> {
+        let Some((mut user_state, version)) = self.get_object::<UserState>(&user_id).await? else {
+            log::warn!("unbanned attribute refers to user {user_id} that does not exist");
+            return Ok(None);
+        };
+
+        for attr_id in user_state.could_be_banned_by.iter() {
+            if let Some((attr_state, _)) = self.get_object::<AttrState>(attr_id).await?
+                && attr_state.banned
+            {
+                if !user_state.banned {
+                    log::warn!(
+                        "user {user_id} is not banned, while their bannable attribute {attr_id} is"
+                    );
+                }
+                return Ok(Some(true));
+            }
+        }
+
+        if !user_state.banned {
+            return Ok(Some(false));
+        }
+
+        user_state.banned = false;
+
+        if self
+            .put_object::<UserState>(&user_state, Some(version))
+            .await?
+            .is_none()
+        {
+            log::debug!("user state {user_id} changed while unbanning it");
+            return Err(api::ErrorCode::PleaseRetry);
+        }
+
+        Ok(Some(false))
+    }
+}
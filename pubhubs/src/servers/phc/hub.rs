@@ -7,9 +7,22 @@ use crate::api::ApiResultExt as _;
 use crate::api::OpenError;
 use crate::api::{self, NoPayload};
 
+use crate::id::Id;
+use crate::phcrypto;
+
+use super::hub_registry::{IssuedTickets, TicketRecord};
 use super::server::*;
 use api::phc::hub::*;
 
+/// The maximal number of hubs awaiting approval, see [`OnboardEP`].
+const MAX_PENDING_HUBS: usize = 100;
+
+/// How long a hub awaits approval before its request is dropped, see [`OnboardEP`].
+const PENDING_HUB_VALIDITY: std::time::Duration = std::time::Duration::from_secs(3600 * 24 * 7); // = one week
+
+/// How long tickets issued by [`TicketEP`] are valid.
+const TICKET_VALIDITY: std::time::Duration = std::time::Duration::from_secs(3600 * 24); // = one day
+
 impl App {
     pub(super) async fn handle_hub_ticket(
         app: Rc<Self>,
@@ -31,10 +44,18 @@ impl App {
                 }
             })?;
 
-        let Some(hub) = app.shared.hubs.get(&req.handle) else {
+        let hubs = app.shared.hubs();
+
+        let Some(hub) = hubs.get(&req.handle).cloned() else {
             return Ok(TicketResp::UnknownHub);
         };
 
+        if hubs.suspended.contains(&hub.id) {
+            return Ok(TicketResp::HubSuspended);
+        }
+
+        drop(hubs);
+
         let resp = app
             .client
             .query::<api::hub::InfoEP>(&hub.url, NoPayload)
@@ -68,14 +89,79 @@ impl App {
         })?;
 
         // if so, hand out ticket
-        Ok(TicketResp::Success(api::Signed::new(
-            &app.shared.signing_key,
+        let ticket = api::Signed::new(
+            app.shared.active_signing_key(),
             &TicketContent {
                 handle: req.handle,
                 verifying_key,
             },
-            std::time::Duration::from_secs(3600 * 24), /* = one day */
-        )?))
+            TICKET_VALIDITY,
+        )?;
+
+        app.record_issued_ticket(hub.id, &ticket).await?;
+
+        // If the hub was suspended while we recorded the ticket, the ticket might not have been
+        // revoked, so we must not hand it out.
+        if app.shared.hubs().suspended.contains(&hub.id) {
+            return Ok(TicketResp::HubSuspended);
+        }
+
+        Ok(TicketResp::Success(ticket))
+    }
+
+    /// Adds the given ticket to the [`IssuedTickets`] of the hub with the given id, so that it
+    /// can be revoked when the hub is suspended.
+    ///
+    /// Returns [`api::ErrorCode::PleaseRetry`] when the issued tickets were changed concurrently.
+    async fn record_issued_ticket(&self, hub_id: Id, ticket: &Ticket) -> api::Result<()> {
+        let (mut issued_tickets, version) = match self.get_object::<IssuedTickets>(&hub_id).await? {
+            Some((issued_tickets, version)) => (issued_tickets, Some(version)),
+            None => (
+                IssuedTickets {
+                    hub: hub_id,
+                    tickets: Default::default(),
+                },
+                None,
+            ),
+        };
+
+        let now = api::NumericDate::now();
+
+        issued_tickets
+            .tickets
+            .retain(|record| record.not_valid_after >= now);
+
+        issued_tickets.tickets.push(TicketRecord {
+            digest: phcrypto::ticket_digest(ticket),
+            not_valid_after: now.add_clamp(TICKET_VALIDITY.as_secs()),
+        });
+
+        if self
+            .put_object::<IssuedTickets>(&issued_tickets, version)
+            .await?
+            .is_none()
+        {
+            log::debug!("issued tickets of hub {hub_id} changed while recording a ticket");
+            return Err(api::ErrorCode::PleaseRetry);
+        }
+
+        Ok(())
+    }
+
+    /// Implements [`RevocationsEP`]
+    pub(super) async fn handle_hub_revocations(app: Rc<Self>) -> api::Result<RevocationsResp> {
+        let hubs = app.shared.hubs();
+        let now = api::NumericDate::now();
+
+        Ok(RevocationsResp {
+            suspended_hubs: hubs.suspended.iter().copied().collect(),
+            revoked_tickets: hubs
+                .revoked_tickets
+                .iter()
+                .filter(|record| record.not_valid_after >= now)
+                .map(|record| record.digest)
+                .collect(),
+        })
     }
 
     /// Implements [`api::server::HubPingEP`].
@@ -83,6 +169,184 @@ impl App {
         app: Rc<Self>,
         signed_req: web::Json<TicketSigned<api::server::PingReq>>,
     ) -> api::Result<api::server::PingResp> {
+        let revoked = app
+            .shared
+            .hubs()
+            .revoked_tickets
+            .iter()
+            .any(|record| record.digest == phcrypto::ticket_digest(&signed_req.ticket));
+
+        if revoked {
+            return Ok(api::server::PingResp::RetryWithNewTicket);
+        }
+
         crate::servers::AppBase::<Server>::handle_hub_ping(app, signed_req).await
     }
+
+    /// Implements [`OnboardEP`]
+    pub(super) async fn handle_hub_onboard(
+        app: Rc<Self>,
+        signed_req: web::Json<api::Signed<OnboardReq>>,
+    ) -> api::Result<OnboardResp> {
+        let signed_req = signed_req.into_inner();
+
+        let req = signed_req
+            .clone()
+            .open_without_checking_signature()
+            .map_err(|oe| {
+                log::debug!("received invalid onboarding request: {oe}");
+
+                match oe {
+                    OpenError::OtherConstellation(..)
+                    | OpenError::InternalError
+                    | OpenError::InvalidSignature => api::ErrorCode::InternalError,
+                    OpenError::OtherwiseInvalid | OpenError::Expired => api::ErrorCode::BadRequest,
+                }
+            })?;
+
+        let Ok(hub_verifying_key) = req.verifying_key.decode() else {
+            log::debug!(
+                "onboarding request for {} has a malformed verifying key",
+                req.handle
+            );
+            return Err(api::ErrorCode::BadRequest);
+        };
+
+        // check that the request was signed using the submitted key
+        signed_req.open(&hub_verifying_key, None).map_err(|oe| {
+            log::debug!(
+                "could not verify signature on onboarding request for {}: {oe}",
+                req.handle
+            );
+
+            match oe {
+                OpenError::OtherConstellation(..) | OpenError::InternalError => {
+                    api::ErrorCode::InternalError
+                }
+                OpenError::OtherwiseInvalid | OpenError::Expired | OpenError::InvalidSignature => {
+                    api::ErrorCode::BadRequest
+                }
+            }
+        })?;
+
+        if !req.url.as_str().ends_with('/')
+            || !(app.allow_insecure_onboarding_urls || is_public_https_url(&req.url))
+        {
+            return Ok(OnboardResp::InvalidUrl);
+        }
+
+        // Check the registry before contacting the hub, so that requests that will be refused
+        // anyway do not make us send requests to arbitrary urls.
+        let (mut registry, version, _) = app.get_hub_registry().await?;
+
+        if registry.handle_taken(&req.handle) {
+            return Ok(OnboardResp::HandleTaken);
+        }
+
+        let now = api::NumericDate::now();
+
+        registry.pending.retain(|pending| {
+            pending
+                .requested_at
+                .add_clamp(PENDING_HUB_VALIDITY.as_secs())
+                >= now
+        });
+
+        let previous = registry.pending_by_handle(&req.handle);
+
+        if let Some(i) = previous
+            && registry.pending[i].verifying_key != req.verifying_key
+        {
+            return Ok(OnboardResp::HandleTaken);
+        }
+
+        // at most one pending request per host
+        let host_busy = registry.pending.iter().enumerate().any(|(i, pending)| {
+            Some(i) != previous && pending.info.url.host_str() == req.url.host_str()
+        });
+
+        if host_busy || (previous.is_none() && registry.pending.len() >= MAX_PENDING_HUBS) {
+            log::warn!(
+                "refused onboarding request for {}: too many pending hubs",
+                req.handle
+            );
+            return Ok(OnboardResp::QueueFull);
+        }
+
+        // check that the hub at the given url uses the submitted key
+        let advertised_key = match app
+            .client
+            .query::<api::hub::InfoEP>(&req.url, NoPayload)
+            .quiet()
+            .await
+        {
+            Ok(info) => info.verifying_key,
+            Err(err) => {
+                log::debug!(
+                    "could not reach hub info endpoint at {} for onboarding request for {}: {err}",
+                    req.url,
+                    req.handle
+                );
+                None
+            }
+        };
+
+        if advertised_key.as_ref() != Some(&req.verifying_key) {
+            return Ok(OnboardResp::VerifyingKeyMismatch);
+        }
+
+        let pending_hub = api::admin::PendingHub {
+            info: crate::hub::BasicInfo {
+                handles: vec![req.handle.clone()].into(),
+                name: req.name.clone(),
+                description: req.description.clone(),
+                url: req.url.clone(),
+                id: previous.map_or_else(Id::random, |i| registry.pending[i].info.id),
+            },
+            verifying_key: req.verifying_key.clone(),
+            requested_at: now,
+        };
+
+        match previous {
+            Some(i) => registry.pending[i] = pending_hub,
+            None => registry.pending.push(pending_hub),
+        }
+
+        app.put_hub_registry(registry, version).await?;
+
+        log::info!("hub {} asks to be registered", req.handle);
+
+        Ok(OnboardResp::Pending)
+    }
+}
+
+/// Whether `url` is an `https` url whose host is not obviously internal, such as `localhost`
+/// or a private ip address.  PubHubs Central contacts the urls submitted via [`OnboardEP`], and
+/// should not be used to probe the network it runs in.
+///
+/// Note that a domain name may still resolve to an internal address.
+fn is_public_https_url(url: &url::Url) -> bool {
+    if url.scheme() != "https" {
+        return false;
+    }
+
+    match url.host() {
+        None => false,
+        Some(url::Host::Domain(domain)) => domain != "localhost" && !domain.ends_with(".localhost"),
+        Some(url::Host::Ipv4(ip)) => {
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation())
+        }
+        Some(url::Host::Ipv6(ip)) => {
+            ip.to_ipv4_mapped().is_none()
+                && !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+        }
+    }
 }
@@ -0,0 +1,282 @@
+//! The registry of hubs known to PubHubs Central, see [`HubRegistry`].
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::api::{self, ApiResultExt as _};
+use crate::handle::Handle;
+use crate::hub;
+use crate::id::Id;
+use crate::map::Map;
+
+use super::server::*;
+
+/// Identifies the one [`HubRegistry`] in PHC's object store.
+pub(crate) const HUB_REGISTRY_ID: &str = "current";
+
+/// All hubs ever registered at PubHubs Central, stored in its object store.
+///
+/// Hubs listed in PHC's configuration are added to the registry when not already present in it
+/// (by id), so once a hub is in the registry, changes to its configuration entry have no effect.
+/// Use the `.ph/admin/hubs/...` endpoints (e.g. [`api::admin::UpdateHubEP`]) instead.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+pub struct HubRegistry {
+    /// Increased by one with each change to the registry
+    pub generation: u64,
+
+    /// The hubs offered to users
+    pub active: Vec<hub::BasicInfo>,
+
+    /// Retired hubs, kept so that their handles and ids are not reused
+    pub retired: Vec<hub::BasicInfo>,
+
+    /// Hubs awaiting approval, oldest request first; see [`api::phc::hub::OnboardEP`].
+    #[serde(default)]
+    pub pending: Vec<api::admin::PendingHub>,
+
+    /// Ids of suspended hubs, see [`api::admin::SuspendHubEP`]
+    #[serde(default)]
+    pub suspended: Vec<Id>,
+
+    /// Tickets revoked because their hub was suspended; removed once expired.
+    #[serde(default)]
+    pub revoked_tickets: Vec<TicketRecord>,
+}
+
+/// Identifies a ticket issued by PHC, see [`api::phc::hub::TicketEP`].
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TicketRecord {
+    /// See [`crate::phcrypto::ticket_digest`]
+    pub digest: Id,
+
+    /// When the ticket expires
+    pub not_valid_after: api::NumericDate,
+}
+
+/// The unexpired tickets issued to a hub, kept in PHC's object store so that they can be revoked
+/// when the hub is suspended.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct IssuedTickets {
+    /// The [`Id`] of the hub the tickets were issued to
+    pub hub: Id,
+
+    pub tickets: Vec<TicketRecord>,
+}
+
+impl HubRegistry {
+    /// Returns the hub with the given handle, if any; retired hubs are included when `retired` is
+    /// set.
+    pub fn hub_by_handle(&self, handle: &Handle, retired: bool) -> Option<&hub::BasicInfo> {
+        let retired: &[hub::BasicInfo] = if retired { &self.retired } else { &[] };
+
+        self.active
+            .iter()
+            .chain(retired)
+            .find(|hub| hub.handles.contains(handle))
+    }
+
+    /// Returns whether some hub, possibly retired, has this handle.
+    pub fn handle_taken(&self, handle: &Handle) -> bool {
+        self.hub_by_handle(handle, true).is_some()
+    }
+
+    /// Returns the position in [`HubRegistry::pending`] of the pending hub with the given handle.
+    pub fn pending_by_handle(&self, handle: &Handle) -> Option<usize> {
+        self.pending
+            .iter()
+            .position(|pending_hub| pending_hub.info.handles.contains(handle))
+    }
+
+    /// Returns whether some hub, possibly retired, has this id.
+    pub fn id_taken(&self, id: &Id) -> bool {
+        self.active
+            .iter()
+            .chain(self.retired.iter())
+            .any(|hub| hub.id == *id)
+    }
+
+    /// Adds the given hubs (from PHC's configuration) that are not already in the registry.
+    /// Returns whether any hub was added.
+    fn merge_configured(&mut self, configured_hubs: &[hub::BasicInfo]) -> bool {
+        let mut changed = false;
+
+        'hubs: for hub in configured_hubs {
+            if self.id_taken(&hub.id) {
+                continue;
+            }
+
+            for handle in hub.handles.iter() {
+                if self.handle_taken(handle) {
+                    log::warn!(
+                        "not adding configured hub {} to the hub registry, \
+                        because its handle {handle} is already taken",
+                        hub.id
+                    );
+                    continue 'hubs;
+                }
+            }
+
+            log::info!(
+                "adding configured hub {} to the hub registry",
+                hub.handles.preferred()
+            );
+            self.active.push(hub.clone());
+            changed = true;
+        }
+
+        changed
+    }
+
+    /// Returns a [`Hubs`] snapshot of the active hubs.
+    fn to_hubs(&self) -> Hubs {
+        let mut map: Map<hub::BasicInfo> = Default::default();
+
+        for hub in self.active.iter() {
+            if let Some(handle_or_id) = map.insert_new(hub.clone()) {
+                // should have been prevented when the hub was added
+                log::error!("hub registry contains two hubs known as {handle_or_id}");
+            }
+        }
+
+        Hubs {
+            generation: self.generation,
+            map,
+            suspended: self.suspended.iter().copied().collect(),
+            revoked_tickets: self.revoked_tickets.clone(),
+        }
+    }
+}
+
+/// Snapshot of the active hubs in the [`HubRegistry`], shared by all apps via
+/// [`ExtraSharedState`].
+pub struct Hubs {
+    /// The [`HubRegistry::generation`] this snapshot was taken from
+    pub generation: u64,
+    pub map: Map<hub::BasicInfo>,
+
+    /// See [`HubRegistry::suspended`]
+    pub suspended: HashSet<Id>,
+
+    /// See [`HubRegistry::revoked_tickets`]
+    pub revoked_tickets: Vec<TicketRecord>,
+}
+
+impl std::ops::Deref for Hubs {
+    type Target = Map<hub::BasicInfo>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+impl ExtraSharedState {
+    pub(super) fn new(configured_hubs: Vec<hub::BasicInfo>) -> anyhow::Result<Self> {
+        let mut map: Map<hub::BasicInfo> = Default::default();
+
+        for hub in configured_hubs.iter() {
+            if let Some(handle_or_id) = map.insert_new(hub.clone()) {
+                anyhow::bail!("two hubs are known as {handle_or_id}");
+            }
+        }
+
+        // Until the hub registry has been loaded from the object store, we use the configured
+        // hubs.  Generation 0 makes sure any loaded registry supersedes them.
+        Ok(Self {
+            hubs: tokio::sync::watch::Sender::new(Arc::new(Hubs {
+                generation: 0,
+                map,
+                suspended: Default::default(),
+                revoked_tickets: Default::default(),
+            })),
+            configured_hubs,
+        })
+    }
+
+    /// Returns the current snapshot of the active hubs.
+    pub fn hubs(&self) -> Arc<Hubs> {
+        self.hubs.borrow().clone()
+    }
+
+    /// Returns a receiver that's notified when the active hubs change.
+    pub(super) fn subscribe_to_hubs(&self) -> tokio::sync::watch::Receiver<Arc<Hubs>> {
+        self.hubs.subscribe()
+    }
+
+    /// Replaces the current snapshot of the active hubs, unless it is not older than `hubs`,
+    /// which might happen when two apps change the registry concurrently.
+    fn publish_hubs(&self, hubs: Hubs) {
+        self.hubs.send_if_modified(|current| {
+            if current.generation >= hubs.generation {
+                return false;
+            }
+
+            log::debug!(
+                "publishing generation {} of the hub registry",
+                hubs.generation
+            );
+            *current = Arc::new(hubs);
+            true
+        });
+    }
+}
+
+impl App {
+    /// Retrieves the [`HubRegistry`] from the object store, adding the configured hubs not yet
+    /// present.  Returns the version to pass to [`App::put_hub_registry`], which is `None` when
+    /// there was no registry in the object store yet, and whether configured hubs were added.
+    pub(super) async fn get_hub_registry(
+        &self,
+    ) -> api::Result<(HubRegistry, Option<object_store::UpdateVersion>, bool)> {
+        let (mut registry, version) = match self.get_object::<HubRegistry>(&HUB_REGISTRY_ID).await?
+        {
+            Some((registry, version)) => (registry, Some(version)),
+            None => (HubRegistry::default(), None),
+        };
+
+        let merged = registry.merge_configured(&self.shared.configured_hubs);
+
+        Ok((registry, version, merged))
+    }
+
+    /// Stores the changed `registry` in the object store under the given `version`, and makes its
+    /// active hubs available to all apps.
+    ///
+    /// Returns [`api::ErrorCode::PleaseRetry`] when the registry was changed concurrently.
+    pub(super) async fn put_hub_registry(
+        &self,
+        mut registry: HubRegistry,
+        version: Option<object_store::UpdateVersion>,
+    ) -> api::Result<()> {
+        registry.generation += 1;
+
+        if self
+            .put_object::<HubRegistry>(&registry, version)
+            .await?
+            .is_none()
+        {
+            log::debug!("hub registry changed while updating it");
+            return Err(api::ErrorCode::PleaseRetry);
+        }
+
+        self.shared.publish_hubs(registry.to_hubs());
+
+        Ok(())
+    }
+
+    /// Loads the [`HubRegistry`] from the object store (creating or completing it when needed),
+    /// and makes its active hubs available to all apps.
+    pub(super) async fn load_hub_registry(&self) -> anyhow::Result<()> {
+        crate::misc::task::retry(|| async {
+            let (registry, version, merged) = self.get_hub_registry().await?;
+
+            if version.is_none() || merged {
+                return self.put_hub_registry(registry, version).await.retryable();
+            }
+
+            self.shared.publish_hubs(registry.to_hubs());
+
+            Ok(Some(()))
+        })
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("timeout loading the hub registry"))
+    }
+}
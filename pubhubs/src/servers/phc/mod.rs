@@ -1,10 +1,16 @@
 //! Server: PubHubs Central
+mod admin;
 mod hub;
+mod hub_registry;
+mod rekey;
 mod server;
 mod user;
+mod user_account;
 mod user_card;
 mod user_object_store;
 mod user_sso;
 
+pub(crate) use admin::BanLog;
+pub(crate) use hub_registry::{HUB_REGISTRY_ID, HubRegistry, IssuedTickets};
 pub use server::{Details, HubCacheConfig, Server};
 pub(crate) use user::UserState;
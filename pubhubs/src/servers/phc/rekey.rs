@@ -0,0 +1,142 @@
+//! Re-keying of polymorphic pseudonyms during the rotation of the master encryption key
+use std::rc::Rc;
+
+use curve25519_dalek::Scalar;
+
+use crate::api;
+use crate::common::elgamal;
+use crate::phcrypto;
+
+use super::server::*;
+use super::user::UserState;
+
+/// How long to wait before sweeping the user states again, when some could not be re-keyed.
+const SWEEP_RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// How many user ids to list from the object store at a time while sweeping.
+const SWEEP_PAGE_SIZE: usize = 1000;
+
+/// What PHC needs to move the polymorphic pseudonyms from the previous master encryption keys to
+/// the current one, while one or both master encryption key parts are being rotated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MasterEncRekey {
+    /// The previous master encryption keys `x' B` a polymorphic pseudonym might still be encrypted
+    /// for, each with the factor `x / x'` to re-key with, see
+    /// [`phcrypto::rekey_polymorphic_pseudonym`].
+    ///
+    /// When both parts are being rotated, these are the combinations of a previous part with the
+    /// previous or current other part, because the rotation of one part might have re-keyed a
+    /// polymorphic pseudonym before the rotation of the other part started.
+    pub previous_master_enc_keys: Vec<(elgamal::PublicKey, Scalar)>,
+}
+
+impl MasterEncRekey {
+    /// Re-keys `pp` to the current master encryption key when it is encrypted for one of the
+    /// previous ones.  Returns whether it did.
+    pub fn rekey(&self, pp: &mut elgamal::Triple) -> bool {
+        let pk = pp.pk();
+
+        let Some((_, factor)) = self
+            .previous_master_enc_keys
+            .iter()
+            .find(|(previous_master_enc_key, _)| *previous_master_enc_key == pk)
+        else {
+            return false;
+        };
+
+        *pp = phcrypto::rekey_polymorphic_pseudonym(pp.clone(), factor);
+
+        true
+    }
+}
+
+impl App {
+    /// Re-keys the polymorphic pseudonyms of all users to the current master encryption key,
+    /// sweeping the user states again until none are left.  Does nothing when no master encryption
+    /// key part is being rotated.
+    pub(super) async fn rekey_sweep(app: Rc<Self>) {
+        let Some(rekey) = app
+            .running_state
+            .as_ref()
+            .and_then(|rs| rs.master_enc_rekey.clone())
+        else {
+            return;
+        };
+
+        log::info!("re-keying polymorphic pseudonyms to the current master encryption key");
+
+        loop {
+            match app.rekey_sweep_once(&rekey).await {
+                Ok(0) => {
+                    log::info!(
+                        "all polymorphic pseudonyms have been re-keyed to the current master \
+                        encryption key;  the previous master encryption key parts can now be \
+                        removed from the configuration of PHC and the transcryptor"
+                    );
+                    return;
+                }
+                Ok(remaining) => {
+                    log::info!(
+                        "{remaining} polymorphic pseudonym(s) changed while being re-keyed; \
+                        retrying in {SWEEP_RETRY_INTERVAL:?}"
+                    );
+                }
+                Err(err) => {
+                    log::warn!(
+                        "re-keying polymorphic pseudonyms failed: {err:?}; \
+                        retrying in {SWEEP_RETRY_INTERVAL:?}"
+                    );
+                }
+            }
+
+            tokio::time::sleep(SWEEP_RETRY_INTERVAL).await;
+        }
+    }
+
+    /// Re-keys the polymorphic pseudonyms of all users once.  Returns the number of user states
+    /// that were changed concurrently, and thus still need to be re-keyed.
+    async fn rekey_sweep_once(&self, rekey: &MasterEncRekey) -> api::Result<usize> {
+        let mut rekeyed: usize = 0;
+        let mut remaining: usize = 0;
+
+        let mut after: Option<crate::id::Id> = None;
+
+        loop {
+            let user_ids = self
+                .list_object_ids::<UserState>(after.as_ref(), SWEEP_PAGE_SIZE)
+                .await?;
+
+            let Some(last) = user_ids.last() else {
+                break;
+            };
+            after = Some(*last);
+
+            for user_id in user_ids {
+                let Some((mut user_state, version)) =
+                    self.get_object::<UserState>(&user_id).await?
+                else {
+                    continue; // removed in the meantime
+                };
+
+                if !rekey.rekey(&mut user_state.polymorphic_pseudonym) {
+                    continue;
+                }
+
+                if self
+                    .put_object::<UserState>(&user_state, Some(version))
+                    .await?
+                    .is_none()
+                {
+                    remaining += 1;
+                    continue;
+                }
+
+                rekeyed += 1;
+            }
+        }
+
+        log::info!("re-keyed {rekeyed} polymorphic pseudonym(s)");
+
+        Ok(remaining)
+    }
+}
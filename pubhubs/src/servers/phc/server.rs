@@ -1,13 +1,17 @@
 //! Basic server [`Details`]: [`Server`], [`App`], etc.
 use std::cell::{Cell, RefCell};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::Infallible;
 use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
+use std::sync::Arc;
 
 use actix_web::web;
+use curve25519_dalek::Scalar;
 use sha2::digest::Digest as _;
 
+use super::hub_registry::Hubs;
+use super::rekey::MasterEncRekey;
 use crate::api::{self, ApiResultExt as _, EndpointDetails as _, NoPayload};
 use crate::client;
 use crate::common::secret::DigestibleSecret as _;
@@ -24,6 +28,7 @@ use crate::servers::{
 };
 
 use crate::{
+    attr,
     common::{elgamal, kem},
     hub,
 };
@@ -54,22 +59,30 @@ impl servers::Details for Details {
             attr_signing_key: phcrypto::attr_signing_key(&auths_ss),
             t_sealing_secret: phcrypto::sealing_secret(&t_ss),
             auths_sealing_secret: phcrypto::sealing_secret(&auths_ss),
+            co_transcryptor_sealing_secrets: seed
+                .co_transcryptor_ss
+                .iter()
+                .map(phcrypto::sealing_secret)
+                .collect(),
             master_enc_key: seed.master_enc_key.clone(),
+            master_enc_rekey: seed.master_enc_rekey.clone(),
             t_ss,
             auths_ss,
+            co_transcryptor_ss: seed.co_transcryptor_ss.clone(),
         })
     }
 
     fn create_extra_shared_state(config: &servers::Config) -> anyhow::Result<ExtraSharedState> {
-        let mut hubs: crate::map::Map<hub::BasicInfo> = Default::default();
-
-        for basic_hub_info in config.phc.as_ref().unwrap().hubs.iter() {
-            if let Some(hub_or_id) = hubs.insert_new(basic_hub_info.clone().into()) {
-                anyhow::bail!("two hubs are known as {hub_or_id}");
-            }
-        }
-
-        Ok(ExtraSharedState { hubs })
+        ExtraSharedState::new(
+            config
+                .phc
+                .as_ref()
+                .unwrap()
+                .hubs
+                .iter()
+                .map(|basic_hub_info| basic_hub_info.clone().into())
+                .collect(),
+        )
     }
 
     fn create_extra_server_state(_config: &servers::Config) -> anyhow::Result<()> {
@@ -78,16 +91,28 @@ impl servers::Details for Details {
 }
 
 pub struct ExtraSharedState {
-    /// Immutable hub registry, built once from config and shared by all apps.
-    pub hubs: crate::map::Map<hub::BasicInfo>,
+    /// The active hubs from the [`HubRegistry`], shared by all apps; see [`ExtraSharedState::hubs`].
+    ///
+    /// [`HubRegistry`]: super::hub_registry::HubRegistry
+    pub(super) hubs: tokio::sync::watch::Sender<Arc<Hubs>>,
+
+    /// The hubs from the configuration, added to the [`HubRegistry`] when not already present.
+    ///
+    /// [`HubRegistry`]: super::hub_registry::HubRegistry
+    pub(super) configured_hubs: Vec<hub::BasicInfo>,
 }
 
 pub struct App {
     pub base: AppBase<Server>,
     pub transcryptor_url: url::Url,
+    pub co_transcryptor_urls: Vec<url::Url>,
     pub auths_url: url::Url,
     pub global_client_url: url::Url,
     pub master_enc_key_part: elgamal::PrivateKey,
+
+    /// Set while the master encryption key part is being rotated
+    pub previous_master_enc_key_part: Option<elgamal::PrivateKey>,
+
     pub attr_id_secret: Box<[u8]>,
     pub auth_token_secret: crypto::SealingKey,
     pub auth_token_validity: core::time::Duration,
@@ -96,14 +121,28 @@ pub struct App {
     pub user_object_hmac_secret: Box<[u8]>,
     pub quota: api::phc::user::Quota,
     pub card_pseud_validity: core::time::Duration,
+    pub bannable_sources: Option<HashSet<attr::Source>>,
+    pub allow_insecure_onboarding_urls: bool,
 
     /// channel for sending messages between apps
     pub broadcast: tokio::sync::broadcast::Sender<InterAppMsg>,
 
     pub cached_hub_info: std::cell::RefCell<api::CachedResponse<api::phc::user::CachedHubInfoEP>>,
+
+    /// The response to [`api::phc::user::WelcomeEP`] together with the [`Hubs::generation`] it
+    /// was computed for
+    pub cached_welcome:
+        std::cell::RefCell<Option<(u64, api::CachedResponse<api::phc::user::WelcomeEP>)>>,
+
+    /// The preferred handles of the attribute types known to the authentication server, together
+    /// with when they were retrieved, see [`App::attr_type_handles`].
+    pub attr_type_handles: RefCell<Option<(std::time::Instant, AttrTypeHandles)>>,
     pub hub_cache_config: HubCacheConfig,
 }
 
+/// Preferred handles of attribute types by their id, see [`App::attr_type_handles`].
+pub type AttrTypeHandles = Rc<HashMap<id::Id, handle::Handle>>;
+
 impl Deref for App {
     type Target = AppBase<Server>;
 
@@ -120,6 +159,10 @@ pub struct ExtraRunningState {
     /// Hybrid post-quantum shared secret with the authentication server.
     pub(super) auths_ss: kem::SharedSecret,
 
+    /// Hybrid post-quantum shared secrets with the co-transcryptors, in the order of
+    /// [`constellation::Inner::co_transcryptors`].
+    pub(super) co_transcryptor_ss: Vec<kem::SharedSecret>,
+
     /// Key used to sign [`Attr`]s, shared with the authentication server
     ///
     /// [`Attr`]: crate::attr::Attr
@@ -132,11 +175,28 @@ pub struct ExtraRunningState {
     #[expect(dead_code)]
     pub(super) auths_sealing_secret: crypto::SealingKey,
 
+    /// Keys used to (un)seal messages to and from the co-transcryptors
+    pub(super) co_transcryptor_sealing_secrets: Vec<crypto::SealingKey>,
+
     /// The master encryption key `x_T x_PHC B`, derived from the transcryptor's sealed master key
     /// part.  `None` until PHC has been able to unseal it (e.g. while the transcryptor has not yet
     /// published a sealed part).  Held here because it is no longer part of the (public)
     /// constellation.
     pub(super) master_enc_key: Option<elgamal::PublicKey>,
+
+    /// Set while one or both of the master encryption key parts are being rotated, and PHC has
+    /// been able to derive the previous master encryption key.
+    pub(super) master_enc_rekey: Option<MasterEncRekey>,
+}
+
+impl ExtraRunningState {
+    /// Key used to open [`api::sso::EncryptedHubPseudonymPackage`]s, which are sealed by the last
+    /// co-transcryptor when the transcryptor role is split.
+    pub(super) fn ehpp_sealing_secret(&self) -> &crypto::SealingKey {
+        self.co_transcryptor_sealing_secrets
+            .last()
+            .unwrap_or(&self.t_sealing_secret)
+    }
 }
 
 /// Data threaded out of PHC's [`discover`](crate::servers::App::discover) into
@@ -146,21 +206,34 @@ pub struct ExtraRunningState {
 pub struct RunningStateSeed {
     pub(super) t_ss: kem::SharedSecret,
     pub(super) auths_ss: kem::SharedSecret,
+    pub(super) co_transcryptor_ss: Vec<kem::SharedSecret>,
     pub(super) master_enc_key: Option<elgamal::PublicKey>,
+    pub(super) master_enc_rekey: Option<MasterEncRekey>,
 }
 
 impl crate::servers::App<Server> for App {
     fn configure_actix_app(self: &Rc<Self>, sc: &mut web::ServiceConfig) {
         api::phc::hub::TicketEP::add_to(self, sc, App::handle_hub_ticket);
+        api::phc::hub::OnboardEP::add_to(self, sc, App::handle_hub_onboard);
+        api::phc::hub::RevocationsEP::add_to(self, sc, App::handle_hub_revocations);
         api::server::HubPingEP::add_to(self, sc, App::handle_hub_ping);
 
-        api::phc::user::WelcomeEP::caching_add_to(self, sc, App::cached_handle_user_welcome);
         api::phc::user::EnterEP::add_to(self, sc, App::handle_user_enter);
         api::phc::user::RefreshEP::add_to(self, sc, App::handle_user_refresh);
         api::phc::user::StateEP::add_to(self, sc, App::handle_user_state);
+        api::phc::user::MergeEP::add_to(self, sc, App::handle_user_merge);
+        api::phc::user::RemoveIdentifyingAttrEP::add_to(
+            self,
+            sc,
+            App::handle_user_remove_identifying_attr,
+        );
+        api::phc::user::ExportEP::add_to(self, sc, App::handle_user_export);
+        api::phc::user::DeleteEP::add_to(self, sc, App::handle_user_delete);
 
         api::phc::user::NewObjectEP::add_to(self, sc, App::handle_user_new_object);
         api::phc::user::OverwriteObjectEP::add_to(self, sc, App::handle_user_overwrite_object);
+        api::phc::user::DeleteObjectEP::add_to(self, sc, App::handle_user_delete_object);
+        api::phc::user::SetObjectHistoryEP::add_to(self, sc, App::handle_user_set_object_history);
         api::phc::user::GetObjectEP::add_to(self, sc, App::handle_user_get_object);
 
         api::phc::user::PppEP::add_to(self, sc, App::handle_user_ppp);
@@ -168,11 +241,31 @@ impl crate::servers::App<Server> for App {
 
         api::phc::user::CardPseudEP::add_to(self, sc, App::handle_user_card_pseud);
 
-        // We add the following endpoint manually, for efficiency
-        sc.app_data(web::Data::new(self.clone())).route(
-            api::phc::user::CachedHubInfoEP::PATH,
-            web::method(api::phc::user::CachedHubInfoEP::METHOD).to(App::handle_cached_hub_info),
-        );
+        api::admin::BanEP::add_to(self, sc, App::handle_admin_ban);
+        api::admin::UnbanEP::add_to(self, sc, App::handle_admin_unban);
+        api::admin::BanLogEP::add_to(self, sc, App::handle_admin_ban_log);
+        api::admin::RevokeCardsEP::add_to(self, sc, App::handle_admin_revoke_cards);
+
+        api::admin::AddHubEP::add_to(self, sc, App::handle_admin_add_hub);
+        api::admin::UpdateHubEP::add_to(self, sc, App::handle_admin_update_hub);
+        api::admin::RetireHubEP::add_to(self, sc, App::handle_admin_retire_hub);
+        api::admin::PendingHubsEP::add_to(self, sc, App::handle_admin_pending_hubs);
+        api::admin::ApproveHubEP::add_to(self, sc, App::handle_admin_approve_hub);
+        api::admin::RejectHubEP::add_to(self, sc, App::handle_admin_reject_hub);
+        api::admin::SuspendHubEP::add_to(self, sc, App::handle_admin_suspend_hub);
+        api::admin::UnsuspendHubEP::add_to(self, sc, App::handle_admin_unsuspend_hub);
+
+        // We add the following endpoints manually, for efficiency
+        sc.app_data(web::Data::new(self.clone()))
+            .route(
+                api::phc::user::WelcomeEP::PATH,
+                web::method(api::phc::user::WelcomeEP::METHOD).to(App::handle_user_welcome),
+            )
+            .route(
+                api::phc::user::CachedHubInfoEP::PATH,
+                web::method(api::phc::user::CachedHubInfoEP::METHOD)
+                    .to(App::handle_cached_hub_info),
+            );
     }
 
     fn check_constellation(&self, _constellation: &Constellation) -> bool {
@@ -183,18 +276,30 @@ impl crate::servers::App<Server> for App {
         self: &Rc<Self>,
         _phc_di: api::DiscoveryInfoResp,
     ) -> api::Result<DiscoverVerdict<RunningStateSeed>> {
-        let (tdi_res, asdi_res) = tokio::join!(
+        let (tdi_res, asdi_res, cotdi_res) = tokio::join!(
             self.discovery_info_of(servers::Name::Transcryptor, &self.transcryptor_url),
-            self.discovery_info_of(servers::Name::AuthenticationServer, &self.auths_url)
+            self.discovery_info_of(servers::Name::AuthenticationServer, &self.auths_url),
+            futures::future::join_all(
+                self.co_transcryptor_urls
+                    .iter()
+                    .map(|url| self.discovery_info_of(servers::Name::Transcryptor, url))
+            )
         );
 
         let tdi = tdi_res?;
         let asdi = asdi_res?;
+        let cotdis = cotdi_res.into_iter().collect::<api::Result<Vec<_>>>()?;
 
         for (odi, other_server_name) in [
             (&tdi, servers::Name::Transcryptor),
             (&asdi, servers::Name::AuthenticationServer),
-        ] {
+        ]
+        .into_iter()
+        .chain(
+            cotdis
+                .iter()
+                .map(|cotdi| (cotdi, servers::Name::Transcryptor)),
+        ) {
             if let Some(ref other_version) = odi.version
                 && let Some(my_version) = &self.version
             {
@@ -231,6 +336,21 @@ impl crate::servers::App<Server> for App {
             }
         }
 
+        if !cotdis.is_empty()
+            && (self.previous_master_enc_key_part.is_some()
+                || std::iter::once(&tdi)
+                    .chain(&cotdis)
+                    .any(|di| di.previous_master_enc_key_part_hash.is_some()))
+        {
+            log::error!(
+                "master encryption key parts can not be rotated while the transcryptor role is \
+                 split between the transcryptor and co-transcryptors: unset \
+                 `previous_master_enc_key_part` in the configurations of PHC, the transcryptor \
+                 and every co-transcryptor"
+            );
+            return Err(api::ErrorCode::InternalError);
+        }
+
         let current_rs = self.running_state.as_ref();
 
         let (transcryptor_encap_key_id, transcryptor_ss_encap, t_ss) = Self::encap_or_reuse(
@@ -257,6 +377,47 @@ impl crate::servers::App<Server> for App {
             }),
         )?;
 
+        let mut co_transcryptors: Vec<constellation::CoTranscryptor> = Vec::new();
+        let mut co_transcryptor_ss: Vec<kem::SharedSecret> = Vec::new();
+
+        for (i, (cotdi, url)) in cotdis.iter().zip(&self.co_transcryptor_urls).enumerate() {
+            let encap_key = cotdi.encap_key.clone().ok_or_else(|| {
+                log::error!("co-transcryptor at {url}'s discovery info has no encapsulation key");
+                api::ErrorCode::InternalError
+            })?;
+
+            let prior = current_rs.and_then(|rs| {
+                Some((
+                    rs.constellation.co_transcryptors.get(i)?,
+                    rs.co_transcryptor_ss.get(i)?,
+                ))
+            });
+            let prior_encap_key_id = prior.map(|(ct, _)| ct.encap_key.id());
+
+            let (_, ss_encap, ss) = Self::encap_or_reuse(
+                servers::Name::Transcryptor,
+                Some(&encap_key),
+                prior
+                    .zip(prior_encap_key_id.as_ref())
+                    .map(|((ct, ss), id)| (id, &ct.ss_encap, ss)),
+            )?;
+
+            co_transcryptors.push(constellation::CoTranscryptor {
+                url: url.clone(),
+                verifying_key: cotdi.verifying_key.clone(),
+                next_verifying_key: cotdi.next_verifying_key.clone(),
+                master_enc_key_part_hash: cotdi.master_enc_key_part_hash.ok_or_else(|| {
+                    log::error!(
+                        "co-transcryptor at {url}'s discovery info has no master_enc_key_part_hash"
+                    );
+                    api::ErrorCode::InternalError
+                })?,
+                encap_key,
+                ss_encap,
+            });
+            co_transcryptor_ss.push(ss);
+        }
+
         let new_constellation_inner = constellation::Inner {
             // the transcryptor publishes the hash of `x_T B` in its discovery info
             transcryptor_master_enc_key_part_hash: tdi.master_enc_key_part_hash.ok_or_else(
@@ -265,9 +426,14 @@ impl crate::servers::App<Server> for App {
                     api::ErrorCode::InternalError
                 },
             )?,
+            transcryptor_previous_master_enc_key_part_hash: tdi.previous_master_enc_key_part_hash,
             phc_master_enc_key_part_hash: phcrypto::master_enc_key_part_hash(
                 self.master_enc_key_part.public_key(),
             ),
+            phc_previous_master_enc_key_part_hash: self
+                .previous_master_enc_key_part
+                .as_ref()
+                .map(|part| phcrypto::master_enc_key_part_hash(part.public_key())),
             global_client_url: self.global_client_url.clone(),
             phc_url: self.phc_url.clone(),
             // PHC's ed25519 public key (the `ed` half), so pre-hybrid hubs can verify the EdDSA HHPP.
@@ -276,13 +442,17 @@ impl crate::servers::App<Server> for App {
             )
             .into(),
             phc_verifying_key: self.shared.verifying_key_bytes.clone(),
+            phc_next_verifying_key: self.shared.next_verifying_key().cloned(),
             transcryptor_url: self.transcryptor_url.clone(),
             // cloned (not moved) so `tdi` stays whole for `master_enc_key_from_sealed_part` below
             transcryptor_verifying_key: tdi.verifying_key.clone(),
+            transcryptor_next_verifying_key: tdi.next_verifying_key.clone(),
             transcryptor_encap_key_id,
             transcryptor_ss_encap,
+            co_transcryptors,
             auths_url: self.auths_url.clone(),
             auths_verifying_key: asdi.verifying_key.clone(),
+            auths_next_verifying_key: asdi.next_verifying_key.clone(),
             auths_encap_key_id,
             auths_ss_encap,
             ph_version: self.version.clone(),
@@ -290,34 +460,82 @@ impl crate::servers::App<Server> for App {
 
         let new_constellation_id = constellation::Inner::derive_id(&new_constellation_inner);
 
-        // PHC keeps the master encryption key once derived: it never changes in a real deployment
-        // (changing it would invalidate every polymorphic pseudonym).  The exception is an ephemeral
-        // local test setup that regenerates the key parts; detect that via their hashes in the
-        // constellation, warn loudly, and drop the now-stale key so it is re-derived.
+        // PHC keeps the master encryption key once derived: it only changes when one of its parts
+        // is rotated (see `previous_master_enc_key_part` in the configuration of PHC and the
+        // transcryptor), or in an ephemeral local test setup that regenerates the key parts.
+        // Detect either via the key part hashes in the constellation, and drop the now-stale key
+        // so it is re-derived.
         let prior_master_enc_key = 'prior: {
             let Some(rs) = self.running_state.as_ref() else {
                 break 'prior None;
             };
-            if rs.constellation.transcryptor_master_enc_key_part_hash
-                != new_constellation_inner.transcryptor_master_enc_key_part_hash
-                || rs.constellation.phc_master_enc_key_part_hash
-                    != new_constellation_inner.phc_master_enc_key_part_hash
+            let (old, new) = (&rs.constellation.inner, &new_constellation_inner);
+            if old.co_transcryptors.len() != new.co_transcryptors.len()
+                || std::iter::zip(&old.co_transcryptors, &new.co_transcryptors)
+                    .any(|(o, n)| o.master_enc_key_part_hash != n.master_enc_key_part_hash)
             {
                 log::warn!(
-                    "a master encryption key part changed; this invalidates all existing \
-                     polymorphic pseudonyms and should only happen in an ephemeral test setup"
+                    "the co-transcryptors or their master encryption key parts changed; this \
+                     invalidates all existing polymorphic pseudonyms and should only happen in an \
+                     ephemeral test setup"
                 );
                 break 'prior None;
             }
-            rs.master_enc_key.clone()
+            if old.transcryptor_master_enc_key_part_hash
+                != new.transcryptor_master_enc_key_part_hash
+                || old.phc_master_enc_key_part_hash != new.phc_master_enc_key_part_hash
+            {
+                if Some(old.transcryptor_master_enc_key_part_hash)
+                    == new
+                        .transcryptor_previous_master_enc_key_part_hash
+                        .or(Some(new.transcryptor_master_enc_key_part_hash))
+                    && Some(old.phc_master_enc_key_part_hash)
+                        == new
+                            .phc_previous_master_enc_key_part_hash
+                            .or(Some(new.phc_master_enc_key_part_hash))
+                {
+                    log::info!("a master encryption key part is being rotated");
+                } else {
+                    log::warn!(
+                        "a master encryption key part changed without being rotated; this \
+                         invalidates all existing polymorphic pseudonyms and should only happen \
+                         in an ephemeral test setup"
+                    );
+                }
+                break 'prior None;
+            }
+            if old.transcryptor_previous_master_enc_key_part_hash
+                != new.transcryptor_previous_master_enc_key_part_hash
+                || old.phc_previous_master_enc_key_part_hash
+                    != new.phc_previous_master_enc_key_part_hash
+            {
+                // the rotation of a master encryption key part has started or ended
+                break 'prior None;
+            }
+            rs.master_enc_key
+                .clone()
+                .map(|key| (key, rs.master_enc_rekey.clone()))
         };
 
         // Otherwise derive it from the transcryptor's sealed master key part — but only once the
         // transcryptor has adopted the constellation PHC is computing, so the part is sealed under
-        // the shared secret PHC currently holds and the unseal is guaranteed to succeed.
-        let master_enc_key = match prior_master_enc_key {
-            existing @ Some(_) => existing,
-            None => self.master_enc_key_from_sealed_part(&tdi, &t_ss, new_constellation_id)?,
+        // the shared secret PHC currently holds and the unseal is guaranteed to succeed.  The
+        // co-transcryptors, if any, then multiply in their parts.
+        let (master_enc_key, master_enc_rekey) = match prior_master_enc_key {
+            Some((key, rekey)) => (Some(key), rekey),
+            None => match self
+                .master_enc_key_from_sealed_part(
+                    &tdi,
+                    &t_ss,
+                    &cotdis,
+                    &co_transcryptor_ss,
+                    new_constellation_id,
+                )
+                .await?
+            {
+                Some((key, rekey)) => (Some(key), rekey),
+                None => (None, None),
+            },
         };
 
         if self.running_state.is_none()
@@ -342,7 +560,9 @@ impl crate::servers::App<Server> for App {
                 seed: RunningStateSeed {
                     t_ss,
                     auths_ss,
+                    co_transcryptor_ss,
                     master_enc_key,
+                    master_enc_rekey,
                 },
             });
         }
@@ -354,13 +574,17 @@ impl crate::servers::App<Server> for App {
         // Our constellation is unchanged.  If PHC has only now been able to derive the master
         // encryption key (or it changed), rebuild the running state without changing the
         // constellation, so the transcryptor and authentication server are not restarted.
-        if running_state.master_enc_key != master_enc_key {
+        if running_state.master_enc_key != master_enc_key
+            || running_state.master_enc_rekey != master_enc_rekey
+        {
             log::info!("master encryption key (re)derived; updating running state only");
             return Ok(DiscoverVerdict::RunningStateOutdated {
                 seed: RunningStateSeed {
                     t_ss,
                     auths_ss,
+                    co_transcryptor_ss,
                     master_enc_key,
+                    master_enc_rekey,
                 },
             });
         }
@@ -411,6 +635,27 @@ impl crate::servers::App<Server> for App {
             );
         }
 
+        for (cotdi, url) in cotdis.iter().zip(&self.co_transcryptor_urls) {
+            if cotdi
+                .constellation_or_id
+                .as_ref()
+                .is_some_and(|c| *c.id() != constellation.id)
+            {
+                // co-transcryptor's constellation is out of date; invoke discovery
+                log::info!(
+                    "{phc}: the co-transcryptor at {url}'s constellation is out of date - invoking \
+                     its discovery..",
+                    phc = servers::Name::PubhubsCentral,
+                );
+                let url = url.clone();
+                js.spawn_local(
+                    self.client
+                        .query::<api::DiscoveryRun>(&url, NoPayload)
+                        .into_future(),
+                );
+            }
+        }
+
         let result_maybe = js.join_next().await;
 
         // Whatever the result, we don't want to abort the the discovery run calls
@@ -420,7 +665,12 @@ impl crate::servers::App<Server> for App {
         match result_maybe {
             // joinset was empty, no discovery was ran
             None => {
-                if tdi.constellation_or_id.is_some() && asdi.constellation_or_id.is_some() {
+                if tdi.constellation_or_id.is_some()
+                    && asdi.constellation_or_id.is_some()
+                    && cotdis
+                        .iter()
+                        .all(|cotdi| cotdi.constellation_or_id.is_some())
+                {
                     // All servers share our constellation, so the transcryptor has adopted it and
                     // published a sealed master key part for it — meaning we must have derived the
                     // master encryption key by now.
@@ -462,6 +712,10 @@ impl crate::servers::App<Server> for App {
         Some(&self.master_enc_key_part)
     }
 
+    fn previous_master_enc_key_part(&self) -> Option<&elgamal::PrivateKey> {
+        self.previous_master_enc_key_part.as_ref()
+    }
+
     async fn local_task(weak: std::rc::Weak<Self>) {
         use tokio::sync::broadcast::error::RecvError;
 
@@ -508,7 +762,10 @@ impl crate::servers::App<Server> for App {
     }
 
     async fn global_task(app: Rc<Self>) -> anyhow::Result<Infallible> {
+        app.load_hub_registry().await?;
+
         let localset = tokio::task::LocalSet::new();
+        localset.spawn_local(Self::rekey_sweep(app.clone()));
         let _hcu = HubCacheUpdater::new(app, &localset);
 
         localset.await;
@@ -559,6 +816,9 @@ struct HubCacheUpdater {
     app: Rc<App>,
     hub_info: RefCell<HashMap<handle::Handle, Option<api::hub::InfoResp>>>,
     unpublished_updates: Cell<bool>,
+
+    /// The hubs for which a [`HubCacheUpdater::handle_hub`] task is running
+    hub_tasks: RefCell<HashMap<id::Id, (hub::BasicInfo, tokio::task::AbortHandle)>>,
 }
 
 impl HubCacheUpdater {
@@ -567,17 +827,67 @@ impl HubCacheUpdater {
             app: app.clone(),
             hub_info: RefCell::new(Default::default()),
             unpublished_updates: Cell::new(false),
+            hub_tasks: RefCell::new(Default::default()),
         });
 
-        for basic_hub_info in app.shared.hubs.values() {
-            localset.spawn_local(hcu.clone().handle_hub(basic_hub_info.clone()));
-        }
-
+        localset.spawn_local(hcu.clone().track_hubs());
         localset.spawn_local(hcu.clone().push_updates());
 
         hcu
     }
 
+    /// Keeps the [`HubCacheUpdater::handle_hub`] tasks in line with the active hubs.
+    async fn track_hubs(self: Rc<Self>) {
+        let mut receiver = self.app.shared.subscribe_to_hubs();
+
+        loop {
+            let hubs = receiver.borrow_and_update().clone();
+
+            self.update_hub_tasks(&hubs);
+
+            if receiver.changed().await.is_err() {
+                log::error!("bug: hub registry sender dropped");
+                return;
+            }
+        }
+    }
+
+    /// Stops the tasks of hubs that were retired or changed, and starts tasks for hubs that
+    /// have none.
+    fn update_hub_tasks(self: &Rc<Self>, hubs: &Hubs) {
+        let mut hub_tasks = self.hub_tasks.borrow_mut();
+
+        hub_tasks.retain(|id, (basic_hub_info, abort_handle)| {
+            if hubs.get(id) == Some(basic_hub_info) {
+                return true;
+            }
+
+            abort_handle.abort();
+
+            // the aborted task will not be polled again, so it will not miss this entry
+            self.hub_info
+                .borrow_mut()
+                .remove(basic_hub_info.handles.preferred());
+            self.unpublished_updates.set(true);
+
+            false
+        });
+
+        for basic_hub_info in hubs.values() {
+            if hub_tasks.contains_key(&basic_hub_info.id) {
+                continue;
+            }
+
+            let join_handle =
+                tokio::task::spawn_local(self.clone().handle_hub(basic_hub_info.clone()));
+
+            hub_tasks.insert(
+                basic_hub_info.id,
+                (basic_hub_info.clone(), join_handle.abort_handle()),
+            );
+        }
+    }
+
     async fn handle_hub(self: Rc<Self>, basic_hub_info: hub::BasicInfo) {
         let hub_handle = basic_hub_info.handles.preferred();
 
@@ -699,12 +1009,20 @@ impl App {
     /// inconsistency that should never happen: the transcryptor *has* adopted our constellation —
     /// so it has a running state and must publish a sealed part — yet that part is absent, fails to
     /// open, or does not match its published hash.
-    fn master_enc_key_from_sealed_part(
+    ///
+    /// When the transcryptor role is split, the co-transcryptors multiply in their parts before
+    /// PHC does, see [`Self::scale_master_enc_key_part_by_co_transcryptors`].
+    ///
+    /// When one or both master encryption key parts are being rotated, also returns the
+    /// [`MasterEncRekey`] needed to move polymorphic pseudonyms to the new master encryption key.
+    async fn master_enc_key_from_sealed_part(
         &self,
         tdi: &api::DiscoveryInfoResp,
         t_ss: &kem::SharedSecret,
+        cotdis: &[api::DiscoveryInfoResp],
+        co_transcryptor_ss: &[kem::SharedSecret],
         constellation_id: id::Id,
-    ) -> api::Result<Option<elgamal::PublicKey>> {
+    ) -> api::Result<Option<(elgamal::PublicKey, Option<MasterEncRekey>)>> {
         if tdi.constellation_or_id.as_ref().map(|c| *c.id()) != Some(constellation_id) {
             return Ok(None);
         }
@@ -734,10 +1052,193 @@ impl App {
             return Err(api::ErrorCode::InternalError);
         }
 
-        Ok(Some(phcrypto::combine_master_enc_key_parts(
-            &transcryptor_part,
-            &self.master_enc_key_part,
-        )))
+        if !cotdis.is_empty() {
+            // (master encryption key parts are not rotated while the transcryptor role is split)
+            let Some(proof) = tdi.master_enc_key_part_proof.clone() else {
+                log::error!(
+                    "transcryptor adopted our constellation but published no master key part proof"
+                );
+                return Err(api::ErrorCode::InternalError);
+            };
+
+            return Ok(self
+                .scale_master_enc_key_part_by_co_transcryptors(
+                    api::tr::ScaledMasterEncKey {
+                        key: transcryptor_part,
+                        proof,
+                    },
+                    cotdis,
+                    co_transcryptor_ss,
+                    constellation_id,
+                )
+                .await?
+                .map(|part| {
+                    (
+                        phcrypto::combine_master_enc_key_parts(&part, &self.master_enc_key_part),
+                        None,
+                    )
+                }));
+        }
+
+        let master_enc_key =
+            phcrypto::combine_master_enc_key_parts(&transcryptor_part, &self.master_enc_key_part);
+
+        let transcryptor_previous = match tdi.previous_master_enc_key_part_sealed.clone() {
+            None => None,
+            Some(sealed) => {
+                let previous: api::PreviousMasterEncKeyPart =
+                    sealed.open(&phcrypto::sealing_secret(t_ss)).map_err(|_| {
+                        log::error!(
+                            "could not open the transcryptor's sealed previous master encryption \
+                             key part"
+                        );
+                        api::ErrorCode::InternalError
+                    })?;
+
+                if tdi.previous_master_enc_key_part_hash
+                    != Some(phcrypto::master_enc_key_part_hash(&previous.part))
+                {
+                    log::error!(
+                        "transcryptor's sealed previous master_enc_key_part does not match its \
+                         published hash"
+                    );
+                    return Err(api::ErrorCode::InternalError);
+                }
+
+                Some(previous)
+            }
+        };
+
+        if transcryptor_previous.is_none() && self.previous_master_enc_key_part.is_none() {
+            return Ok(Some((master_enc_key, None)));
+        }
+
+        // A polymorphic pseudonym may be encrypted for any combination of the previous or
+        // current transcryptor part with the previous or current part of PHC.
+        let transcryptor_parts: Vec<(elgamal::PublicKey, Scalar)> =
+            std::iter::once((transcryptor_part, Scalar::ONE))
+                .chain(
+                    transcryptor_previous
+                        .map(|previous| (previous.part, *previous.rekey_factor.as_scalar())),
+                )
+                .collect();
+
+        let phc_parts: Vec<(elgamal::PrivateKey, Scalar)> =
+            std::iter::once((self.master_enc_key_part.clone(), Scalar::ONE))
+                .chain(self.previous_master_enc_key_part.as_ref().map(|previous| {
+                    (
+                        previous.clone(),
+                        phcrypto::master_enc_key_part_rekey_factor(
+                            previous,
+                            &self.master_enc_key_part,
+                        ),
+                    )
+                }))
+                .collect();
+
+        let mut previous_master_enc_keys: Vec<(elgamal::PublicKey, Scalar)> = Vec::new();
+
+        for (transcryptor_part, transcryptor_factor) in transcryptor_parts.iter() {
+            for (phc_part, phc_factor) in phc_parts.iter() {
+                let previous_master_enc_key =
+                    phcrypto::combine_master_enc_key_parts(transcryptor_part, phc_part);
+
+                if previous_master_enc_key == master_enc_key {
+                    continue;
+                }
+
+                let factor = transcryptor_factor * phc_factor;
+
+                if elgamal::PrivateKey::from(factor).scale(&previous_master_enc_key)
+                    != master_enc_key
+                {
+                    log::error!(
+                        "the previous master encryption key parts do not re-key to the current \
+                         master encryption key"
+                    );
+                    return Err(api::ErrorCode::InternalError);
+                }
+
+                previous_master_enc_keys.push((previous_master_enc_key, factor));
+            }
+        }
+
+        let rekey = MasterEncRekey {
+            previous_master_enc_keys,
+        };
+
+        Ok(Some((master_enc_key, Some(rekey))))
+    }
+
+    /// Has the co-transcryptors multiply the transcryptor's master encryption key part `x_T1 B`
+    /// by their parts in turn, via [`api::tr::ScaleMasterEncKeyEP`], returning `x_Tn ... x_T1 B`.
+    ///
+    /// Like [`Self::master_enc_key_from_sealed_part`], returns `Ok(None)` when a co-transcryptor
+    /// has not adopted our constellation `constellation_id` yet.
+    async fn scale_master_enc_key_part_by_co_transcryptors(
+        &self,
+        transcryptor_part: api::tr::ScaledMasterEncKey,
+        cotdis: &[api::DiscoveryInfoResp],
+        co_transcryptor_ss: &[kem::SharedSecret],
+        constellation_id: id::Id,
+    ) -> api::Result<Option<elgamal::PublicKey>> {
+        let mut to_scale = api::tr::MasterEncKeyToScale {
+            chain: vec![transcryptor_part],
+        };
+
+        for ((url, cotdi), ss) in self
+            .co_transcryptor_urls
+            .iter()
+            .zip(cotdis)
+            .zip(co_transcryptor_ss)
+        {
+            if cotdi.constellation_or_id.as_ref().map(|c| *c.id()) != Some(constellation_id) {
+                return Ok(None);
+            }
+
+            let sealing_secret = phcrypto::sealing_secret(ss);
+
+            let resp = self
+                .client
+                .query::<api::tr::ScaleMasterEncKeyEP>(
+                    url,
+                    api::tr::ScaleMasterEncKeyReq {
+                        key: api::Sealed::new(&to_scale, &sealing_secret)?,
+                    },
+                )
+                .await
+                .into_server_result()?;
+
+            let sealed = match resp {
+                api::tr::ScaleMasterEncKeyResp::Success(sealed) => sealed,
+                api::tr::ScaleMasterEncKeyResp::RetryWithNewConstellation => {
+                    log::debug!("the co-transcryptor at {url} is not on our constellation yet");
+                    return Ok(None);
+                }
+            };
+
+            let scaled: api::tr::ScaledMasterEncKey =
+                sealed.open(&sealing_secret).map_err(|_| {
+                    log::error!(
+                        "could not open the master encryption key scaled by the co-transcryptor \
+                         at {url}"
+                    );
+                    api::ErrorCode::InternalError
+                })?;
+
+            let previous = &to_scale.chain.last().expect("chain is never empty").key;
+
+            if !scaled.proof.verify(previous, &scaled.key) {
+                log::error!(
+                    "the co-transcryptor at {url} did not prove it scaled the master encryption key"
+                );
+                return Err(api::ErrorCode::InternalError);
+            }
+
+            to_scale.chain.push(scaled);
+        }
+
+        Ok(to_scale.chain.pop().map(|scaled| scaled.key))
     }
 
     /// Encapsulate a fresh shared secret against the encapsulation key in `peer`'s discovery info
@@ -777,9 +1278,11 @@ impl App {
 pub struct AppCreator {
     pub base: AppCreatorBase<Server>,
     pub transcryptor_url: url::Url,
+    pub co_transcryptor_urls: Vec<url::Url>,
     pub auths_url: url::Url,
     pub global_client_url: url::Url,
     pub master_enc_key_part: elgamal::PrivateKey,
+    pub previous_master_enc_key_part: Option<elgamal::PrivateKey>,
     pub attr_id_secret: Box<[u8]>,
     pub auth_token_secret: crypto::SealingKey,
     pub auth_token_validity: core::time::Duration,
@@ -788,6 +1291,8 @@ pub struct AppCreator {
     pub user_object_hmac_secret: Box<[u8]>,
     pub quota: api::phc::user::Quota,
     pub card_pseud_validity: core::time::Duration,
+    pub bannable_sources: Option<HashSet<attr::Source>>,
+    pub allow_insecure_onboarding_urls: bool,
     pub hub_cache_config: HubCacheConfig,
 }
 
@@ -825,9 +1330,11 @@ impl crate::servers::AppCreator<Server> for AppCreator {
         App {
             base: AppBase::new(self.base, handle, generation),
             transcryptor_url: self.transcryptor_url,
+            co_transcryptor_urls: self.co_transcryptor_urls,
             auths_url: self.auths_url,
             global_client_url: self.global_client_url,
             master_enc_key_part: self.master_enc_key_part,
+            previous_master_enc_key_part: self.previous_master_enc_key_part,
             attr_id_secret: self.attr_id_secret,
             auth_token_secret: self.auth_token_secret,
             auth_token_validity: self.auth_token_validity,
@@ -836,11 +1343,15 @@ impl crate::servers::AppCreator<Server> for AppCreator {
             user_object_hmac_secret: self.user_object_hmac_secret,
             quota: self.quota,
             card_pseud_validity: self.card_pseud_validity,
+            bannable_sources: self.bannable_sources,
+            allow_insecure_onboarding_urls: self.allow_insecure_onboarding_urls,
             broadcast: context.broadcast.clone(),
             // cached_hub_info will be set later
             cached_hub_info: std::cell::RefCell::new(
                 api::Responder(Err(api::ErrorCode::PleaseRetry)).into_cached(),
             ),
+            cached_welcome: Default::default(),
+            attr_type_handles: Default::default(),
             hub_cache_config: self.hub_cache_config,
         }
     }
@@ -865,9 +1376,15 @@ impl crate::servers::AppCreator<Server> for AppCreator {
         Ok(Self {
             base,
             transcryptor_url: xconf.transcryptor_url.as_ref().clone(),
+            co_transcryptor_urls: xconf
+                .co_transcryptor_urls
+                .iter()
+                .map(|url| url.as_ref().clone())
+                .collect(),
             auths_url: xconf.auths_url.as_ref().clone(),
             global_client_url: xconf.global_client_url.as_ref().clone(),
             master_enc_key_part,
+            previous_master_enc_key_part: xconf.previous_master_enc_key_part.clone(),
             attr_id_secret: <serde_bytes::ByteBuf as Clone>::clone(
                 xconf
                     .attr_id_secret
@@ -890,6 +1407,8 @@ impl crate::servers::AppCreator<Server> for AppCreator {
             .into_boxed_slice(),
             quota: xconf.user_quota.clone(),
             card_pseud_validity: xconf.card_pseud_validity,
+            bannable_sources: xconf.bannable_sources.clone(),
+            allow_insecure_onboarding_urls: xconf.allow_insecure_onboarding_urls,
             hub_cache_config: xconf.hub_cache.clone(),
         })
     }
@@ -1,5 +1,6 @@
 //! Basic user endpoints, such as [`EnterEP`].
 use crate::api;
+use crate::api::ApiResultExt as _;
 use crate::api::OpenError;
 use crate::attr::{Attr, AttrState};
 use crate::common::elgamal;
@@ -23,14 +24,31 @@ use api::phc::user::*;
 
 use api::phc::user::UserState as ApiUserState;
 
+/// The minimal age of [`App::attr_type_handles`] before they are retrieved again.
+const ATTR_TYPE_HANDLES_MIN_AGE: std::time::Duration = std::time::Duration::from_secs(60);
+
 impl App {
-    /// Implements [`WelcomeEP`]
-    pub(super) fn cached_handle_user_welcome(app: &Self) -> api::Result<WelcomeResp> {
-        let running_state = app.running_state_or_please_retry()?;
+    /// Implements [`WelcomeEP`], recomputing the cached response only when the hubs changed
+    pub(super) async fn handle_user_welcome(app: web::Data<Rc<App>>) -> impl actix_web::Responder {
+        let hubs = app.shared.hubs();
+
+        let mut cached_welcome = app.cached_welcome.borrow_mut();
+
+        if let Some((generation, cached)) = cached_welcome.as_ref()
+            && *generation == hubs.generation
+        {
+            return cached.clone();
+        }
+
+        let cached = api::Responder(app.compute_user_welcome(&hubs)).into_cached();
+        *cached_welcome = Some((hubs.generation, cached.clone()));
+        cached
+    }
+
+    fn compute_user_welcome(&self, hubs: &super::hub_registry::Hubs) -> api::Result<WelcomeResp> {
+        let running_state = self.running_state_or_please_retry()?;
 
-        let hubs: HashMap<handle::Handle, hub::BasicInfo> = app
-            .shared
-            .hubs
+        let hubs: HashMap<handle::Handle, hub::BasicInfo> = hubs
             .values()
             .map(|hub| (hub.handles.preferred().clone(), hub.clone()))
             .collect();
@@ -60,7 +78,49 @@ impl App {
             return Ok(StateResp::RetryWithNewAuthToken);
         };
 
-        Ok(StateResp::State(user_state.into_user_version(&app)))
+        let attr_type_handles = app.attr_type_handles(&user_state).await?;
+
+        Ok(StateResp::State(Box::new(
+            user_state.into_user_version(&app, &attr_type_handles),
+        )))
+    }
+
+    /// Returns the preferred handles of the attribute types known to the authentication server
+    /// by their [`Id`], as advertised by [`api::auths::WelcomeEP`].
+    ///
+    /// The handles are cached, and only retrieved again when an attribute type of `user_state`
+    /// is missing, at most once every [`ATTR_TYPE_HANDLES_MIN_AGE`].
+    pub(super) async fn attr_type_handles(
+        &self,
+        user_state: &UserState,
+    ) -> api::Result<AttrTypeHandles> {
+        if let Some((retrieved_at, handles)) = self.attr_type_handles.borrow().as_ref()
+            && (retrieved_at.elapsed() < ATTR_TYPE_HANDLES_MIN_AGE
+                || user_state
+                    .attr_details
+                    .values()
+                    .all(|details| handles.contains_key(&details.attr_type)))
+        {
+            return Ok(handles.clone());
+        }
+
+        let welcome = self
+            .client
+            .query::<api::auths::WelcomeEP>(&self.auths_url, api::NoPayload)
+            .await
+            .into_server_result()?;
+
+        let handles: AttrTypeHandles = Rc::new(
+            welcome
+                .attr_types
+                .into_values()
+                .map(|attr_type| (attr_type.id, attr_type.handles.preferred().clone()))
+                .collect(),
+        );
+
+        *self.attr_type_handles.borrow_mut() = Some((std::time::Instant::now(), handles.clone()));
+
+        Ok(handles)
     }
 
     /// Implements [`EnterEP`]
@@ -111,20 +171,22 @@ impl App {
 
         // Check attributes are valid
         let identifying_attr = if let Some(identifying_attr) = identifying_attr {
-            let identifying_attr = app.id_attr(
-                match identifying_attr.open(&running_state.attr_signing_key, None) {
-                    Ok(identifying_attr) => identifying_attr,
-                    Err(OpenError::OtherConstellation(..)) | Err(OpenError::InternalError) => {
-                        return Err(api::ErrorCode::InternalError);
-                    }
-                    Err(OpenError::OtherwiseInvalid) => {
-                        return Err(api::ErrorCode::BadRequest);
-                    }
-                    Err(OpenError::Expired) | Err(OpenError::InvalidSignature) => {
-                        return Ok(EnterResp::RetryWithNewIdentifyingAttr);
-                    }
-                },
-            );
+            let identifying_attr = app
+                .id_attr(
+                    match identifying_attr.open(&running_state.attr_signing_key, None) {
+                        Ok(identifying_attr) => identifying_attr,
+                        Err(OpenError::OtherConstellation(..)) | Err(OpenError::InternalError) => {
+                            return Err(api::ErrorCode::InternalError);
+                        }
+                        Err(OpenError::OtherwiseInvalid) => {
+                            return Err(api::ErrorCode::BadRequest);
+                        }
+                        Err(OpenError::Expired) | Err(OpenError::InvalidSignature) => {
+                            return Ok(EnterResp::RetryWithNewIdentifyingAttr);
+                        }
+                    },
+                )
+                .await?;
 
             if identifying_attr.not_identifying {
                 log::warn!(
@@ -148,8 +210,8 @@ impl App {
             }
 
             for (add_attr_index, add_attr) in add_attrs.into_iter().enumerate() {
-                let ided_attr =
-                    app.id_attr(match add_attr.open(&running_state.attr_signing_key, None) {
+                let ided_attr = app
+                    .id_attr(match add_attr.open(&running_state.attr_signing_key, None) {
                         Ok(attr) => attr,
                         Err(OpenError::OtherConstellation(..)) | Err(OpenError::InternalError) => {
                             return Err(api::ErrorCode::InternalError);
@@ -162,7 +224,8 @@ impl App {
                                 index: add_attr_index,
                             });
                         }
-                    });
+                    })
+                    .await?;
 
                 if ided_attr.not_addable {
                     log::warn!(
@@ -215,6 +278,10 @@ impl App {
                         api::ErrorCode::InternalError
                     })?;
 
+                if user_and_version.0.deleted.is_some() {
+                    return Ok(EnterResp::RetryWithNewAuthToken);
+                }
+
                 break 'found_user (user_and_version, false);
             }
 
@@ -226,6 +293,7 @@ impl App {
                 // see if account exists
                 if let Some((ias, ias_v)) =
                     app.get_object::<AttrState>(&identifying_attr.id).await?
+                    && let Some(user_id) = ias.may_identify_user
                 {
                     log::trace!(
                         "enter: account exists for attribute {} of type {}",
@@ -233,15 +301,6 @@ impl App {
                         identifying_attr.attr_type,
                     );
 
-                    let user_id = ias.may_identify_user.ok_or_else(|| {
-                        log::error!(
-                            "identifying attribute {} of type {} has may_identify_user set to None",
-                            identifying_attr.value,
-                            identifying_attr.attr_type
-                        );
-                        api::ErrorCode::InternalError
-                    })?;
-
                     attr_states.insert(identifying_attr.id, (ias, ias_v));
 
                     let user_and_version = app
@@ -257,7 +316,21 @@ impl App {
                             api::ErrorCode::InternalError
                         })?;
 
-                    break 'found_user (user_and_version, false);
+                    if user_and_version
+                        .0
+                        .allow_login_by
+                        .contains(&identifying_attr.id)
+                    {
+                        break 'found_user (user_and_version, false);
+                    }
+
+                    // The user removed this attribute via `RemoveIdentifyingAttrEP`.  Registering
+                    // with it will fail, because it is still taken.
+                    log::trace!(
+                        "enter: attribute {} of type {} no longer allows login to account {user_id}",
+                        identifying_attr.value,
+                        identifying_attr.attr_type,
+                    );
                 }
 
                 log::trace!(
@@ -315,10 +388,15 @@ impl App {
                 return Err(api::ErrorCode::PleaseRetry);
             };
 
-            let user_state = UserState {
+            let now = api::NumericDate::now();
+
+            let mut user_state = UserState {
                 id: Id::random(),
                 card_id: Some(CardPseud(Id::random())),
-                registration_date: Some(api::NumericDate::now()),
+                card_revocation_nonce: None,
+                revoked_cards: Default::default(),
+                merged_cards: Default::default(),
+                registration_date: Some(now),
                 polymorphic_pseudonym: master_enc_key.encrypt_random(),
                 banned: false,
                 allow_login_by: attrs
@@ -333,9 +411,20 @@ impl App {
                     .collect(),
                 could_be_banned_by: Default::default(),
                 // NOTE: `could_be_banned_by` is set after the bannable attributes have been added
+                removed_identifying_attrs: Default::default(),
+                attr_details: Default::default(),
                 stored_objects: Default::default(),
+                object_history: Default::default(),
+                deleted: None,
+                merged_into: None,
             };
 
+            for attr in attrs.values() {
+                if !attr.not_identifying {
+                    user_state.record_attr_details(attr, now);
+                }
+            }
+
             let user_state_version = app
                 .put_object::<UserState>(&user_state, None)
                 .await?
@@ -344,15 +433,25 @@ impl App {
                     api::ErrorCode::InternalError
                 })?;
 
-            // Add identifying attribute.  We do not expect the attribute to exist, because
-            // otherwise we would be logging in, not registering.
-            assert!(!attr_states.contains_key(&identifying_attr.id));
-
-            let identifying_attr_state =
-                AttrState::new(identifying_attr.id, &identifying_attr, user_state.id);
+            // Add identifying attribute.  The attribute may only exist when it no longer identifies
+            // a user (because that user deleted their account), as was checked by
+            // `precheck_attrs_for_registration`, because otherwise we would be logging in, not
+            // registering.
+            let (identifying_attr_state, identifying_attr_state_version) =
+                match attr_states.get(&identifying_attr.id) {
+                    Some((ias, ias_v)) => {
+                        let mut ias = ias.clone();
+                        ias.may_identify_user = Some(user_state.id);
+                        (ias, Some(ias_v.clone()))
+                    }
+                    None => (
+                        AttrState::new(identifying_attr.id, &identifying_attr, user_state.id),
+                        None,
+                    ),
+                };
 
             if let Some(identifying_attr_state_version) = app
-                .put_object::<AttrState>(&identifying_attr_state, None)
+                .put_object::<AttrState>(&identifying_attr_state, identifying_attr_state_version)
                 .await
                 .inspect_err(|err| {
                     log::warn!(
@@ -362,13 +461,9 @@ impl App {
                     );
                 })?
             {
-                assert!(
-                    attr_states
-                        .insert(
-                            identifying_attr.id,
-                            (identifying_attr_state, identifying_attr_state_version),
-                        )
-                        .is_none()
+                attr_states.insert(
+                    identifying_attr.id,
+                    (identifying_attr_state, identifying_attr_state_version),
                 );
 
                 assert!(
@@ -397,6 +492,13 @@ impl App {
             return Ok(EnterResp::Banned);
         }
 
+        if let Some(attr) = attrs
+            .values()
+            .find(|attr| user_state.is_revoked_card(&attr.value))
+        {
+            return Ok(EnterResp::CardRevoked(attr.attr.clone()));
+        }
+
         if !retrieved_attr_states {
             for attr in attrs.values() {
                 if attr_states.contains_key(&attr.id) {
@@ -448,7 +550,8 @@ impl App {
             }
         }
 
-        // Now check that all the bannable attributes ban this user
+        // Now check that all the bannable attributes ban this user, and that the identifying
+        // attributes detached from a deleted account identify this user
         for attr in attrs.values() {
             let (attr_state, attr_state_version) =
                 if let Some(attr_state_and_version) = attr_states.get(&attr.id) {
@@ -457,12 +560,22 @@ impl App {
                     continue;
                 };
 
-            if !attr.bannable || attr_state.bans_users.contains(&user_state.id) {
+            let add_ban = attr.bannable && !attr_state.bans_users.contains(&user_state.id);
+            let attach = !attr.not_identifying && attr_state.may_identify_user.is_none();
+
+            if !add_ban && !attach {
                 continue;
             }
 
             let mut attr_state = attr_state.clone();
-            assert!(attr_state.bans_users.insert(user_state.id));
+
+            if add_ban {
+                assert!(attr_state.bans_users.insert(user_state.id));
+            }
+
+            if attach {
+                attr_state.may_identify_user = Some(user_state.id);
+            }
 
             match app
                 .put_object::<AttrState>(&attr_state, Some(attr_state_version.clone()))
@@ -488,6 +601,7 @@ impl App {
 
         let mut new_user_state = user_state.clone();
         let mut added_attrs: HashSet<Id> = Default::default();
+        let now = api::NumericDate::now();
 
         // Finally check that the attributes are added to the user's account state
         for (attr_id, (attr_state, ..)) in attr_states {
@@ -508,6 +622,10 @@ impl App {
             {
                 added_attrs.insert(attr_id);
             }
+
+            if added_attrs.contains(&attr_id) {
+                new_user_state.record_attr_details(attrs.get(&attr_id).unwrap(), now);
+            }
         }
 
         if !added_attrs.is_empty() {
@@ -551,12 +669,133 @@ impl App {
         })
     }
 
-    /// Computes and caches the [`Id`] of an [`Attr`].
-    fn id_attr(&self, attr: Attr) -> IdedAttr {
-        IdedAttr {
-            id: attr.id(&*self.attr_id_secret),
+    /// Computes (see [`Self::attr_id`]) and caches the [`Id`] of an [`Attr`].
+    ///
+    /// Also clears [`Attr::bannable`] when the attribute's source is not among the configured
+    /// `bannable_sources`.
+    async fn id_attr(&self, mut attr: Attr) -> api::Result<IdedAttr> {
+        if let (Some(bannable_sources), Some(source)) = (&self.bannable_sources, attr.source)
+            && attr.bannable
+            && !bannable_sources.contains(&source)
+        {
+            log::debug!(
+                "treating attribute of type {} obtained via {source:?} as not bannable",
+                attr.attr_type
+            );
+            attr.bannable = false;
+        }
+
+        Ok(IdedAttr {
+            id: self.attr_id(&attr).await?,
             attr,
+        })
+    }
+
+    /// Computes the [`Id`] of an [`Attr`].
+    ///
+    /// Attributes registered before their type was normalized are stored under
+    /// [`Attr::unnormalized_id`] (see [`Attr::unnormalized_value`]), and are moved to their
+    /// normalized id here, so that other ways of writing the same value (which have no
+    /// unnormalized id in common with it) find them too.
+    pub(super) async fn attr_id(&self, attr: &Attr) -> api::Result<Id> {
+        let id = attr.id(&*self.attr_id_secret);
+
+        let Some(unnormalized_id) = attr.unnormalized_id(&*self.attr_id_secret) else {
+            return Ok(id);
+        };
+
+        if self.get_object::<AttrState>(&id).await?.is_some() {
+            return Ok(id);
         }
+
+        let Some((attr_state, _)) = self.get_object::<AttrState>(&unnormalized_id).await? else {
+            return Ok(id);
+        };
+
+        log::info!(
+            "moving attribute {unnormalized_id} of type {} to the id {id} of its normalized value",
+            attr.attr_type
+        );
+
+        self.move_attr_state(attr_state, id).await?;
+
+        Ok(id)
+    }
+
+    /// Moves the given [`AttrState`] (and its [`BanLog`]) to the id `to`, updating the users
+    /// that refer to it.  The old [`AttrState`] is left in place, but is no longer referred to.
+    ///
+    /// The users are updated first, so that when this is interrupted, the attribute state is
+    /// still found under its old id, and the move is tried again.
+    ///
+    /// [`BanLog`]: super::admin::BanLog
+    async fn move_attr_state(&self, attr_state: AttrState, to: Id) -> api::Result<()> {
+        let from = attr_state.attr;
+
+        for user_id in attr_state
+            .may_identify_user
+            .iter()
+            .chain(attr_state.bans_users.iter())
+        {
+            let Some((mut user_state, version)) = self.get_object::<UserState>(user_id).await?
+            else {
+                log::warn!("attribute {from} refers to user {user_id} that does not exist");
+                continue;
+            };
+
+            let mut changed = false;
+
+            for attr_ids in [
+                &mut user_state.allow_login_by,
+                &mut user_state.could_be_banned_by,
+                &mut user_state.removed_identifying_attrs,
+            ] {
+                if attr_ids.remove(&from) {
+                    attr_ids.insert(to);
+                    changed = true;
+                }
+            }
+
+            if let Some(details) = user_state.attr_details.remove(&from) {
+                user_state.attr_details.insert(to, details);
+                changed = true;
+            }
+
+            if changed
+                && self
+                    .put_object::<UserState>(&user_state, Some(version))
+                    .await?
+                    .is_none()
+            {
+                log::debug!("user {user_id} changed while moving attribute {from}");
+                return Err(api::ErrorCode::PleaseRetry);
+            }
+        }
+
+        if let Some((ban_log, _)) = self.get_object::<super::admin::BanLog>(&from).await? {
+            let ban_log = super::admin::BanLog {
+                attr: to,
+                ..ban_log
+            };
+
+            // `None` means there is already a ban log for `to`, which we leave alone
+            let _ = self
+                .put_object::<super::admin::BanLog>(&ban_log, None)
+                .await?;
+        }
+
+        // `None` means the attribute state was moved concurrently
+        let _ = self
+            .put_object::<AttrState>(
+                &AttrState {
+                    attr: to,
+                    ..attr_state
+                },
+                None,
+            )
+            .await?;
+
+        Ok(())
     }
 
     /// Pre-checks whether the given attributes in `attrs` are suitable for
@@ -630,6 +869,284 @@ impl App {
         Ok(None)
     }
 
+    /// Implements [`MergeEP`].
+    ///
+    /// # Note on the implementation
+    ///
+    /// To merge the other account into the current account we:
+    ///   (1) Make the other account's bannable attributes ban the current account too;
+    ///   (2) Add the other account's attributes, cards and objects to the current account;
+    ///   (3) Mark the other account as merged into the current account; and
+    ///   (4) Make the other account's identifying attributes identify the current account.
+    ///
+    /// If the process fails halfway the client can simply retry.  When the other account has
+    /// already been marked as merged (3), only step (4) is performed.
+    pub(super) async fn handle_user_merge(
+        app: Rc<Self>,
+        req: web::Json<MergeReq>,
+        auth_token: actix_web::web::Header<AuthToken>,
+    ) -> api::Result<MergeResp> {
+        let running_state = &app.running_state_or_please_retry()?;
+
+        let Ok((mut user_state, user_state_version)) = app
+            .open_auth_token_and_get_user_state(auth_token.into_inner())
+            .await?
+        else {
+            return Ok(MergeResp::RetryWithNewAuthToken);
+        };
+
+        let identifying_attr = app
+            .id_attr(
+                match req
+                    .into_inner()
+                    .identifying_attr
+                    .open(&running_state.attr_signing_key, None)
+                {
+                    Ok(identifying_attr) => identifying_attr,
+                    Err(OpenError::OtherConstellation(..)) | Err(OpenError::InternalError) => {
+                        return Err(api::ErrorCode::InternalError);
+                    }
+                    Err(OpenError::OtherwiseInvalid) => {
+                        return Err(api::ErrorCode::BadRequest);
+                    }
+                    Err(OpenError::Expired) | Err(OpenError::InvalidSignature) => {
+                        return Ok(MergeResp::RetryWithNewIdentifyingAttr);
+                    }
+                },
+            )
+            .await?;
+
+        if identifying_attr.not_identifying {
+            log::debug!(
+                "merge: attribute of type {} is not identifying",
+                identifying_attr.attr_type
+            );
+            return Err(api::ErrorCode::BadRequest);
+        }
+
+        let Some(other_user_id) = app
+            .get_object::<AttrState>(&identifying_attr.id)
+            .await?
+            .and_then(|(attr_state, _)| attr_state.may_identify_user)
+        else {
+            return Ok(MergeResp::UnknownAccount);
+        };
+
+        if other_user_id == user_state.id {
+            return Ok(MergeResp::SameAccount);
+        }
+
+        let (mut other_user_state, other_user_state_version) = app
+            .get_object::<UserState>(&other_user_id)
+            .await?
+            .ok_or_else(|| {
+                log::error!(
+                    "identifying attribute {} refers to a user account {other_user_id} \
+                    that does not exist",
+                    identifying_attr.id
+                );
+                api::ErrorCode::InternalError
+            })?;
+
+        if other_user_state.merged_into != Some(user_state.id) {
+            if other_user_state.deleted.is_some()
+                || !other_user_state
+                    .allow_login_by
+                    .contains(&identifying_attr.id)
+            {
+                return Ok(MergeResp::UnknownAccount);
+            }
+
+            if user_state.banned || other_user_state.banned {
+                return Ok(MergeResp::Banned);
+            }
+
+            // (1) make the other account's bannable attributes ban this account too
+            for attr_id in other_user_state.could_be_banned_by.iter() {
+                let Some((mut attr_state, attr_state_version)) =
+                    app.get_object::<AttrState>(attr_id).await?
+                else {
+                    log::warn!("bannable attribute {attr_id} of user {other_user_id} is missing");
+                    continue;
+                };
+
+                if !attr_state.bans_users.insert(user_state.id) {
+                    continue;
+                }
+
+                if app
+                    .put_object::<AttrState>(&attr_state, Some(attr_state_version))
+                    .await?
+                    .is_none()
+                {
+                    return Err(api::ErrorCode::PleaseRetry);
+                }
+            }
+
+            // (2) add the other account's attributes and objects to this account
+            user_state
+                .allow_login_by
+                .extend(other_user_state.allow_login_by.iter().copied());
+            user_state
+                .could_be_banned_by
+                .extend(other_user_state.could_be_banned_by.iter().copied());
+            user_state
+                .removed_identifying_attrs
+                .extend(other_user_state.removed_identifying_attrs.iter().copied());
+
+            for (attr_id, details) in other_user_state.attr_details.iter() {
+                user_state
+                    .attr_details
+                    .entry(*attr_id)
+                    .or_insert_with(|| details.clone());
+            }
+
+            user_state.registration_date = match (
+                user_state.registration_date,
+                other_user_state.registration_date,
+            ) {
+                (Some(date), Some(other_date)) => Some(date.min(other_date)),
+                (date, other_date) => date.or(other_date),
+            };
+
+            // The cards issued to the other account now identify this account, so they must
+            // remain revocable (see `RevokeCardsEP`), and stay revoked when they were.
+            for merged_cards in std::iter::once(MergedCards {
+                card_pseud: other_user_state.card_id(),
+                revocation_nonce: other_user_state.card_revocation_nonce,
+            })
+            .chain(other_user_state.merged_cards.iter().cloned())
+            {
+                if !user_state.merged_cards.contains(&merged_cards) {
+                    user_state.merged_cards.push(merged_cards);
+                }
+            }
+
+            for revoked_cards in other_user_state.revoked_cards.iter() {
+                if !user_state.revoked_cards.contains(revoked_cards) {
+                    user_state.revoked_cards.push(revoked_cards.clone());
+                }
+            }
+
+            // Objects of the other account stored under a handle that is also used by this
+            // account become the most recent earlier version of this account's object, and the
+            // earlier versions of both are kept too, as far as `SetObjectHistoryReq::MAX_KEEP`
+            // allows.
+            let mut merged_histories: HashSet<handle::Handle> = Default::default();
+
+            for (handle, obj_details) in other_user_state.stored_objects.drain() {
+                match user_state.stored_objects.entry(handle.clone()) {
+                    std::collections::hash_map::Entry::Vacant(entry) => {
+                        entry.insert(obj_details);
+                    }
+                    std::collections::hash_map::Entry::Occupied(entry) => {
+                        if entry.get().id != obj_details.id {
+                            let history =
+                                user_state.object_history.entry(handle.clone()).or_default();
+
+                            if !history.versions.contains(&obj_details) {
+                                history.versions.insert(0, obj_details);
+                                merged_histories.insert(handle);
+                            }
+                        }
+                    }
+                }
+            }
+
+            for (handle, other_history) in other_user_state.object_history.drain() {
+                let history = user_state.object_history.entry(handle.clone()).or_default();
+
+                history.keep = history.keep.max(other_history.keep);
+
+                for version in other_history.versions {
+                    if !history.versions.contains(&version) {
+                        history.versions.push(version);
+                    }
+                }
+
+                merged_histories.insert(handle);
+            }
+
+            let mut discarded_objects: Vec<Id> = Default::default();
+
+            for handle in merged_histories {
+                let history = user_state.object_history.get_mut(&handle).unwrap();
+
+                history.keep = history.keep.max(
+                    history
+                        .versions
+                        .len()
+                        .min(SetObjectHistoryReq::MAX_KEEP as usize) as u8,
+                );
+
+                discarded_objects.extend(history.truncate());
+            }
+
+            if let Err(quotum_name) = user_state.update_quota(app.quota.clone()) {
+                return Ok(MergeResp::QuotumReached(quotum_name));
+            }
+
+            if app
+                .put_object::<UserState>(&user_state, Some(user_state_version))
+                .await?
+                .is_none()
+            {
+                return Err(api::ErrorCode::PleaseRetry);
+            }
+
+            // (3) mark the other account as merged into this account
+            other_user_state.allow_login_by.clear();
+            other_user_state.removed_identifying_attrs.clear();
+            other_user_state
+                .attr_details
+                .retain(|attr_id, _| other_user_state.could_be_banned_by.contains(attr_id));
+            other_user_state.deleted = Some(api::NumericDate::now());
+            other_user_state.merged_into = Some(user_state.id);
+
+            if app
+                .put_object::<UserState>(&other_user_state, Some(other_user_state_version))
+                .await?
+                .is_none()
+            {
+                return Err(api::ErrorCode::PleaseRetry);
+            }
+
+            app.delete_unreferenced_user_objects(&user_state, discarded_objects)
+                .await;
+        }
+
+        // (4) make the other account's identifying attributes identify this account
+        for attr_id in user_state.identifying_attrs_ever() {
+            let Some((mut attr_state, attr_state_version)) =
+                app.get_object::<AttrState>(attr_id).await?
+            else {
+                continue;
+            };
+
+            if attr_state.may_identify_user != Some(other_user_id) {
+                continue;
+            }
+
+            attr_state.may_identify_user = Some(user_state.id);
+
+            if app
+                .put_object::<AttrState>(&attr_state, Some(attr_state_version))
+                .await?
+                .is_none()
+            {
+                return Err(api::ErrorCode::PleaseRetry);
+            }
+        }
+
+        log::debug!("merged user {other_user_id} into {}", user_state.id);
+
+        let attr_type_handles = app.attr_type_handles(&user_state).await?;
+
+        Ok(MergeResp::Merged(Box::new(
+            user_state.into_user_version(&app, &attr_type_handles),
+        )))
+    }
+
     /// Implements [`RefreshEP`]
     pub(super) async fn handle_user_refresh(
         app: Rc<Self>,
@@ -742,6 +1259,8 @@ impl App {
 
     /// Like [`Self::open_auth_token_and_get_user_state`] but with the option to accept an expired auth
     /// token.
+    ///
+    /// Auth tokens of deleted accounts (see [`UserState::deleted`]) are treated as invalid.
     pub(super) async fn open_auth_token_and_get_user_state_ext(
         &self,
         auth_token: AuthToken,
@@ -751,15 +1270,22 @@ impl App {
             return Ok(Err(OPAQUE));
         };
 
-        Ok(Ok(self
-            .get_object::<UserState>(&user_id)
-            .await?
-            .ok_or_else(|| {
-                log::error!(
-                    "auth token refers to non- (or no longer) existing user with id {user_id}",
-                );
-                api::ErrorCode::InternalError
-            })?))
+        let (user_state, version) =
+            self.get_object::<UserState>(&user_id)
+                .await?
+                .ok_or_else(|| {
+                    log::error!(
+                        "auth token refers to non- (or no longer) existing user with id {user_id}",
+                    );
+                    api::ErrorCode::InternalError
+                })?;
+
+        if user_state.deleted.is_some() {
+            log::debug!("auth token used for deleted user account {user_id}");
+            return Ok(Err(OPAQUE));
+        }
+
+        Ok(Ok((user_state, version)))
     }
 }
 
@@ -799,6 +1325,24 @@ pub struct UserState {
     #[serde(default)]
     card_id: Option<CardPseud>,
 
+    /// Random nonce used as yivi revocation key for the cards issued for [`UserState::card_id`],
+    /// see [`CardPseudPackage::revocation_nonce`].  Set when the user first requests a card
+    /// pseudonym package.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    card_revocation_nonce: Option<Id>,
+
+    /// The cards of this user that were revoked via [`api::admin::RevokeCardsEP`]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub revoked_cards: Vec<RevokedCards>,
+
+    /// The (unrevoked) cards issued to the accounts merged into this one via [`MergeEP`], which
+    /// now identify this account.  Revoked together with this account's own cards.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub merged_cards: Vec<MergedCards>,
+
     /// Registration date for this user
     ///
     /// Might not be set for users that registered under v3.0.0.
@@ -828,11 +1372,127 @@ pub struct UserState {
     /// login in.
     pub could_be_banned_by: HashSet<Id>,
 
+    /// Attributes removed from [`UserState::allow_login_by`] via [`RemoveIdentifyingAttrEP`].
+    ///
+    /// Their [`AttrState::may_identify_user`] still points to this account, so that they can not
+    /// be used to register another account, until this account is deleted.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HashSet::is_empty")]
+    pub removed_identifying_attrs: HashSet<Id>,
+
+    /// Details on the attributes in [`UserState::allow_login_by`] and
+    /// [`UserState::could_be_banned_by`], as far as known.  Also kept for attributes removed
+    /// from [`UserState::allow_login_by`].
+    ///
+    /// Not recorded for attributes added before this field was introduced.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub attr_details: HashMap<Id, UserAttrDetails>,
+
     /// Details about the objects stored by this user at pubhubs central
     pub stored_objects: HashMap<handle::Handle, super::user_object_store::UserObjectDetails>,
+
+    /// Earlier versions of the objects stored by this user, for those handles for which the user
+    /// asked to keep them via [`SetObjectHistoryEP`].  Counts towards the user's [`Quota`].
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub object_history: HashMap<handle::Handle, super::user_object_store::ObjectHistory>,
+
+    /// When this account was deleted via [`DeleteEP`], if it was.
+    ///
+    /// A deleted account is kept so that it can still be banned via the attributes in
+    /// [`UserState::could_be_banned_by`], but can no longer be used.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted: Option<api::NumericDate>,
+
+    /// Set (together with [`UserState::deleted`]) when this account was merged into the account
+    /// with the given [`Id`] via [`MergeEP`].
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merged_into: Option<Id>,
+}
+
+/// Cards of a user that were revoked, see [`UserState::revoked_cards`].
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RevokedCards {
+    /// The registration pseudonym on the revoked cards
+    pub card_pseud: CardPseud,
+
+    /// The [`UserState::card_revocation_nonce`] of the revoked cards, if any
+    pub revocation_nonce: Option<Id>,
+
+    pub revoked_at: api::NumericDate,
+}
+
+/// Cards issued to an account merged into another, see [`UserState::merged_cards`].
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MergedCards {
+    /// The registration pseudonym on the cards
+    pub card_pseud: CardPseud,
+
+    /// The [`UserState::card_revocation_nonce`] of the merged account, if any
+    pub revocation_nonce: Option<Id>,
+}
+
+/// Details on an attribute of a user, see [`UserState::attr_details`].
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct UserAttrDetails {
+    /// [`Attr::attr_type`]
+    pub attr_type: Id,
+
+    /// When the attribute was added to the user's account
+    pub added_at: api::NumericDate,
 }
 
 impl UserState {
+    /// Records the [`UserAttrDetails`] of the given attribute, unless already present.
+    fn record_attr_details(&mut self, attr: &IdedAttr, added_at: api::NumericDate) {
+        self.attr_details
+            .entry(attr.id)
+            .or_insert_with(|| UserAttrDetails {
+                attr_type: attr.attr_type,
+                added_at,
+            });
+    }
+
+    /// Returns the [`AttrDetails`] of all attributes of this user, using the given
+    /// [`App::attr_type_handles`].
+    pub(crate) fn attrs(
+        &self,
+        attr_type_handles: &HashMap<Id, handle::Handle>,
+    ) -> HashMap<Id, AttrDetails> {
+        self.allow_login_by
+            .iter()
+            .chain(self.could_be_banned_by.iter())
+            .map(|attr_id| {
+                let details = self.attr_details.get(attr_id);
+
+                (
+                    *attr_id,
+                    AttrDetails {
+                        attr_type: details
+                            .and_then(|details| attr_type_handles.get(&details.attr_type).cloned()),
+                        allows_login: self.allow_login_by.contains(attr_id),
+                        bans: self.could_be_banned_by.contains(attr_id),
+                        added_at: details.map(|details| details.added_at),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// The attributes that may still identify this account: those in
+    /// [`UserState::allow_login_by`] and those removed from it.  (Before
+    /// [`UserState::removed_identifying_attrs`] was introduced, removed attributes were only
+    /// remembered via [`UserState::attr_details`].)
+    pub(super) fn identifying_attrs_ever(&self) -> impl Iterator<Item = &Id> {
+        self.allow_login_by
+            .iter()
+            .chain(self.removed_identifying_attrs.iter())
+            .chain(self.attr_details.keys())
+    }
+
     /// Returns [`UserState::card_id`] when available, and otherwise an [`Id`] derived from [`UserState::id`].
     pub fn card_id(&self) -> CardPseud {
         if let Some(card_id) = self.card_id {
@@ -845,19 +1505,104 @@ impl UserState {
         ))
     }
 
+    /// Returns [`UserState::card_revocation_nonce`], generating it first if needed, in which case
+    /// the user state must be stored again.
+    pub(super) fn card_revocation_nonce(&mut self) -> (Id, bool) {
+        match self.card_revocation_nonce {
+            Some(nonce) => (nonce, false),
+            None => (*self.card_revocation_nonce.insert(Id::random()), true),
+        }
+    }
+
+    /// Records that the cards issued so far have been revoked, including the
+    /// [`UserState::merged_cards`], and gives the user a new [`UserState::card_id`] for new cards.
+    /// Returns the newly revoked cards, this user's own first.
+    pub(super) fn revoke_cards(&mut self) -> &[RevokedCards] {
+        let revoked_at = api::NumericDate::now();
+        let first = self.revoked_cards.len();
+
+        self.revoked_cards.push(RevokedCards {
+            card_pseud: self.card_id(),
+            revocation_nonce: self.card_revocation_nonce.take(),
+            revoked_at,
+        });
+
+        self.revoked_cards.extend(
+            self.merged_cards
+                .drain(..)
+                .map(|merged_cards| RevokedCards {
+                    card_pseud: merged_cards.card_pseud,
+                    revocation_nonce: merged_cards.revocation_nonce,
+                    revoked_at,
+                }),
+        );
+
+        self.card_id = Some(CardPseud(Id::random()));
+
+        &self.revoked_cards[first..]
+    }
+
+    /// Whether the given attribute value is the registration pseudonym on revoked cards of this
+    /// user.
+    ///
+    /// NOTE: the attribute type is not checked, but registration pseudonyms are random [`Id`]s
+    /// that will not be the value of any other attribute.
+    pub(super) fn is_revoked_card(&self, value: &str) -> bool {
+        self.revoked_cards
+            .iter()
+            .any(|rc| rc.card_pseud.to_string() == value)
+    }
+
+    /// Returns the objects stored by this user, including the earlier versions kept in
+    /// [`UserState::object_history`].
+    pub(crate) fn object_versions(
+        &self,
+    ) -> impl Iterator<Item = &super::user_object_store::UserObjectDetails> {
+        self.stored_objects.values().chain(
+            self.object_history
+                .values()
+                .flat_map(|history| history.versions.iter()),
+        )
+    }
+
+    /// Whether the user object with the given id is stored by this user, either currently or as
+    /// earlier version.
+    pub(crate) fn refers_to_object(&self, obj_id: Id) -> bool {
+        self.object_versions().any(|uod| uod.id == obj_id)
+    }
+
+    /// Keeps `version`, which was just replaced or deleted, in the [`UserState::object_history`]
+    /// of `handle`, if so requested by the user.  Returns the ids of the objects that are
+    /// discarded as a result.
+    pub(crate) fn keep_in_history(
+        &mut self,
+        handle: &handle::Handle,
+        version: super::user_object_store::UserObjectDetails,
+    ) -> Vec<Id> {
+        match self.object_history.get_mut(handle) {
+            Some(history) => history.push(version),
+            None => vec![version.id],
+        }
+    }
+
     /// Subtract quota usage from the given [`Quota`], returning an error when a [`QuotumName`] was
     /// reached.
     pub(crate) fn update_quota(&self, mut quota: Quota) -> Result<Quota, QuotumName> {
         quota.object_count = quota
             .object_count
-            .checked_sub(self.stored_objects.len().try_into().unwrap_or(u16::MAX))
+            .checked_sub(
+                self.object_versions()
+                    .count()
+                    .try_into()
+                    .unwrap_or(u16::MAX),
+            )
             .ok_or_else(|| {
                 let quotum = QuotumName::ObjectCount;
                 log::warn!("user {} has reached quotum {quotum}", self.id);
                 quotum
             })?;
 
-        for sod in self.stored_objects.values() {
+        for sod in self.object_versions() {
             quota.object_bytes_total =
                 quota
                     .object_bytes_total
@@ -872,9 +1617,30 @@ impl UserState {
         Ok(quota)
     }
 
-    /// Turns this [`UserState`] into a [`ApiUserState`].
-    pub(crate) fn into_user_version(self: UserState, app: &App) -> ApiUserState {
+    /// Returns how much of the [`Quota`] this user uses.
+    pub(crate) fn quota_usage(&self) -> Quota {
+        Quota {
+            object_count: self
+                .object_versions()
+                .count()
+                .try_into()
+                .unwrap_or(u16::MAX),
+            object_bytes_total: self
+                .object_versions()
+                .fold(0u32, |total, sod| total.saturating_add(sod.size)),
+        }
+    }
+
+    /// Turns this [`UserState`] into a [`ApiUserState`], using the given
+    /// [`App::attr_type_handles`].
+    pub(crate) fn into_user_version(
+        self: UserState,
+        app: &App,
+        attr_type_handles: &HashMap<Id, handle::Handle>,
+    ) -> ApiUserState {
         ApiUserState {
+            attrs: self.attrs(attr_type_handles),
+            object_history: self.object_history_user_version(&app.user_object_hmac_secret),
             allow_login_by: self.allow_login_by,
             could_be_banned_by: self.could_be_banned_by,
             stored_objects: self
@@ -884,4 +1650,20 @@ impl UserState {
                 .collect(),
         }
     }
+
+    /// Returns [`UserState::object_history`] in the form of [`ApiUserState::object_history`].
+    pub(crate) fn object_history_user_version(
+        &self,
+        hmac_secret: &[u8],
+    ) -> HashMap<handle::Handle, api::phc::user::ObjectHistory> {
+        self.object_history
+            .iter()
+            .map(|(handle, history)| {
+                (
+                    handle.clone(),
+                    history.clone().into_user_version(hmac_secret),
+                )
+            })
+            .collect()
+    }
 }
@@ -0,0 +1,289 @@
+//! User endpoints for managing one's account, such as [`RemoveIdentifyingAttrEP`], [`ExportEP`]
+//! and [`DeleteEP`]
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use actix_web::web;
+
+use crate::api;
+use crate::attr::AttrState;
+use crate::handle;
+use crate::id::Id;
+
+use super::server::*;
+use super::user::UserState;
+use super::user_object_store::{UserObject, UserObjectDetails};
+use crate::api::phc::user::*;
+
+impl App {
+    /// Implements [`RemoveIdentifyingAttrEP`].
+    ///
+    /// Leaves [`AttrState::may_identify_user`] alone, so that the attribute can not be used to
+    /// register another account.
+    pub(super) async fn handle_user_remove_identifying_attr(
+        app: Rc<Self>,
+        req: web::Json<RemoveIdentifyingAttrReq>,
+        auth_token: actix_web::web::Header<AuthToken>,
+    ) -> api::Result<RemoveIdentifyingAttrResp> {
+        let RemoveIdentifyingAttrReq { attr } = req.into_inner();
+
+        let Ok((mut user_state, user_state_version)) = app
+            .open_auth_token_and_get_user_state(auth_token.into_inner())
+            .await?
+        else {
+            return Ok(RemoveIdentifyingAttrResp::RetryWithNewAuthToken);
+        };
+
+        if !user_state.allow_login_by.contains(&attr) {
+            return Ok(RemoveIdentifyingAttrResp::UnknownAttr);
+        }
+
+        if user_state.allow_login_by.len() == 1 {
+            return Ok(RemoveIdentifyingAttrResp::LastIdentifyingAttr);
+        }
+
+        // NOTE: we remember the attribute, so that it can be detached when the user deletes their
+        // account
+        user_state.allow_login_by.remove(&attr);
+        user_state.removed_identifying_attrs.insert(attr);
+
+        if app
+            .put_object::<UserState>(&user_state, Some(user_state_version))
+            .await?
+            .is_none()
+        {
+            return Err(api::ErrorCode::PleaseRetry);
+        }
+
+        log::debug!(
+            "user {} removed identifying attribute {attr}",
+            user_state.id
+        );
+
+        let attr_type_handles = app.attr_type_handles(&user_state).await?;
+
+        Ok(RemoveIdentifyingAttrResp::Removed(Box::new(
+            user_state.into_user_version(&app, &attr_type_handles),
+        )))
+    }
+
+    /// Implements [`ExportEP`].
+    pub(super) async fn handle_user_export(
+        app: Rc<Self>,
+        auth_token: actix_web::web::Header<AuthToken>,
+    ) -> api::Result<ExportResp> {
+        let Ok((user_state, _)) = app
+            .open_auth_token_and_get_user_state(auth_token.into_inner())
+            .await?
+        else {
+            return Ok(ExportResp::RetryWithNewAuthToken);
+        };
+
+        // Bound the size of the export, which is assembled in memory, before retrieving objects
+        let quota_usage = user_state.quota_usage();
+
+        if quota_usage.object_bytes_total > app.quota.object_bytes_total {
+            log::debug!(
+                "refusing to export user {}: their objects exceed the quota",
+                user_state.id
+            );
+            return Ok(ExportResp::QuotumReached(QuotumName::ObjectBytesTotal));
+        }
+
+        let attr_type_handles = app.attr_type_handles(&user_state).await?;
+
+        let mut attrs: HashMap<Id, ExportedAttrState> = Default::default();
+
+        for attr_id in user_state
+            .allow_login_by
+            .iter()
+            .chain(user_state.could_be_banned_by.iter())
+        {
+            if attrs.contains_key(attr_id) {
+                continue;
+            }
+
+            let Some((attr_state, _)) = app.get_object::<AttrState>(attr_id).await? else {
+                log::warn!(
+                    "attribute {attr_id} of user {} has no attribute state",
+                    user_state.id
+                );
+                continue;
+            };
+
+            let details = user_state.attr_details.get(attr_id);
+
+            // NOTE: we do not include the other users identified or banned by this attribute
+            attrs.insert(
+                *attr_id,
+                ExportedAttrState {
+                    banned: attr_state.banned,
+                    identifies_user: attr_state.may_identify_user == Some(user_state.id),
+                    bans_user: attr_state.bans_users.contains(&user_state.id),
+                    attr_type: details
+                        .and_then(|details| attr_type_handles.get(&details.attr_type).cloned()),
+                    added_at: details.map(|details| details.added_at),
+                },
+            );
+        }
+
+        let mut stored_objects: HashMap<handle::Handle, ExportedObject> =
+            HashMap::with_capacity(user_state.stored_objects.len());
+
+        for (handle, obj_details) in user_state.stored_objects.iter() {
+            stored_objects.insert(
+                handle.clone(),
+                app.export_user_object(&user_state, obj_details).await?,
+            );
+        }
+
+        let mut object_history: HashMap<handle::Handle, Vec<ExportedObject>> =
+            HashMap::with_capacity(user_state.object_history.len());
+
+        for (handle, history) in user_state.object_history.iter() {
+            let mut versions: Vec<ExportedObject> = Vec::with_capacity(history.versions.len());
+
+            for obj_details in history.versions.iter() {
+                versions.push(app.export_user_object(&user_state, obj_details).await?);
+            }
+
+            object_history.insert(handle.clone(), versions);
+        }
+
+        Ok(ExportResp::Success(Box::new(UserExport {
+            id: user_state.id,
+            card_pseud: user_state.card_id(),
+            merged_card_pseuds: user_state
+                .merged_cards
+                .iter()
+                .map(|merged_cards| merged_cards.card_pseud)
+                .collect(),
+            registration_date: user_state.registration_date,
+            banned: user_state.banned,
+            allow_login_by: user_state.allow_login_by,
+            could_be_banned_by: user_state.could_be_banned_by,
+            attrs,
+            stored_objects,
+            object_history,
+            quota_usage,
+            quota: app.quota.clone(),
+        })))
+    }
+
+    /// Retrieves the given object of the user for [`Self::handle_user_export`].
+    async fn export_user_object(
+        &self,
+        user_state: &UserState,
+        obj_details: &UserObjectDetails,
+    ) -> api::Result<ExportedObject> {
+        let Some(contents) = self.get_user_object(obj_details.id).await? else {
+            // the object was probably replaced after we retrieved `user_state`
+            log::debug!(
+                "user object {} of user {} disappeared during export",
+                obj_details.id,
+                user_state.id
+            );
+            return Err(api::ErrorCode::PleaseRetry);
+        };
+
+        if contents.len() as u64 > u64::from(obj_details.size) {
+            // `UserState::quota_usage` relies on `size`, so this should not happen
+            log::error!(
+                "bug: user object {} of user {} is larger than recorded",
+                obj_details.id,
+                user_state.id
+            );
+            return Err(api::ErrorCode::InternalError);
+        }
+
+        Ok(ExportedObject {
+            hash: obj_details.id,
+            contents: serde_bytes::ByteBuf::from(contents.to_vec()).into(),
+        })
+    }
+
+    /// Implements [`DeleteEP`].
+    ///
+    /// # Note on the implementation
+    ///
+    /// To delete a user account we:
+    ///   (1) Detach the identifying attributes (including those removed via
+    ///       [`RemoveIdentifyingAttrEP`]) from the [`UserState`];
+    ///   (2) Delete the user's [`UserObject`]s, including the earlier versions; and
+    ///   (3) Mark the [`UserState`] as deleted, forgetting about the identifying attributes and
+    ///       stored objects.
+    ///
+    /// Since the auth token remains valid until step (3) succeeds, the client can simply retry
+    /// when the process fails halfway.  The attributes that could ban the user are left alone, so
+    /// that the (deleted) account can still be banned.
+    pub(super) async fn handle_user_delete(
+        app: Rc<Self>,
+        auth_token: actix_web::web::Header<AuthToken>,
+    ) -> api::Result<DeleteResp> {
+        let Ok((mut user_state, user_state_version)) = app
+            .open_auth_token_and_get_user_state(auth_token.into_inner())
+            .await?
+        else {
+            return Ok(DeleteResp::RetryWithNewAuthToken);
+        };
+
+        // includes the identifying attributes removed via `RemoveIdentifyingAttrEP`
+        for attr_id in user_state.identifying_attrs_ever() {
+            let Some((mut attr_state, attr_state_version)) =
+                app.get_object::<AttrState>(attr_id).await?
+            else {
+                continue;
+            };
+
+            if attr_state.may_identify_user != Some(user_state.id) {
+                continue;
+            }
+
+            attr_state.may_identify_user = None;
+
+            if app
+                .put_object::<AttrState>(&attr_state, Some(attr_state_version))
+                .await?
+                .is_none()
+            {
+                log::debug!(
+                    "attribute {attr_id} changed while deleting user {}",
+                    user_state.id
+                );
+                return Err(api::ErrorCode::PleaseRetry);
+            }
+        }
+
+        for obj_details in user_state.object_versions() {
+            if !app.delete_object::<UserObject>(obj_details.id).await? {
+                log::debug!(
+                    "user object {} of user {} was already gone",
+                    obj_details.id,
+                    user_state.id
+                );
+            }
+        }
+
+        user_state.allow_login_by.clear();
+        user_state.removed_identifying_attrs.clear();
+        user_state
+            .attr_details
+            .retain(|attr_id, _| user_state.could_be_banned_by.contains(attr_id));
+        user_state.stored_objects.clear();
+        user_state.object_history.clear();
+        user_state.deleted = Some(api::NumericDate::now());
+
+        if app
+            .put_object::<UserState>(&user_state, Some(user_state_version))
+            .await?
+            .is_none()
+        {
+            // someone else is changing `user_state` too, for example by storing an object
+            return Err(api::ErrorCode::PleaseRetry);
+        }
+
+        log::debug!("deleted user account {}", user_state.id);
+
+        Ok(DeleteResp::Deleted)
+    }
+}
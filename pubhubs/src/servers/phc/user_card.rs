@@ -4,6 +4,7 @@ use crate::api;
 use std::rc::Rc;
 
 use super::server::*;
+use super::user::UserState;
 use crate::api::phc::user::*;
 
 impl App {
@@ -12,20 +13,36 @@ impl App {
         app: Rc<Self>,
         auth_token: actix_web::web::Header<AuthToken>,
     ) -> api::Result<CardPseudResp> {
-        let Ok((user_state, _)) = app
+        let Ok((mut user_state, version)) = app
             .open_auth_token_and_get_user_state(auth_token.into_inner())
             .await?
         else {
             return Ok(CardPseudResp::RetryWithNewAuthToken);
         };
 
+        let (revocation_nonce, generated) = user_state.card_revocation_nonce();
+
+        if generated
+            && app
+                .put_object::<UserState>(&user_state, Some(version))
+                .await?
+                .is_none()
+        {
+            log::debug!(
+                "user state {} changed while setting card revocation nonce",
+                user_state.id
+            );
+            return Err(api::ErrorCode::PleaseRetry);
+        }
+
         log::debug!("user {} retrieved registration pseudonym", user_state.id);
 
         Ok(CardPseudResp::Success(api::Signed::new(
-            &app.shared.signing_key,
+            app.shared.active_signing_key(),
             &CardPseudPackage {
                 card_pseud: user_state.card_id(),
                 registration_date: user_state.registration_date,
+                revocation_nonce: Some(revocation_nonce),
             },
             app.card_pseud_validity,
         )?))
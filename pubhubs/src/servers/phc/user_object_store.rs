@@ -49,7 +49,8 @@ impl App {
     /// To add a user object we:
     ///   (1) First add the [`UserObject`] to the object store, if its not there already;
     ///   (2) Add a reference to that object in [`UserState`]; and
-    ///   (3) Delete the old [`UserObject`] object, if there is any.
+    ///   (3) Delete the old [`UserObject`] object, if there is any, unless it is kept in the
+    ///       [`ObjectHistory`].
     ///
     /// This way, if the process fails between steps (1) and (2), the client will simply retry,
     /// and if the process fails between steps (2) and (3) we are only left with an orphaned
@@ -61,21 +62,12 @@ impl App {
         overwrite_hash: Option<Id>,
         auth_token: AuthToken,
     ) -> api::Result<StoreObjectResp> {
-        let user_id = if let Ok(user_id) = self.open_auth_token(auth_token) {
-            user_id
-        } else {
+        let Ok((mut user_state, user_state_version)) =
+            self.open_auth_token_and_get_user_state(auth_token).await?
+        else {
             return Ok(StoreObjectResp::RetryWithNewAuthToken);
         };
-
-        let (mut user_state, user_state_version) = self
-            .get_object::<UserState>(&user_id)
-            .await?
-            .ok_or_else(|| {
-                log::error!(
-                    "auth token refers to non- (or no longer) existing user with id {user_id}",
-                );
-                api::ErrorCode::InternalError
-            })?;
+        let user_id = user_state.id;
 
         let obj = UserObject::new(payload, user_id);
 
@@ -108,14 +100,19 @@ impl App {
         };
 
         // modify `user_state` locally to check quotum
-        let mut existing_object_id: Option<Id> = None;
+        let mut existing_object: Option<UserObjectDetails> = None;
 
         user_state
             .stored_objects
-            .entry(handle)
-            .and_modify(|e| existing_object_id = Some(e.id))
+            .entry(handle.clone())
+            .and_modify(|e| existing_object = Some(e.clone()))
             .insert_entry(object_details.clone());
 
+        let discarded_objects: Vec<Id> = match existing_object {
+            Some(existing_object) => user_state.keep_in_history(&handle, existing_object),
+            None => vec![],
+        };
+
         // check quota
         let _quota = match user_state.update_quota(self.quota.clone()) {
             Ok(quota) => quota,
@@ -131,7 +128,7 @@ impl App {
         }
 
         if self
-            .put_object(&user_state, Some(user_state_version))
+            .put_object::<UserState>(&user_state, Some(user_state_version))
             .await?
             .is_none()
         {
@@ -140,28 +137,144 @@ impl App {
         }
 
         // remove previous object, if there is any
-        if let Some(existing_object_id) = existing_object_id {
-            match self.delete_object::<UserObject>(existing_object_id).await {
+        self.delete_unreferenced_user_objects(&user_state, discarded_objects)
+            .await;
+
+        Ok(StoreObjectResp::Stored {
+            stored_objects: user_state
+                .stored_objects
+                .into_iter()
+                .map(|(handle, uod)| (handle, uod.into_user_version(&self.user_object_hmac_secret)))
+                .collect(),
+        })
+    }
+
+    /// Implements [`DeleteObjectEP`].
+    ///
+    /// Like [`Self::handle_user_store_object`], we first update the [`UserState`], and only then
+    /// delete the [`UserObject`].
+    pub(super) async fn handle_user_delete_object(
+        app: Rc<Self>,
+        path: actix_web::web::Path<(handle::Handle, Id)>,
+        actix_web::web::Header(auth_token): actix_web::web::Header<AuthToken>,
+    ) -> api::Result<DeleteObjectResp> {
+        let (handle, hash) = path.into_inner();
+
+        let Ok((mut user_state, user_state_version)) =
+            app.open_auth_token_and_get_user_state(auth_token).await?
+        else {
+            return Ok(DeleteObjectResp::RetryWithNewAuthToken);
+        };
+
+        let Some(existing_object) = user_state.stored_objects.remove(&handle) else {
+            return Ok(DeleteObjectResp::NotFound);
+        };
+
+        if existing_object.id != hash {
+            return Ok(DeleteObjectResp::HashDidNotMatch);
+        }
+
+        let discarded_objects = user_state.keep_in_history(&handle, existing_object);
+
+        if app
+            .put_object::<UserState>(&user_state, Some(user_state_version))
+            .await?
+            .is_none()
+        {
+            // someone else is changing `user_state` too
+            return Ok(DeleteObjectResp::PleaseRetry);
+        }
+
+        app.delete_unreferenced_user_objects(&user_state, discarded_objects)
+            .await;
+
+        Ok(DeleteObjectResp::Deleted {
+            stored_objects: user_state
+                .stored_objects
+                .into_iter()
+                .map(|(handle, uod)| (handle, uod.into_user_version(&app.user_object_hmac_secret)))
+                .collect(),
+        })
+    }
+
+    /// Implements [`SetObjectHistoryEP`].
+    pub(super) async fn handle_user_set_object_history(
+        app: Rc<Self>,
+        path: actix_web::web::Path<(handle::Handle,)>,
+        req: actix_web::web::Json<SetObjectHistoryReq>,
+        actix_web::web::Header(auth_token): actix_web::web::Header<AuthToken>,
+    ) -> api::Result<SetObjectHistoryResp> {
+        let (handle,) = path.into_inner();
+        let SetObjectHistoryReq { keep } = req.into_inner();
+
+        if keep > SetObjectHistoryReq::MAX_KEEP {
+            log::debug!(
+                "user requested to keep {keep} > {} versions of an object",
+                SetObjectHistoryReq::MAX_KEEP
+            );
+            return Err(api::ErrorCode::BadRequest);
+        }
+
+        let Ok((mut user_state, user_state_version)) =
+            app.open_auth_token_and_get_user_state(auth_token).await?
+        else {
+            return Ok(SetObjectHistoryResp::RetryWithNewAuthToken);
+        };
+
+        let discarded_objects: Vec<Id> = if keep == 0 {
+            user_state
+                .object_history
+                .remove(&handle)
+                .map(|history| history.versions.into_iter().map(|uod| uod.id).collect())
+                .unwrap_or_default()
+        } else {
+            let history = user_state.object_history.entry(handle).or_default();
+            history.keep = keep;
+            history.truncate()
+        };
+
+        if app
+            .put_object::<UserState>(&user_state, Some(user_state_version))
+            .await?
+            .is_none()
+        {
+            return Ok(SetObjectHistoryResp::PleaseRetry);
+        }
+
+        app.delete_unreferenced_user_objects(&user_state, discarded_objects)
+            .await;
+
+        Ok(SetObjectHistoryResp::Success {
+            object_history: user_state.object_history_user_version(&app.user_object_hmac_secret),
+        })
+    }
+
+    /// Deletes those of the given [`UserObject`]s that are no longer referred to by
+    /// `user_state`, see [`UserState::refers_to_object`].  Failures are only logged, since they
+    /// just leave orphaned objects.
+    pub(super) async fn delete_unreferenced_user_objects(
+        &self,
+        user_state: &UserState,
+        obj_ids: impl IntoIterator<Item = Id>,
+    ) {
+        for obj_id in obj_ids {
+            if user_state.refers_to_object(obj_id) {
+                continue;
+            }
+
+            match self.delete_object::<UserObject>(obj_id).await {
                 Err(err) => {
                     log::warn!(
-                        "failed to delete user object {existing_object_id} that is replaced by {}: {err:#}",
-                        obj.object_id
+                        "failed to delete user object {obj_id} discarded by user {}: {err:#}",
+                        user_state.id
                     );
                 }
                 Ok(false) => {
-                    log::warn!("expected to delete {existing_object_id}, but it is already gone");
+                    log::warn!("expected to delete {obj_id}, but it is already gone");
                 }
                 Ok(true) => { /* ok */ }
             }
         }
-
-        Ok(StoreObjectResp::Stored {
-            stored_objects: user_state
-                .stored_objects
-                .into_iter()
-                .map(|(handle, uod)| (handle, uod.into_user_version(&self.user_object_hmac_secret)))
-                .collect(),
-        })
     }
 
     /// Implements [`GetObjectEP`].
@@ -175,15 +288,20 @@ impl App {
             return api::Payload::Json(Ok(GetObjectResp::RetryWithNewHmac));
         }
 
-        let (obj, _) = match app.get_object::<UserObject>(&hash).await {
-            Ok(Some(obj)) => obj,
+        match app.get_user_object(hash).await {
+            Ok(Some(payload)) => api::Payload::Octets(payload),
             Ok(None) => {
                 log::debug!("user object {hash} was requested (with valid hmac), but not found");
-                return api::Payload::Json(Ok(GetObjectResp::NotFound));
-            }
-            Err(err) => {
-                return api::Payload::Json(Err(err));
+                api::Payload::Json(Ok(GetObjectResp::NotFound))
             }
+            Err(err) => api::Payload::Json(Err(err)),
+        }
+    }
+
+    /// Retrieves the contents of the user object with the given `hash`, checking its integrity.
+    pub(super) async fn get_user_object(&self, hash: Id) -> api::Result<Option<bytes::Bytes>> {
+        let Some((obj, _)) = self.get_object::<UserObject>(&hash).await? else {
+            return Ok(None);
         };
 
         if obj.object_id != hash {
@@ -192,10 +310,10 @@ impl App {
                 hash,
                 obj.user_id
             );
-            return api::Payload::Json(Err(api::ErrorCode::InternalError));
+            return Err(api::ErrorCode::InternalError);
         }
 
-        api::Payload::Octets(obj.payload)
+        Ok(Some(obj.payload))
     }
 }
 
@@ -293,6 +411,50 @@ pub struct UserObjectDetails {
     pub id: Id,
 }
 
+/// Earlier versions of an object stored by a user, see [`UserState::object_history`].
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ObjectHistory {
+    /// Number of versions to keep, see [`SetObjectHistoryReq::keep`]
+    pub keep: u8,
+
+    /// The earlier versions, most recent first
+    pub versions: Vec<UserObjectDetails>,
+}
+
+impl ObjectHistory {
+    /// Adds `version` as the most recent version, returning the ids of the versions that
+    /// no longer fit.
+    pub(crate) fn push(&mut self, version: UserObjectDetails) -> Vec<Id> {
+        self.versions.insert(0, version);
+        self.truncate()
+    }
+
+    /// Drops the versions beyond [`ObjectHistory::keep`], returning their ids.
+    pub(crate) fn truncate(&mut self) -> Vec<Id> {
+        if self.versions.len() <= self.keep as usize {
+            return vec![];
+        }
+
+        self.versions
+            .split_off(self.keep as usize)
+            .into_iter()
+            .map(|uod| uod.id)
+            .collect()
+    }
+
+    /// Turns this [`ObjectHistory`] into a [`api::phc::user::ObjectHistory`].
+    pub(crate) fn into_user_version(self, hmac_secret: &[u8]) -> api::phc::user::ObjectHistory {
+        api::phc::user::ObjectHistory {
+            keep: self.keep,
+            versions: self
+                .versions
+                .into_iter()
+                .map(|uod| uod.into_user_version(hmac_secret))
+                .collect(),
+        }
+    }
+}
+
 impl UserObjectDetails {
     /// Turns this [`UserObjectDetails`] into a [`api::phc::user::UserObjectDetails`].
     pub(crate) fn into_user_version(self, hmac_secret: &[u8]) -> api::phc::user::UserObjectDetails {
@@ -1,14 +1,16 @@
 //! User endpoints for entering hubs
 use std::rc::Rc;
 
-use curve25519_dalek::RistrettoPoint;
+use curve25519_dalek::{RistrettoPoint, traits::Identity as _};
 
 use crate::api;
+use crate::common::elgamal;
 use crate::id;
 
 use serde::{Deserialize, Serialize};
 
 use super::server::*;
+use super::user::UserState;
 use crate::api::phc::user::*;
 use crate::api::sso::*;
 
@@ -20,26 +22,54 @@ impl App {
     ) -> api::Result<PppResp> {
         let running_state = app.running_state_or_please_retry()?;
 
-        let Ok((user_state, _)) = app
+        let Ok((mut user_state, version)) = app
             .open_auth_token_and_get_user_state(auth_token.into_inner())
             .await?
         else {
             return Ok(PppResp::RetryWithNewAuthToken);
         };
 
+        // Until the master encryption key is derived, we do not know whether a master encryption
+        // key part is being rotated, and so whether the polymorphic pseudonym must be re-keyed.
+        if running_state.master_enc_key.is_none() {
+            log::info!("cannot hand out a PPP yet: master encryption key not available");
+            return Err(api::ErrorCode::PleaseRetry);
+        }
+
+        // While a master encryption key part is being rotated, re-key the user's polymorphic
+        // pseudonym before handing it out, so the transcryptor need not fall back on its previous
+        // master encryption key part.
+        if let Some(rekey) = running_state.master_enc_rekey.as_ref()
+            && rekey.rekey(&mut user_state.polymorphic_pseudonym)
+            && app
+                .put_object::<UserState>(&user_state, Some(version))
+                .await?
+                .is_none()
+        {
+            // The re-keyed polymorphic pseudonym is still valid, so hand it out anyway;  the user
+            // state will be re-keyed later by `App::rekey_sweep` or the next PPP request.
+            log::debug!(
+                "user state of {} changed while re-keying its polymorphic pseudonym",
+                user_state.id
+            );
+        }
+
         let now = api::NumericDate::now();
 
+        // we make sure to rerandomize the polymorphic pseudonym so the transcryptor cannot
+        // track the user based on it
+        let polymorphic_pseudonym = user_state.polymorphic_pseudonym.rerandomize();
+
         let nonce_inner = PpNonceInner {
             user_id: user_state.id,
             not_valid_after: now.add_clamp(app.pp_nonce_validity.as_secs()),
             issued_at: now,
+            polymorphic_pseudonym: polymorphic_pseudonym.clone(),
         };
 
         Ok(PppResp::Success(api::Sealed::new(
             &PolymorphicPseudonymPackage {
-                // we make sure to rerandomize the polymorphic pseudonym so the transcryptor cannot
-                // track the user based on it
-                polymorphic_pseudonym: user_state.polymorphic_pseudonym.rerandomize(),
+                polymorphic_pseudonym,
                 nonce: api::Sealed::new(&nonce_inner, &app.pp_nonce_secret)?.into(),
             },
             &running_state.t_sealing_secret,
@@ -66,7 +96,9 @@ impl App {
             hub_nonce,
             phc_nonce,
             hub_id_mac,
-        }) = req.ehpp.open(&running_state.t_sealing_secret)
+            mut previous_encrypted_hub_pseudonyms,
+            transcryption_proof,
+        }) = req.ehpp.open(running_state.ehpp_sealing_secret())
         else {
             log::debug!("invalid Ehpp submitted to Hhpp endpoint");
             return Ok(HhppResp::RetryWithNewPpp);
@@ -76,6 +108,7 @@ impl App {
             user_id: phc_nonce_user_id,
             issued_at: pp_issued_at,
             not_valid_after,
+            polymorphic_pseudonym,
         }) = api::Sealed::<PpNonceInner>::from(phc_nonce).open(&app.pp_nonce_secret)
         else {
             log::info!("Ehpp containing invalid PHC nonce submitted to Hhpp endpoint");
@@ -93,32 +126,46 @@ impl App {
             return Ok(HhppResp::RetryWithNewPpp);
         }
 
-        let Some(hub_pseudonym) =
-            encrypted_hub_pseudonym.decrypt_and_check_pk(&app.master_enc_key_part)
-        else {
-            log::warn!("hub pseudonym was encrypted for the wrong public key");
+        // Don't take the transcryptor's word for it, see "Proofs of correct transcryption" in
+        // the `api::sso` module.
+        if !transcryption_proof.verify(&polymorphic_pseudonym, &encrypted_hub_pseudonym) {
+            log::error!("transcryptor returned an encrypted hub pseudonym with an invalid proof");
             return Err(api::ErrorCode::InternalError);
-            // Internal error, because the encrypted_hub_pseudonym is guaranteed to be
-            // generated by the transcryptor, and the master encryption key should not have changed
-            // if the sealing secret is still valid.
-        };
+        }
 
-        let hashed_hub_pseudonym: api::CurvePoint =
-            RistrettoPoint::hash_from_bytes::<sha2::Sha512>(hub_pseudonym.compress().as_bytes())
-                .compress()
-                .into();
+        if !previous_encrypted_hub_pseudonyms
+            .iter()
+            .all(|previous| previous.verify(&polymorphic_pseudonym, &transcryption_proof))
+        {
+            log::error!(
+                "transcryptor returned a previous encrypted hub pseudonym with an invalid proof"
+            );
+            return Err(api::ErrorCode::InternalError);
+        }
+
+        let hashed_hub_pseudonym = app.decrypt_and_hash_hub_pseudonym(encrypted_hub_pseudonym)?;
+
+        previous_encrypted_hub_pseudonyms
+            .sort_by_key(|previous| std::cmp::Reverse(previous.version));
+
+        let previous_hashed_hub_pseudonyms = previous_encrypted_hub_pseudonyms
+            .into_iter()
+            .map(|previous| app.decrypt_and_hash_hub_pseudonym(previous.pseudonym))
+            .collect::<api::Result<Vec<_>>>()?;
 
         let hhpp = HashedHubPseudonymPackage {
             hashed_hub_pseudonym,
             pp_issued_at,
             hub_nonce,
             hub_id_mac,
+            previous_hashed_hub_pseudonyms,
         };
 
         // Sign with the key the hub can verify (see `HhppSignatureScheme`): the ed25519 component
         // for pre-hybrid hubs, the hybrid composite for updated ones.  Validity reuses
         // `pp_nonce_validity` (TODO: a dedicated config field for the HHPP's validity?).
         let signed = match hhpp_signature_scheme {
+            // (pre-hybrid hubs only know the ed25519 half of our current key, not of our next key)
             HhppSignatureScheme::Ed25519 => api::Signed::new_opts(
                 app.shared.signing_key.ed25519_signing_key(),
                 &hhpp,
@@ -126,7 +173,7 @@ impl App {
                 Some(&running_state.constellation),
             )?,
             HhppSignatureScheme::HybridInterim => api::Signed::new_opts(
-                &app.shared.signing_key,
+                app.shared.active_signing_key(),
                 &hhpp,
                 app.pp_nonce_validity,
                 Some(&running_state.constellation),
@@ -141,6 +188,40 @@ impl App {
 
         Ok(HhppResp::Success(signed))
     }
+
+    /// Decrypts the given hub pseudonym, encrypted by the transcryptor for `x_PHC`, and hashes it
+    /// for the [`HashedHubPseudonymPackage`].
+    fn decrypt_and_hash_hub_pseudonym(
+        &self,
+        encrypted_hub_pseudonym: elgamal::Triple,
+    ) -> api::Result<api::CurvePoint> {
+        // NB. Also while PHC's master encryption key part is being rotated, the hub pseudonym is
+        // encrypted for the current part: PHC re-keys every polymorphic pseudonym it hands out
+        // (see `handle_user_ppp`), and the PPPs handed out before PHC was restarted with the new
+        // part are sealed for a shared secret the transcryptor no longer accepts.
+        let Some(hub_pseudonym) =
+            encrypted_hub_pseudonym.decrypt_and_check_pk(&self.master_enc_key_part)
+        else {
+            log::warn!("hub pseudonym was encrypted for the wrong public key");
+            return Err(api::ErrorCode::InternalError);
+            // Internal error, because the encrypted_hub_pseudonym is guaranteed to be
+            // generated by the transcryptor, and the master encryption key should not have changed
+            // if the sealing secret is still valid.
+        };
+
+        // The transcryption proof does not rule out that the transcryptor multiplied by zero,
+        // which would give every user the same hub pseudonym.
+        if hub_pseudonym == RistrettoPoint::identity() {
+            log::error!("transcryptor returned the encryption of the zero hub pseudonym");
+            return Err(api::ErrorCode::InternalError);
+        }
+
+        Ok(
+            RistrettoPoint::hash_from_bytes::<sha2::Sha512>(hub_pseudonym.compress().as_bytes())
+                .compress()
+                .into(),
+        )
+    }
 }
 
 /// The contents of a [`PpNonce`].
@@ -154,6 +235,9 @@ struct PpNonceInner {
 
     /// The [`id::Id`] of the user requesting this [`PolymorphicPseudonymPackage`].
     user_id: id::Id,
+
+    /// The polymorphic pseudonym handed out, against which the [`TranscryptionProof`] is checked.
+    polymorphic_pseudonym: elgamal::Triple,
 }
 
 api::having_message_code!(PpNonceInner, PpNonce);
@@ -605,7 +605,7 @@ pub trait App<S: Server>: Deref<Target = AppBase<S>> + 'static {
         );
 
         // NOTE: phc_inf has already been (partially) checked
-        let url = phc_inf_constellation.url(S::NAME);
+        let url = self.own_url(&phc_inf_constellation);
 
         // obtain DiscoveryInfo from oneself
         let di = self
@@ -630,6 +630,12 @@ pub trait App<S: Server>: Deref<Target = AppBase<S>> + 'static {
         })
     }
 
+    /// Where this server can be reached according to the given constellation.  Overridden by the
+    /// transcryptor, which might be one of the co-transcryptors.
+    fn own_url<'a>(&self, constellation: &'a Constellation) -> &'a url::Url {
+        constellation.url(S::NAME)
+    }
+
     /// Should return the master encryption key part for PHC and the transcryption.
     fn master_enc_key_part(&self) -> Option<&elgamal::PrivateKey> {
         if matches!(S::NAME, Name::PubhubsCentral | Name::Transcryptor) {
@@ -638,6 +644,12 @@ pub trait App<S: Server>: Deref<Target = AppBase<S>> + 'static {
         None
     }
 
+    /// The master encryption key part this server used before the current one, while it is being
+    /// rotated.  Only overridden by PHC and the transcryptor.
+    fn previous_master_enc_key_part(&self) -> Option<&elgamal::PrivateKey> {
+        None
+    }
+
     /// This server's published [`kem::EncapKeyBytes`], if any.  Overridden by T/AS.
     fn encap_key(&self) -> Option<&kem::EncapKeyBytes> {
         None
@@ -714,6 +726,25 @@ where
         // `check_constellation`.
         let verifying_key_bytes = signing_key.verifying_key().encode();
 
+        let next_signing_key = server_config
+            .next_signing_key
+            .as_ref()
+            .map(|nsk| -> Result<_> {
+                let signing_key = nsk
+                    .signing_key
+                    .decode()
+                    .map_err(|_| anyhow::anyhow!("invalid next_signing_key in config"))?;
+
+                Ok(NextSigningKey {
+                    next_verifying_key: api::NextVerifyingKey {
+                        verifying_key: signing_key.verifying_key().encode(),
+                        active_from: nsk.active_from,
+                    },
+                    signing_key,
+                })
+            })
+            .transpose()?;
+
         let admin_key = crate::misc::jwt::HS256(
             server_config
                 .admin_key
@@ -745,6 +776,7 @@ where
                     .with_context(|| format!("Creating object store for {}", S::NAME))?,
                 signing_key,
                 verifying_key_bytes,
+                next_signing_key,
                 extra: S::create_extra_shared_state(config)?,
             }),
             version: server_config.version.clone(),
@@ -1055,14 +1087,59 @@ impl<S: Server> AppBase<S> {
             None
         };
 
+        // When the transcryptor role is split, the co-transcryptors need to be convinced that the
+        // transcryptor knows its master encryption key part's discrete logarithm.
+        let master_enc_key_part_proof = if matches!(S::NAME, Name::Transcryptor)
+            && let Some(rs) = app.running_state.as_ref()
+            && !rs.constellation.co_transcryptors.is_empty()
+            && let Some(sk) = app.master_enc_key_part()
+        {
+            Some(sk.scale_proven(&elgamal::PublicKey::generator()).1)
+        } else {
+            None
+        };
+
+        // While rotating its master encryption key part, the transcryptor publishes the same for its
+        // previous part, together with the factor PHC needs to re-key the polymorphic pseudonyms.
+        let previous_master_enc_key_part_hash = if matches!(S::NAME, Name::Transcryptor)
+            && let Some(sk) = app.previous_master_enc_key_part()
+        {
+            Some(crate::phcrypto::master_enc_key_part_hash(sk.public_key()))
+        } else {
+            None
+        };
+        let previous_master_enc_key_part_sealed = if matches!(S::NAME, Name::Transcryptor)
+            && let Some(previous_sk) = app.previous_master_enc_key_part()
+            && let Some(sk) = app.master_enc_key_part()
+            && let Some(key) = app.master_enc_key_part_sealing_key()
+        {
+            Some(api::Sealed::new(
+                &api::PreviousMasterEncKeyPart {
+                    part: previous_sk.public_key().clone(),
+                    rekey_factor: crate::phcrypto::master_enc_key_part_rekey_factor(
+                        previous_sk,
+                        sk,
+                    )
+                    .into(),
+                },
+                key,
+            )?)
+        } else {
+            None
+        };
+
         Ok(api::DiscoveryInfoResp {
             name: S::NAME,
             version: app.version.clone(),
             self_check_code: app.self_check_code.clone(),
             phc_url: app.phc_url.clone(),
             verifying_key: app.shared.verifying_key_bytes.clone(),
+            next_verifying_key: app.shared.next_verifying_key().cloned(),
             master_enc_key_part_hash,
             master_enc_key_part_sealed,
+            master_enc_key_part_proof,
+            previous_master_enc_key_part_hash,
+            previous_master_enc_key_part_sealed,
             encap_key: app.encap_key().cloned(),
             constellation_or_id,
         })
@@ -1150,6 +1227,44 @@ factory_tuple! { A B C D E F G H I J K L M N }
 factory_tuple! { A B C D E F G H I J K L M N O }
 factory_tuple! { A B C D E F G H I J K L M N O P }
 
+/// Caches the [`api::phc::hub::RevocationsResp`] retrieved from PHC, for the servers other than
+/// PHC that accept [`api::phc::hub::TicketSigned`] requests.
+#[derive(Default)]
+pub struct RevocationsCache {
+    /// The last [`api::phc::hub::RevocationsResp`] retrieved from PHC, and when
+    inner: std::cell::RefCell<Option<(std::time::Instant, Rc<api::phc::hub::RevocationsResp>)>>,
+}
+
+impl RevocationsCache {
+    /// How long a retrieved [`api::phc::hub::RevocationsResp`] is used before retrieving it again.
+    const MAX_AGE: std::time::Duration = std::time::Duration::from_secs(10);
+
+    /// Returns the hubs suspended and tickets revoked by PHC, as retrieved at most
+    /// [`Self::MAX_AGE`] ago.
+    pub async fn get<S: Server>(
+        &self,
+        app: &AppBase<S>,
+    ) -> api::Result<Rc<api::phc::hub::RevocationsResp>> {
+        if let Some((retrieved_at, revocations)) = self.inner.borrow().as_ref()
+            && retrieved_at.elapsed() < Self::MAX_AGE
+        {
+            return Ok(revocations.clone());
+        }
+
+        let revocations = Rc::new(
+            app.client
+                .query::<api::phc::hub::RevocationsEP>(&app.phc_url, api::NoPayload)
+                .await
+                .into_server_result()?,
+        );
+
+        self.inner
+            .replace(Some((std::time::Instant::now(), revocations.clone())));
+
+        Ok(revocations)
+    }
+}
+
 /// Additional state when discovery has been completed.  Derefs to `Extra`.
 #[derive(Clone, Debug)]
 pub struct RunningState<Extra: Clone + core::fmt::Debug> {
@@ -1157,7 +1272,10 @@ pub struct RunningState<Extra: Clone + core::fmt::Debug> {
 
     /// PHC's hybrid verifying key, decoded once here from `constellation.phc_verifying_key` rather
     /// than re-parsing the ML-DSA key on every request that opens a PHC-signed ticket or package.
-    pub phc_verifying_key: api::VerifyingKey,
+    ///
+    /// Includes `constellation.phc_next_verifying_key`, if any, so that tickets and packages signed
+    /// using PHC's next signing key are accepted too.
+    pub phc_verifying_key: api::RotatingVerifyingKey,
 
     /// Accessible via [`Deref`].
     extra: Extra,
@@ -1170,6 +1288,20 @@ impl<Extra: Clone + core::fmt::Debug> RunningState<Extra> {
             .decode()
             .map_err(|_| anyhow::anyhow!("constellation's phc_verifying_key does not decode"))?;
 
+        let phc_next_verifying_key = constellation
+            .phc_next_verifying_key
+            .as_ref()
+            .map(|nvk| nvk.verifying_key.decode())
+            .transpose()
+            .map_err(|_| {
+                anyhow::anyhow!("constellation's phc_next_verifying_key does not decode")
+            })?;
+
+        let phc_verifying_key = api::RotatingVerifyingKey {
+            current: phc_verifying_key,
+            next: phc_next_verifying_key,
+        };
+
         Ok(RunningState {
             constellation: Box::new(constellation),
             phc_verifying_key,
@@ -1228,9 +1360,42 @@ pub struct SharedStateInner<S: Server> {
     /// `check_constellation`.
     pub verifying_key_bytes: api::VerifyingKeyBytes,
 
+    /// See [`crate::servers::config::ServerConfig::next_signing_key`].
+    next_signing_key: Option<NextSigningKey>,
+
     pub extra: S::ExtraSharedState,
 }
 
+/// Decoded [`crate::servers::config::NextSigningKey`].
+struct NextSigningKey {
+    signing_key: api::SigningKey,
+
+    /// Cached, like [`SharedStateInner::verifying_key_bytes`].
+    next_verifying_key: api::NextVerifyingKey,
+}
+
+impl<S: Server> SharedStateInner<S> {
+    /// The key to sign with: [`Self::signing_key`], unless the next signing key has become active.
+    ///
+    /// (The classical ed25519 HHPP for pre-hybrid hubs is an exception: those hubs only know
+    /// the ed25519 half of [`Self::signing_key`].)
+    pub fn active_signing_key(&self) -> &api::SigningKey {
+        match self.next_signing_key.as_ref() {
+            Some(nsk) if nsk.next_verifying_key.active_from <= api::NumericDate::now() => {
+                &nsk.signing_key
+            }
+            _ => &self.signing_key,
+        }
+    }
+
+    /// The verifying key of the next signing key, if one has been staged.
+    pub fn next_verifying_key(&self) -> Option<&api::NextVerifyingKey> {
+        self.next_signing_key
+            .as_ref()
+            .map(|nsk| &nsk.next_verifying_key)
+    }
+}
+
 impl<S: Server> std::ops::Deref for SharedStateInner<S> {
     type Target = S::ExtraSharedState;
 
@@ -1,5 +1,7 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
+use std::sync::Arc;
 
 use actix_web::web;
 
@@ -10,8 +12,8 @@ use crate::phcrypto;
 use crate::{
     api::{self, EndpointDetails as _},
     servers::{
-        self, AppBase, AppCreatorBase, Constellation, DiscoverVerdict, Handle, Server as _,
-        constellation,
+        self, AppBase, AppCreatorBase, Constellation, DiscoverVerdict, Handle, RevocationsCache,
+        Server as _, constellation,
     },
 };
 
@@ -36,15 +38,49 @@ impl servers::Details for Details {
         constellation: &Constellation,
         _seed: &(),
     ) -> anyhow::Result<Self::ExtraRunningState> {
-        let phc_ss = server
-            .extra()
-            .decap_key
-            .decap(&constellation.transcryptor_ss_encap)
+        // Are we the transcryptor, or one of the co-transcryptors?
+        let co_transcryptor_index =
+            if constellation.transcryptor_encap_key_id == server.encap_key.id() {
+                None
+            } else {
+                Some(
+                    constellation
+                        .co_transcryptors
+                        .iter()
+                        .position(|ct| ct.encap_key == server.encap_key)
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("our encapsulation key is not in the constellation")
+                        })?,
+                )
+            };
+
+        let ss_encap = match co_transcryptor_index {
+            None => &constellation.transcryptor_ss_encap,
+            Some(i) => &constellation.co_transcryptors[i].ss_encap,
+        };
+
+        let decap_key = server.extra().decap_key.clone();
+
+        let phc_ss = decap_key
+            .decap(ss_encap)
             .map_err(|_| anyhow::anyhow!("decapsulating shared secret from PHC failed"))?;
 
+        let next_encap_key = constellation
+            .co_transcryptors
+            .get(co_transcryptor_index.map_or(0, |i| i + 1))
+            .map(|ct| ct.encap_key.decode().map(Arc::new))
+            .transpose()
+            .map_err(|_| {
+                anyhow::anyhow!("decoding the next co-transcryptor's encapsulation key")
+            })?;
+
         Ok(ExtraRunningState {
             phc_sealing_secret: phcrypto::sealing_secret(&phc_ss),
             phc_ss,
+            co_transcryptor_index,
+            next_encap_key,
+            decap_key,
+            scaled_master_enc_key: Default::default(),
         })
     }
 
@@ -60,14 +96,16 @@ impl servers::Details for Details {
             .expect("decap_key was not set nor generated")
             .decode()
             .map_err(|_| anyhow::anyhow!("decoding kem decapsulation key"))?;
-        Ok(ExtraServerState { decap_key })
+        Ok(ExtraServerState {
+            decap_key: Arc::new(decap_key),
+        })
     }
 }
 
 pub struct ExtraSharedState {}
 
 pub struct ExtraServerState {
-    pub(super) decap_key: kem::DecapKey,
+    pub(super) decap_key: Arc<kem::DecapKey>,
 }
 
 #[derive(Clone, Debug)]
@@ -78,6 +116,61 @@ pub struct ExtraRunningState {
 
     /// Key used to (un)seal messages to and from PHC
     pub(super) phc_sealing_secret: crypto::SealingKey,
+
+    /// `None` when we are the transcryptor, and otherwise our index in
+    /// [`constellation::Inner::co_transcryptors`].
+    pub(super) co_transcryptor_index: Option<usize>,
+
+    /// Encapsulation key of the co-transcryptor following us, if any, used to seal
+    /// [`api::sso::PartialEhpp`]s for it.
+    pub(super) next_encap_key: Option<Arc<kem::EncapKey>>,
+
+    /// Used to open the [`api::sso::PartialEhpp`]s sealed for us by the preceding transcryptor.
+    pub(super) decap_key: Arc<kem::DecapKey>,
+
+    /// The point we were asked to scale via [`ScaleMasterEncKeyEP`] on this constellation, and
+    /// the result.  We scale no other point until the constellation changes.
+    scaled_master_enc_key: Arc<std::sync::OnceLock<(elgamal::PublicKey, ScaledMasterEncKey)>>,
+}
+
+impl ExtraRunningState {
+    /// Seals `partial` for the next co-transcryptor, or, when there is none, turns it into the
+    /// [`api::sso::EncryptedHubPseudonymPackage`] for PHC.
+    fn pass_on(&self, partial: api::sso::PartialEhpp) -> api::Result<EhppResp> {
+        if let Some(next_encap_key) = self.next_encap_key.as_ref() {
+            let (ss_encap, ss) = next_encap_key.encap().map_err(|_| {
+                log::error!("failed to encapsulate for the next co-transcryptor");
+                api::ErrorCode::InternalError
+            })?;
+
+            return Ok(EhppResp::Continue(api::sso::SealedPartialEhpp {
+                ss_encap,
+                sealed: api::Sealed::new(&partial, &phcrypto::sealing_secret(&ss))?,
+            }));
+        }
+
+        let api::sso::PartialEhpp {
+            polymorphic_pseudonym: encrypted_hub_pseudonym,
+            previous_polymorphic_pseudonyms: previous_encrypted_hub_pseudonyms,
+            hub: _,
+            hub_nonce,
+            phc_nonce,
+            hub_id_mac,
+            transcryption_proof,
+        } = partial;
+
+        Ok(EhppResp::Success(api::Sealed::new(
+            &api::sso::EncryptedHubPseudonymPackage {
+                encrypted_hub_pseudonym,
+                hub_nonce,
+                phc_nonce,
+                hub_id_mac,
+                previous_encrypted_hub_pseudonyms,
+                transcryption_proof,
+            },
+            &self.phc_sealing_secret,
+        )?))
+    }
 }
 
 pub struct App {
@@ -85,8 +178,21 @@ pub struct App {
     master_enc_key_part: elgamal::PrivateKey,
     master_enc_key_part_inv: curve25519_dalek::Scalar,
     master_enc_key_part_hash: crate::id::Id,
+
+    /// Set while the master encryption key part is being rotated
+    previous_master_enc_key_part: Option<elgamal::PrivateKey>,
+    previous_master_enc_key_part_inv: Option<curve25519_dalek::Scalar>,
+    previous_master_enc_key_part_hash: Option<crate::id::Id>,
+
     pseud_factor_secret: B64UU,
+
+    /// The pseudonymisation factor secrets being changed, by version
+    previous_pseud_factor_secrets: BTreeMap<u32, B64UU>,
+
     encap_key: kem::EncapKeyBytes,
+
+    /// Used to refuse suspended hubs and revoked tickets
+    revocations: RevocationsCache,
 }
 
 impl Deref for App {
@@ -100,6 +206,8 @@ impl Deref for App {
 impl crate::servers::App<Server> for App {
     fn configure_actix_app(self: &Rc<Self>, sc: &mut web::ServiceConfig) {
         EhppEP::add_to(self, sc, App::handle_ehpp);
+        ContinueEhppEP::add_to(self, sc, App::handle_continue_ehpp);
+        ScaleMasterEncKeyEP::add_to(self, sc, App::handle_scale_master_enc_key);
         api::server::HubPingEP::add_to(self, sc, App::handle_hub_ping);
     }
 
@@ -111,19 +219,25 @@ impl crate::servers::App<Server> for App {
                 constellation::Inner {
                     // These fields we must check:
                     transcryptor_verifying_key,
+                    transcryptor_next_verifying_key,
                     transcryptor_master_enc_key_part_hash,
+                    transcryptor_previous_master_enc_key_part_hash,
                     transcryptor_encap_key_id,
+                    co_transcryptors,
 
                     // These fields we don't care about:
                     transcryptor_url: _,
                     transcryptor_ss_encap: _,
                     auths_verifying_key: _,
+                    auths_next_verifying_key: _,
                     auths_url: _,
                     auths_encap_key_id: _,
                     auths_ss_encap: _,
                     phc_jwt_key: _,
                     phc_verifying_key: _,
+                    phc_next_verifying_key: _,
                     phc_master_enc_key_part_hash: _,
+                    phc_previous_master_enc_key_part_hash: _,
                     phc_url: _,
                     global_client_url: _,
                     ph_version: _, // (already checked)
@@ -135,17 +249,34 @@ impl crate::servers::App<Server> for App {
         // PHC must have encapsulated against our current encapsulation key; otherwise reject so that
         // discovery re-runs and PHC (re)publishes a matching ciphertext.
         if *transcryptor_encap_key_id != self.encap_key.id() {
-            return false;
+            // We might be one of the co-transcryptors, though.
+            let Some(ct) = co_transcryptors
+                .iter()
+                .find(|ct| ct.encap_key == self.encap_key)
+            else {
+                return false;
+            };
+
+            return ct.verifying_key == self.shared.verifying_key_bytes
+                && ct.next_verifying_key.as_ref() == self.shared.next_verifying_key()
+                && ct.master_enc_key_part_hash == self.master_enc_key_part_hash;
         }
 
         transcryptor_verifying_key == &self.shared.verifying_key_bytes
+            && transcryptor_next_verifying_key.as_ref() == self.shared.next_verifying_key()
             && *transcryptor_master_enc_key_part_hash == self.master_enc_key_part_hash
+            && *transcryptor_previous_master_enc_key_part_hash
+                == self.previous_master_enc_key_part_hash
     }
 
     fn master_enc_key_part(&self) -> Option<&elgamal::PrivateKey> {
         Some(&self.master_enc_key_part)
     }
 
+    fn previous_master_enc_key_part(&self) -> Option<&elgamal::PrivateKey> {
+        self.previous_master_enc_key_part.as_ref()
+    }
+
     fn encap_key(&self) -> Option<&kem::EncapKeyBytes> {
         Some(&self.encap_key)
     }
@@ -154,6 +285,14 @@ impl crate::servers::App<Server> for App {
         self.running_state.as_ref().map(|rs| &rs.phc_sealing_secret)
     }
 
+    fn own_url<'a>(&self, constellation: &'a Constellation) -> &'a url::Url {
+        constellation
+            .co_transcryptors
+            .iter()
+            .find(|ct| ct.encap_key == self.encap_key)
+            .map_or(&constellation.transcryptor_url, |ct| &ct.url)
+    }
+
     async fn discover(
         self: &Rc<Self>,
         phc_inf: api::DiscoveryInfoResp,
@@ -168,6 +307,15 @@ impl App {
         app: Rc<Self>,
         signed_req: web::Json<api::phc::hub::TicketSigned<api::server::PingReq>>,
     ) -> api::Result<api::server::PingResp> {
+        if app
+            .revocations
+            .get(&app)
+            .await?
+            .is_revoked(&signed_req.ticket)
+        {
+            return Ok(api::server::PingResp::RetryWithNewTicket);
+        }
+
         crate::servers::AppBase::<Server>::handle_hub_ping(app, signed_req).await
     }
 
@@ -175,6 +323,11 @@ impl App {
     async fn handle_ehpp(app: Rc<Self>, req: web::Json<EhppReq>) -> api::Result<EhppResp> {
         let running_state = app.running_state_or_please_retry()?;
 
+        if running_state.co_transcryptor_index.is_some() {
+            log::debug!("co-transcryptor received a request meant for the transcryptor");
+            return Err(api::ErrorCode::BadRequest);
+        }
+
         let EhppReq {
             hub_nonce,
             hub,
@@ -190,33 +343,285 @@ impl App {
             return Ok(EhppResp::RetryWithNewPpp);
         };
 
-        let encrypted_hub_pseudonym: elgamal::Triple = phcrypto::t_encrypted_hub_pseudonym(
+        // PHC can not check this itself, because it must not learn what hub the user visits.
+        if app.is_suspended(&hub).await? {
+            return Ok(EhppResp::HubSuspended);
+        }
+
+        let master_enc_key_part_inv =
+            app.master_enc_key_part_inv_for(&polymorphic_pseudonym, &running_state.constellation);
+
+        // While the pseudonymisation factor secret is being changed, the hub is also given the
+        // user's previous hub pseudonyms, so it can move the user's account over.
+        let previous_polymorphic_pseudonyms = app
+            .previous_pseud_factor_secrets
+            .iter()
+            .rev()
+            .map(|(&version, secret)| {
+                let (pseudonym, proof) = phcrypto::t_encrypted_hub_pseudonym(
+                    polymorphic_pseudonym.clone(),
+                    &****secret,
+                    master_enc_key_part_inv,
+                    hub,
+                );
+                api::sso::PreviousPseudonym {
+                    version,
+                    pseudonym,
+                    transcryption_proof: api::sso::TranscryptionProof::new(proof),
+                }
+            })
+            .collect();
+
+        let (polymorphic_pseudonym, proof) = phcrypto::t_encrypted_hub_pseudonym(
             polymorphic_pseudonym,
             &***app.pseud_factor_secret,
+            master_enc_key_part_inv,
+            hub,
+        );
+
+        running_state.pass_on(api::sso::PartialEhpp {
+            polymorphic_pseudonym,
+            previous_polymorphic_pseudonyms,
+            hub,
+            hub_nonce,
+            phc_nonce,
+            hub_id_mac: hub_mac_key.map(|key| key.mac(&hub)),
+            transcryption_proof: api::sso::TranscryptionProof::new(proof),
+        })
+    }
+
+    /// Applies our part of the pseudonymisation to a [`api::sso::PartialEhpp`] handed to us by
+    /// the preceding transcryptor.
+    async fn handle_continue_ehpp(
+        app: Rc<Self>,
+        req: web::Json<ContinueEhppReq>,
+    ) -> api::Result<EhppResp> {
+        let running_state = app.running_state_or_please_retry()?;
+
+        if running_state.co_transcryptor_index.is_none() {
+            log::debug!("transcryptor received a request meant for a co-transcryptor");
+            return Err(api::ErrorCode::BadRequest);
+        }
+
+        let ContinueEhppReq {
+            partial_ehpp: api::sso::SealedPartialEhpp { ss_encap, sealed },
+        } = req.into_inner();
+
+        let Ok(api::sso::PartialEhpp {
+            polymorphic_pseudonym,
+            previous_polymorphic_pseudonyms,
+            hub,
+            hub_nonce,
+            phc_nonce,
+            hub_id_mac,
+            mut transcryption_proof,
+        }) = running_state
+            .decap_key
+            .decap(&ss_encap)
+            .and_then(|ss| sealed.open(&phcrypto::sealing_secret(&ss)))
+        else {
+            // probably sealed for a previous constellation
+            return Ok(EhppResp::RetryWithNewPpp);
+        };
+
+        if app.is_suspended(&hub).await? {
+            return Ok(EhppResp::HubSuspended);
+        }
+
+        // One previous hub pseudonym must be passed along for every version in which we or one
+        // of the preceding transcryptors changed its pseudonymisation factor secret.
+        let versions: BTreeSet<u32> = previous_polymorphic_pseudonyms
+            .iter()
+            .map(|previous| previous.version)
+            .chain(app.previous_pseud_factor_secrets.keys().copied())
+            .collect();
+
+        let previous_polymorphic_pseudonyms = versions
+            .into_iter()
+            .rev()
+            .map(|version| {
+                // Before `version`, the preceding transcryptors used the secrets they used before
+                // the first version from `version` on in which one of them changed its secret.
+                let preceding = previous_polymorphic_pseudonyms
+                    .iter()
+                    .filter(|previous| previous.version >= version)
+                    .min_by_key(|previous| previous.version);
+
+                let input = preceding
+                    .map_or(&polymorphic_pseudonym, |previous| &previous.pseudonym)
+                    .clone();
+
+                let (pseudonym, proof) = phcrypto::t_encrypted_hub_pseudonym(
+                    input.clone(),
+                    &****app.pseud_factor_secret_before(version),
+                    &app.master_enc_key_part_inv,
+                    hub,
+                );
+
+                // The previous hub pseudonym either forks off here, or was already underway.
+                let transcryption_proof = match preceding {
+                    Some(previous) => {
+                        let mut tp = previous.transcryption_proof.clone();
+                        tp.push(input, proof);
+                        tp
+                    }
+                    None => api::sso::TranscryptionProof::new(proof),
+                };
+
+                api::sso::PreviousPseudonym {
+                    version,
+                    pseudonym,
+                    transcryption_proof,
+                }
+            })
+            .collect();
+
+        let (next_polymorphic_pseudonym, proof) = phcrypto::t_encrypted_hub_pseudonym(
+            polymorphic_pseudonym.clone(),
+            &***app.pseud_factor_secret,
             &app.master_enc_key_part_inv,
             hub,
         );
 
-        let hub_id_mac = hub_mac_key.map(|key| key.mac(&hub));
+        transcryption_proof.push(polymorphic_pseudonym, proof);
 
-        Ok(EhppResp::Success(api::Sealed::new(
-            &api::sso::EncryptedHubPseudonymPackage {
-                encrypted_hub_pseudonym,
-                hub_nonce,
-                phc_nonce,
-                hub_id_mac,
-            },
+        running_state.pass_on(api::sso::PartialEhpp {
+            polymorphic_pseudonym: next_polymorphic_pseudonym,
+            previous_polymorphic_pseudonyms,
+            hub,
+            hub_nonce,
+            phc_nonce,
+            hub_id_mac,
+            transcryption_proof,
+        })
+    }
+
+    /// Multiplies the product of the master encryption key parts of the preceding transcryptors
+    /// with our part, but only the one product that belongs to our constellation.
+    async fn handle_scale_master_enc_key(
+        app: Rc<Self>,
+        req: web::Json<ScaleMasterEncKeyReq>,
+    ) -> api::Result<ScaleMasterEncKeyResp> {
+        let running_state = app.running_state_or_please_retry()?;
+
+        let Some(index) = running_state.co_transcryptor_index else {
+            log::debug!("transcryptor received a request meant for a co-transcryptor");
+            return Err(api::ErrorCode::BadRequest);
+        };
+
+        let Ok(to_scale) = req.into_inner().key.open(&running_state.phc_sealing_secret) else {
+            return Ok(ScaleMasterEncKeyResp::RetryWithNewConstellation);
+        };
+
+        // The chain must consist of the transcryptor's part and one product per preceding
+        // co-transcryptor.
+        let Some(key) = (to_scale.chain.len() == index + 1)
+            .then(|| {
+                to_scale.check(
+                    &running_state
+                        .constellation
+                        .transcryptor_master_enc_key_part_hash,
+                )
+            })
+            .flatten()
+        else {
+            log::warn!("refusing to scale a master encryption key that was not properly chained");
+            return Err(api::ErrorCode::BadRequest);
+        };
+
+        let (input, scaled) = running_state.scaled_master_enc_key.get_or_init(|| {
+            let (scaled_key, proof) = app.master_enc_key_part.scale_proven(key);
+            (
+                key.clone(),
+                ScaledMasterEncKey {
+                    key: scaled_key,
+                    proof,
+                },
+            )
+        });
+
+        if input != key {
+            log::warn!(
+                "refusing to scale a master encryption key other than the one scaled before on \
+                 this constellation"
+            );
+            return Err(api::ErrorCode::BadRequest);
+        }
+
+        Ok(ScaleMasterEncKeyResp::Success(api::Sealed::new(
+            scaled,
             &running_state.phc_sealing_secret,
         )?))
     }
 }
 
+impl App {
+    /// Whether `hub` is suspended, in which case we must not hand out pseudonyms for it.
+    async fn is_suspended(&self, hub: &crate::id::Id) -> api::Result<bool> {
+        let suspended = self
+            .revocations
+            .get(self)
+            .await?
+            .suspended_hubs
+            .contains(hub);
+
+        if suspended {
+            log::debug!("refusing pseudonym for suspended hub {hub}");
+        }
+
+        Ok(suspended)
+    }
+
+    /// The pseudonymisation factor secret we used before `version`: the one we replaced in our
+    /// first change from `version` on, or our current one if we have not changed it since.
+    fn pseud_factor_secret_before(&self, version: u32) -> &B64UU {
+        self.previous_pseud_factor_secrets
+            .range(version..)
+            .next()
+            .map_or(&self.pseud_factor_secret, |(_, secret)| secret)
+    }
+
+    /// Returns the inverse of the master encryption key part that must be removed from the
+    /// given polymorphic pseudonym `pp`.
+    ///
+    /// While our master encryption key part is being rotated, PHC might still hand out polymorphic
+    /// pseudonyms it has not yet re-keyed to the current master encryption key.  We recognize
+    /// these by checking which of our parts turns `pp`'s public key into one of PHC's parts.
+    fn master_enc_key_part_inv_for(
+        &self,
+        pp: &elgamal::Triple,
+        constellation: &Constellation,
+    ) -> &curve25519_dalek::Scalar {
+        let Some(previous_inv) = self.previous_master_enc_key_part_inv.as_ref() else {
+            return &self.master_enc_key_part_inv;
+        };
+
+        let pk = pp.pk();
+
+        let is_phc_part = |inv: &curve25519_dalek::Scalar| -> bool {
+            let hash =
+                phcrypto::master_enc_key_part_hash(&elgamal::PrivateKey::from(*inv).scale(&pk));
+            hash == constellation.phc_master_enc_key_part_hash
+                || Some(hash) == constellation.phc_previous_master_enc_key_part_hash
+        };
+
+        if !is_phc_part(&self.master_enc_key_part_inv) && is_phc_part(previous_inv) {
+            log::debug!("received polymorphic pseudonym for the previous master encryption key");
+            return previous_inv;
+        }
+
+        &self.master_enc_key_part_inv
+    }
+}
+
 #[derive(Clone)]
 pub struct AppCreator {
     base: AppCreatorBase<Server>,
     master_enc_key_part: elgamal::PrivateKey,
     master_enc_key_part_inv: curve25519_dalek::Scalar,
+    previous_master_enc_key_part: Option<elgamal::PrivateKey>,
     pseud_factor_secret: B64UU,
+    previous_pseud_factor_secrets: BTreeMap<u32, B64UU>,
     encap_key: kem::EncapKeyBytes,
 }
 
@@ -258,11 +663,25 @@ impl crate::servers::AppCreator<Server> for AppCreator {
             .and_then(|dk| dk.encap_key().encode())
             .map_err(|_| anyhow::anyhow!("deriving kem encapsulation key"))?;
 
+        let mut previous_pseud_factor_secrets = BTreeMap::new();
+
+        for previous in xconf.previous_pseud_factor_secrets.iter() {
+            anyhow::ensure!(
+                previous_pseud_factor_secrets
+                    .insert(previous.version, previous.secret.clone())
+                    .is_none(),
+                "version {} appears more than once in `previous_pseud_factor_secrets`",
+                previous.version
+            );
+        }
+
         Ok(Self {
             base: AppCreatorBase::<Server>::new(config)?,
             master_enc_key_part_inv: master_enc_key_part.as_scalar().invert(),
             master_enc_key_part,
+            previous_master_enc_key_part: xconf.previous_master_enc_key_part.clone(),
             pseud_factor_secret,
+            previous_pseud_factor_secrets,
             encap_key,
         })
     }
@@ -280,8 +699,19 @@ impl crate::servers::AppCreator<Server> for AppCreator {
             ),
             master_enc_key_part: self.master_enc_key_part,
             master_enc_key_part_inv: self.master_enc_key_part_inv,
+            previous_master_enc_key_part_inv: self
+                .previous_master_enc_key_part
+                .as_ref()
+                .map(|part| part.as_scalar().invert()),
+            previous_master_enc_key_part_hash: self
+                .previous_master_enc_key_part
+                .as_ref()
+                .map(|part| phcrypto::master_enc_key_part_hash(part.public_key())),
+            previous_master_enc_key_part: self.previous_master_enc_key_part,
             pseud_factor_secret: self.pseud_factor_secret,
+            previous_pseud_factor_secrets: self.previous_pseud_factor_secrets,
             encap_key: self.encap_key,
+            revocations: Default::default(),
         }
     }
 }
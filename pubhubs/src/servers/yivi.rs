@@ -46,6 +46,12 @@ pub struct SessionRequest {
     /// <https://pkg.go.dev/github.com/privacybydesign/irmago#IssuanceRequest>
     credentials: Option<Vec<CredentialToBeIssued>>,
 
+    /// The message to be signed in a signature session, see
+    /// <https://pkg.go.dev/github.com/privacybydesign/irmago#SignatureRequest>
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+
     /// <https://docs.yivi.app/session-requests/#skip-expiry-check>
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
@@ -60,6 +66,22 @@ impl ExtendedSessionRequest {
                 context: LdContext::Disclosure,
                 disclose: Some(cdc),
                 credentials: None,
+                message: None,
+                skip_expiry_check: vec![],
+            },
+            next_session: None,
+        }
+    }
+
+    /// Creates a request to sign `message` using the attributes specified by `cdc`, see
+    /// <https://docs.yivi.app/what-is-yivi#attribute-based-signatures>.
+    pub fn signature(message: String, cdc: AttributeConDisCon) -> Self {
+        Self {
+            request: SessionRequest {
+                context: LdContext::Signature,
+                disclose: Some(cdc),
+                credentials: None,
+                message: Some(message),
                 skip_expiry_check: vec![],
             },
             next_session: None,
@@ -72,6 +94,7 @@ impl ExtendedSessionRequest {
                 context: LdContext::Issuance,
                 disclose: None,
                 credentials: Some(credentials),
+                message: None,
                 skip_expiry_check: vec![],
             },
             next_session: None,
@@ -163,31 +186,124 @@ impl ExtendedSessionRequest {
         &self,
         df: impl Fn(&AttributeTypeIdentifier) -> String,
     ) -> SessionResult {
-        assert_eq!(self.request.context, LdContext::Disclosure);
-
-        let disclosed: Vec<Vec<DisclosedAttribute>> = self
+        for dc in self
             .request
             .disclose
             .as_ref()
             .expect("missing `disclose` field in disclosure session request")
-            .iter()
-            .map(|dc: &Vec<Vec<AttributeRequest>>| {
-                assert_eq!(dc.len(), 1, "'discon's not supported by this mock function");
+        {
+            assert_eq!(dc.len(), 1, "'discon's not supported by this mock function");
+        }
 
-                let con_req: &Vec<AttributeRequest> = &dc[0];
+        self.mock_disclosure_response_with(|ati| Some(df(ati)))
+    }
 
-                let con_resp: Vec<DisclosedAttribute> = con_req
-                    .iter()
-                    .map(|ar: &AttributeRequest| {
-                        DisclosedAttribute::mock(df(&ar.ty), ar.ty.clone())
-                    })
-                    .collect();
+    /// Like [`Self::mock_disclosure_response`], but `df` may decline to disclose an attribute
+    /// by returning `None`.  For each 'discon' the first inner conjunction of which all
+    /// attributes are disclosed by `df` is picked.  An empty inner conjunction, used to make a
+    /// disclosure optional, is always picked when reached.
+    ///
+    /// # Panics
+    ///  - If `self` is not a disclosure request, or is missing the `disclosure` field.
+    ///  - If none of the inner conjunctions of a 'discon' can be satisfied.
+    pub fn mock_disclosure_response_with(
+        &self,
+        df: impl Fn(&AttributeTypeIdentifier) -> Option<String>,
+    ) -> SessionResult {
+        assert_eq!(self.request.context, LdContext::Disclosure);
+
+        self.mock_response(df).unwrap()
+    }
+
+    /// Mocks a valid [`SessionResult`] to this signature request, signing the requested message
+    /// with the attributes picked as by [`Self::mock_disclosure_response_with`].
+    ///
+    /// Note that the mocked [`SignedMessage`] contains no actual proofs.
+    ///
+    /// # Panics
+    ///  - If `self` is not a signature request.
+    ///  - If none of the inner conjunctions of a 'discon' can be satisfied.
+    pub fn mock_signature_response(
+        &self,
+        df: impl Fn(&AttributeTypeIdentifier) -> Option<String>,
+    ) -> SessionResult {
+        assert_eq!(self.request.context, LdContext::Signature);
+
+        self.mock_response(df).unwrap()
+    }
+
+    /// Mocks a valid [`SessionResult`] to this [`ExtendedSessionRequest`], whatever its type:
+    /// attributes are disclosed as by [`Self::mock_disclosure_response_with`], messages are
+    /// signed as by [`Self::mock_signature_response`], and credentials are assumed to be issued.
+    ///
+    /// Unlike the other mock functions, returns an error when the request can not be satisfied.
+    pub fn mock_response(
+        &self,
+        df: impl Fn(&AttributeTypeIdentifier) -> Option<String>,
+    ) -> anyhow::Result<SessionResult> {
+        let disclosed = self.mock_disclosed(df)?;
+
+        Ok(match self.request.context {
+            LdContext::Disclosure => SessionResult::mock_disclosure(disclosed),
+            LdContext::Signature => SessionResult::mock_signature(
+                disclosed,
+                SignedMessage {
+                    message: self
+                        .request
+                        .message
+                        .clone()
+                        .context("missing `message` field in signature session request")?,
+                    rest: Default::default(),
+                },
+            ),
+            LdContext::Issuance => SessionResult {
+                session_type: SessionType::Issuing,
+                ..SessionResult::mock_disclosure(disclosed)
+            },
+        })
+    }
 
-                con_resp
+    /// Picks the attributes disclosed for [`Self::mock_response`].
+    fn mock_disclosed(
+        &self,
+        df: impl Fn(&AttributeTypeIdentifier) -> Option<String>,
+    ) -> anyhow::Result<Vec<Vec<DisclosedAttribute>>> {
+        let Some(cdc) = self.request.disclose.as_ref() else {
+            anyhow::ensure!(
+                self.request.context == LdContext::Issuance,
+                "missing `disclose` field in session request"
+            );
+            return Ok(vec![]);
+        };
+
+        cdc.iter()
+            .enumerate()
+            .map(|(i, dc): (usize, &Vec<Vec<AttributeRequest>>)| {
+                dc.iter()
+                    .find_map(|con_req: &Vec<AttributeRequest>| {
+                        con_req
+                            .iter()
+                            .map(|ar: &AttributeRequest| {
+                                Some(DisclosedAttribute::mock(df(&ar.ty)?, ar.ty.clone()))
+                            })
+                            .collect::<Option<Vec<DisclosedAttribute>>>()
+                    })
+                    .with_context(|| {
+                        format!(
+                            "none of the inner conjunctions of 'discon' number {i} can be satisfied"
+                        )
+                    })
             })
-            .collect();
+            .collect()
+    }
 
-        SessionResult::mock_disclosure(disclosed)
+    /// The type of this session
+    pub fn session_type(&self) -> SessionType {
+        match self.request.context {
+            LdContext::Disclosure => SessionType::Disclosing,
+            LdContext::Signature => SessionType::Signing,
+            LdContext::Issuance => SessionType::Issuing,
+        }
     }
 }
 
@@ -260,6 +376,13 @@ pub struct CredentialToBeIssued {
     type_id: CredentialTypeIdentifier,
 
     attributes: std::collections::HashMap<String, String>,
+
+    /// Key by which the issued credential can be revoked later on, see [`RevocationRequest`].
+    /// All credentials issued with the same key are revoked together.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "revocationKey")]
+    revocation_key: Option<String>,
 }
 
 impl CredentialToBeIssued {
@@ -268,6 +391,16 @@ impl CredentialToBeIssued {
             validity: None,
             type_id,
             attributes: Default::default(),
+            revocation_key: None,
+        }
+    }
+
+    /// Makes the credential revocable using the given key.  The credential type must support
+    /// revocation.
+    pub fn revocation_key(self, key: String) -> Self {
+        Self {
+            revocation_key: Some(key),
+            ..self
         }
     }
 
@@ -284,6 +417,76 @@ impl CredentialToBeIssued {
     }
 }
 
+/// Request to revoke the credentials of the given type that were issued with the given
+/// revocation key (see [`CredentialToBeIssued::revocation_key`]), to be posted to the yivi
+/// server's `/revocation` endpoint.
+///
+/// Known as
+/// [`RevocationRequest`](https://pkg.go.dev/github.com/privacybydesign/irmago#RevocationRequest) in irmago.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RevocationRequest {
+    #[serde(rename = "@context")]
+    context: RevocationLdContext,
+
+    #[serde(rename = "type")]
+    pub credential: CredentialTypeIdentifier,
+
+    #[serde(rename = "revocationKey")]
+    pub revocation_key: String,
+}
+
+/// The only [`RevocationRequest::context`]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+enum RevocationLdContext {
+    #[serde(rename = "https://irma.app/ld/request/revocation/v1")]
+    V1,
+}
+
+impl RevocationRequest {
+    /// The `sub` field of a signed revocation request JWT
+    const JWT_SUB: &'static str = "revocation_request";
+
+    /// The key that holds the revocation request inside a signed revocation request JWT
+    const JWT_KEY: &'static str = "revreq";
+
+    pub fn new(credential: CredentialTypeIdentifier, revocation_key: String) -> Self {
+        Self {
+            context: RevocationLdContext::V1,
+            credential,
+            revocation_key,
+        }
+    }
+
+    /// Signs this revocation request using the provided requestor credentials.
+    pub fn sign(self, creds: &Credentials<SigningKey>) -> anyhow::Result<jwt::JWT> {
+        creds
+            .key
+            .sign(
+                &jwt::Claims::new()
+                    .iat_now()?
+                    .claim("iss", &creds.name)?
+                    .claim("sub", Self::JWT_SUB)?
+                    .claim(Self::JWT_KEY, self)?,
+            )
+            .context("signing revocation request")
+    }
+
+    /// Opens the given signed [`RevocationRequest`].
+    pub fn open_signed(
+        jwt: &jwt::JWT,
+        requestor_credentials: &Credentials<VerifyingKey>,
+    ) -> anyhow::Result<Self> {
+        requestor_credentials
+            .key
+            .open(jwt)
+            .context("invalid jwt")?
+            .check_iss(jwt::expecting::exactly(&requestor_credentials.name))?
+            .check_sub(jwt::expecting::exactly(Self::JWT_SUB))?
+            .extract(Self::JWT_KEY)?
+            .with_context(|| format!("missing claim {}", Self::JWT_KEY))
+    }
+}
+
 /// Credentials (name and key) for a requestor or yivi server.
 ///
 /// To be used with `K` either [`SigningKey`] or [`VerifyingKey`].
@@ -387,9 +590,10 @@ mod rs256vk_encoding {
     where
         D: serde::Deserializer<'de>,
     {
-        let s: &'de str = <&'de str>::deserialize(d)?;
+        // not `&str`, so that the key can also be read from a `serde_json::Value`
+        let s: std::borrow::Cow<'de, str> = <std::borrow::Cow<'de, str>>::deserialize(d)?;
 
-        jwt::RS256Vk::from_public_key_pem(s).map_err(D::Error::custom)
+        jwt::RS256Vk::from_public_key_pem(&s).map_err(D::Error::custom)
     }
 
     pub fn serialize<S>(pk: &jwt::RS256Vk, s: S) -> Result<S::Ok, S::Error>
@@ -426,7 +630,9 @@ pub struct SessionResult {
 
     pub disclosed: Option<Vec<Vec<DisclosedAttribute>>>,
 
-    // "signature" field: not used by us (yet)
+    /// Set for signature sessions
+    pub signature: Option<SignedMessage>,
+
     pub error: Option<RemoteError>,
 
     #[serde(rename = "nextSession")]
@@ -442,10 +648,23 @@ impl SessionResult {
             session_type: SessionType::Disclosing,
             proof_status: Some(ProofStatus::Valid),
             disclosed: Some(disclosed),
+            signature: None,
             error: None,
             next_session: None,
         }
     }
+
+    /// Creates a mock session result of a signature session
+    fn mock_signature(
+        disclosed: Vec<Vec<DisclosedAttribute>>,
+        signature: SignedMessage,
+    ) -> SessionResult {
+        SessionResult {
+            session_type: SessionType::Signing,
+            signature: Some(signature),
+            ..Self::mock_disclosure(disclosed)
+        }
+    }
 }
 
 impl SessionResult {
@@ -473,13 +692,45 @@ impl SessionResult {
     pub fn open_signed(
         jwt: &jwt::JWT,
         server_credentials: &Credentials<VerifyingKey>,
+    ) -> anyhow::Result<Self> {
+        Self::open_signed_inner(jwt, server_credentials, false)
+    }
+
+    /// Like [`Self::open_signed`], but only accepts the result of a signature session, whose
+    /// expiry is not checked.
+    ///
+    /// The signature in such a session result (see
+    /// [`Self::validate_signature_and_extract_raw_singles`]) is a statement by the end-user that
+    /// remains meaningful long after the session, so it can be checked by hubs much later.
+    pub fn open_signed_signature(
+        jwt: &jwt::JWT,
+        server_credentials: &Credentials<VerifyingKey>,
+    ) -> anyhow::Result<Self> {
+        let session_result = Self::open_signed_inner(jwt, server_credentials, true)?;
+
+        anyhow::ensure!(
+            session_result.session_type == SessionType::Signing,
+            "expected the result of a signature session, not of a {} session",
+            session_result.session_type
+        );
+
+        Ok(session_result)
+    }
+
+    fn open_signed_inner(
+        jwt: &jwt::JWT,
+        server_credentials: &Credentials<VerifyingKey>,
+        ignore_exp: bool,
     ) -> anyhow::Result<Self> {
         let mut session_type_perhaps: Option<SessionType> = None;
 
-        let session_result: Self = server_credentials
-            .key
-            .open(jwt)
-            .context("invalid jwt")?
+        let mut claims = server_credentials.key.open(jwt).context("invalid jwt")?;
+
+        if ignore_exp {
+            claims = claims.ignore("exp");
+        }
+
+        let session_result: Self = claims
             .check_iss(jwt::expecting::exactly(&server_credentials.name))?
             .check_sub(
                 |claim_name: &'static str, sub: Option<String>| -> Result<(), jwt::Error> {
@@ -544,10 +795,15 @@ impl SessionResult {
 
     /// Verifyies that this [`SessionResult`] is valid, and for the first attribute in each inner conjunction
     /// returns the [`AttributeTypeIdentifier`] and raw value.
+    ///
+    /// Yields `None` for an empty inner conjunction, which is what the Yivi app returns when the
+    /// user chose not to disclose an optional attribute.
+    #[allow(clippy::type_complexity)]
     pub fn validate_and_extract_raw_singles(
         &self,
-    ) -> anyhow::Result<impl Iterator<Item = anyhow::Result<(&AttributeTypeIdentifier, &str)>>>
-    {
+    ) -> anyhow::Result<
+        impl Iterator<Item = anyhow::Result<Option<(&AttributeTypeIdentifier, &str)>>>,
+    > {
         self.validate_except_disclosed()?;
 
         Ok(self
@@ -555,15 +811,63 @@ impl SessionResult {
             .iter()
             .flatten()
             .map(|inner_con: &Vec<DisclosedAttribute>| {
-                let da: &DisclosedAttribute = inner_con
-                    .first()
-                    .context("inner conjunction has no attribute")?;
+                let Some(da) = inner_con.first() else {
+                    return Ok(None);
+                };
 
                 da.validate()?;
 
-                Ok((&da.id, da.raw_value.as_str()))
+                Ok(Some((&da.id, da.raw_value.as_str())))
             }))
     }
+
+    /// Verifies that this [`SessionResult`] is a valid result of a signature session over
+    /// `message`, and returns the attributes used to sign like
+    /// [`Self::validate_and_extract_raw_singles`].
+    ///
+    /// The proofs in the [`SignedMessage`] are not checked:  we rely on the signature of the
+    /// yivi server on this session result, see [`Self::open_signed`].
+    #[allow(clippy::type_complexity)]
+    pub fn validate_signature_and_extract_raw_singles(
+        &self,
+        message: &str,
+    ) -> anyhow::Result<
+        impl Iterator<Item = anyhow::Result<Option<(&AttributeTypeIdentifier, &str)>>>,
+    > {
+        anyhow::ensure!(
+            self.session_type == SessionType::Signing,
+            "session type is not 'signing', but {}",
+            self.session_type
+        );
+
+        let signature = self
+            .signature
+            .as_ref()
+            .context("signature session result without signature")?;
+
+        anyhow::ensure!(
+            signature.message == message,
+            "a different message was signed"
+        );
+
+        self.validate_and_extract_raw_singles()
+    }
+}
+
+/// Message signed with yivi attributes in a signature session.
+///
+/// We do not check the proofs it contains ourselves, but rely on the yivi server having done so,
+/// which is why they are kept opaque.
+///
+/// <https://pkg.go.dev/github.com/privacybydesign/irmago#SignedMessage>
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SignedMessage {
+    /// The message that was signed
+    pub message: String,
+
+    /// The other fields, such as `signature`, `indices`, `nonce` and `timestamp`
+    #[serde(flatten)]
+    pub rest: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Disclosure of a single attribute
@@ -1080,7 +1384,7 @@ mod test {
         let mut results: Vec<(&AttributeTypeIdentifier, &str)> = sr
             .validate_and_extract_raw_singles()
             .unwrap()
-            .map(|r| r.unwrap())
+            .map(|r| r.unwrap().unwrap())
             .collect();
 
         // Sort by second argument, just to get predictable output
@@ -1104,6 +1408,58 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_signature_session_result_validation() {
+        let json = serde_json::json!({
+          "disclosed": [
+            [
+              {
+                "id": "irma-demo.sidn-pbdf.email.email",
+                "issuancetime": 1735776000,
+                "rawvalue": "test@test.com",
+                "status": "PRESENT",
+              }
+            ]
+          ],
+          "signature": {
+            "@context": "https://irma.app/ld/signature/v2",
+            "signature": [ { "c": "...", "A": "..." } ],
+            "indices": [ [ { "cred": 0, "attr": 2 } ] ],
+            "nonce": 42,
+            "context": 1337,
+            "message": "I agree",
+            "timestamp": null
+          },
+          "proofStatus": "VALID",
+          "status": "DONE",
+          "token": "KDRkE7LE0jIPhIBNdoBb",
+          "type": "signing"
+        });
+
+        let sr: SessionResult = serde_json::from_value(json.clone()).unwrap();
+
+        // the proofs survive a round trip, so the result can be passed on
+        assert_eq!(
+            serde_json::to_value(&sr).unwrap()["signature"],
+            json["signature"]
+        );
+
+        assert!(
+            sr.validate_signature_and_extract_raw_singles("I disagree")
+                .is_err()
+        );
+        assert!(sr.validate_and_extract_raw_singles().is_ok());
+
+        let results: Vec<(&AttributeTypeIdentifier, &str)> = sr
+            .validate_signature_and_extract_raw_singles("I agree")
+            .unwrap()
+            .map(|r| r.unwrap().unwrap())
+            .collect();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1, "test@test.com");
+    }
+
     #[test]
     fn epoch_boundary() {
         // The largest accepted sequence number is the one whose epoch contains the last
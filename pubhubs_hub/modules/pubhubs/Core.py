@@ -109,6 +109,7 @@ class Core:
         self._hub_id = None # b64url hub id, set by get_constellation; needed to verify the hub-id mac
         self._constellation_last_update_triggered = 0
         self._phc_verifying_key = None # CompositeKey, set by get_constellation
+        self._phc_next_verifying_key = None # CompositeKey PHC is rotating to, if any; set by get_constellation
         self.trigger_get_constellation()
 
     @staticmethod
@@ -170,8 +171,18 @@ class Core:
             logger.error(f"could not parse phc_verifying_key for composite HHPP verification: {e}")
             return
 
+        # The key PHC is rotating to, if any; HHPPs signed by it are accepted too.
+        phc_next_verifying_key = None
+        nvk = constellation.get('phc_next_verifying_key')
+        if nvk is not None:
+            try:
+                phc_next_verifying_key = parse_verifying_key(nvk['verifying_key'])
+            except Exception as e:
+                logger.error(f"could not parse phc_next_verifying_key for composite HHPP verification: {e}")
+
         self._constellation = constellation
         self._phc_verifying_key = phc_verifying_key
+        self._phc_next_verifying_key = phc_next_verifying_key
         logger.info(f"retrieved constellation with id {self._constellation['id']}")
 
         # Resolve our own hub id (to verify the hub-id mac in enter-complete) from PHC's advertised
@@ -326,7 +337,8 @@ class PhEnterCompleteEP(Resource):
         # check_hhpp does the signature check plus the message-code/constellation/expiry routing and
         # the our/their-fault split; we only map each status to a response, refetching the
         # constellation (rate-limited) on the statuses where we might be the stale side.
-        result = check_hhpp(hhpp, self._core._phc_verifying_key, self._core._constellation)
+        result = check_hhpp(hhpp, self._core._phc_verifying_key, self._core._constellation,
+                            self._core._phc_next_verifying_key)
         match result.status:
             case HhppStatus.OUR_CONSTELLATION_STALE:
                 logger.info("enter-complete: our constellation is stale — older than the one mentioned in the "
@@ -407,6 +419,20 @@ class PhEnterCompleteEP(Resource):
 
         mxid = await self._core._api._store.get_user_by_external_id("pubhubs", hhp)
         new_user = False
+
+        # While the transcryptors' pseudonymisation factor secrets are being changed, the hhpp also
+        # contains the user's previous hashed hub pseudonyms (most recent first): move the user's
+        # account over to the new one.
+        for previous_hhp in claims.get('previous_hashed_hub_pseudonyms', []):
+            if mxid != None:
+                break
+
+            mxid = await self._core._api._store.get_user_by_external_id("pubhubs", previous_hhp)
+
+            if mxid != None:
+                await self._core._api.record_user_external_id("pubhubs", hhp, mxid)
+                await self._core._api._store.remove_user_external_id("pubhubs", previous_hhp, mxid)
+                logger.info(f"moved {mxid} from previous hashed hub pseudonym {previous_hhp} to {hhp}")
         
         if mxid == None:
             new_user = True
@@ -153,11 +153,13 @@ def verify(signing_input, signature, key, mldsa_ctx):
     return True
 
 
-def check_hhpp(hhpp, key, constellation):
+def check_hhpp(hhpp, key, constellation, next_key=None):
     """Open an HHPP without raising: verify its composite signature against `key`, then validate the
     signed claims as a current, unexpired HHPP.  Returns an [`HhppOpenResult`]; see [`HhppStatus`]
     for the possible outcomes.  `key` (a [`CompositeKey`]) and `constellation` come from the hub's
-    last /welcome; either being None means we are not set up yet, treated like a stale constellation."""
+    last /welcome; either being None means we are not set up yet, treated like a stale constellation.
+    `next_key` is the key PHC is rotating to (the constellation's `phc_next_verifying_key`), if any;
+    a signature by it is accepted too."""
     if constellation is None or key is None:
         logger.info("no constellation or verifying key yet; treating as a stale constellation")
         return HhppOpenResult(HhppStatus.OUR_CONSTELLATION_STALE, None)
@@ -189,7 +191,9 @@ def check_hhpp(hhpp, key, constellation):
     if not isinstance(alg, str) or alg not in _ALG_CONTEXTS:
         return HhppOpenResult(HhppStatus.OTHERWISE_INVALID, None)
 
-    if not verify(signing_input, signature, key, _ALG_CONTEXTS[alg]):
+    if not verify(signing_input, signature, key, _ALG_CONTEXTS[alg]) and (
+        next_key is None or not verify(signing_input, signature, next_key, _ALG_CONTEXTS[alg])
+    ):
         # The signature did not verify — usually a key rotation, not a forgery: PHC rotated its key
         # and either we or the client's HHPP is on the wrong side.  Route on the now-untrusted ph-ci,
         # which only ever selects a retry, never anything security-sensitive (mirrors Signed::open in
@@ -206,6 +206,15 @@ class TestCheckHhppSignature(unittest.TestCase):
         self.assertIs(result.status, HhppStatus.VERIFIED)
         self.assertEqual(result.claims, claims)
 
+    def test_next_key_accepted_during_rotation(self):
+        # PHC switched to its next key (_KEY) while our current key is still its previous one
+        current = CompositeKey(ed=Ed25519PrivateKey.generate().public_key(),
+                               ml=MLDSA65PrivateKey.generate().public_key())
+        token = _mint(_hhpp_claims())
+        self.assertIs(check_hhpp(token, current, _CONSTELLATION, _KEY).status, HhppStatus.VERIFIED)
+        self.assertIs(check_hhpp(token, _KEY, _CONSTELLATION, current).status, HhppStatus.VERIFIED)
+        self.assertIs(check_hhpp(token, current, _CONSTELLATION).status, HhppStatus.OTHERWISE_INVALID)
+
     def test_not_ready_without_key_or_constellation(self):
         token = _mint(_hhpp_claims())
         self.assertIs(check_hhpp(token, None, _CONSTELLATION).status, HhppStatus.OUR_CONSTELLATION_STALE)